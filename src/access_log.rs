@@ -0,0 +1,913 @@
+//! Apache and Nginx access logs as a file format with typed columns.
+//!
+//! ```sql
+//! CREATE EXTERNAL TABLE access
+//! STORED AS ACCESS_LOG
+//! LOCATION 'logs/access.log'
+//! OPTIONS ('log_format' 'combined');
+//!
+//! SELECT remote_addr, status, body_bytes_sent FROM access WHERE status >= 500;
+//! ```
+//!
+//! `log_format` is `common` (or `clf`), `combined`, or an Nginx `log_format`
+//! string such as `'$remote_addr [$time_local] "$request" $status'`. Each
+//! variable becomes a column named after it, and its type follows the
+//! variable:
+//!
+//! | variables | type |
+//! | --- | --- |
+//! | `time_local`, `time_iso8601`, `msec` | `Timestamp(Millisecond, "UTC")` |
+//! | `status` | `Int32` |
+//! | `body_bytes_sent`, `bytes_sent`, `request_length`, `connection`, `connection_requests` | `Int64` |
+//! | `request_time` | `Float64` |
+//! | anything else | `Utf8` |
+//!
+//! A `-` field is NULL. Fields between double quotes may contain `\"`, `\\`
+//! and `\xHH` escapes, as written by both servers, which are decoded. A line
+//! that does not fit the format, or has a field that does not convert to its
+//! type, is handled as the `mode` option says; see [`ParseMode`]. Under
+//! `PERMISSIVE` only the fields that do not convert are NULL, so a column
+//! has the same values whichever other columns a query reads. The other
+//! modes check every field, read or not, so they keep the same rows.
+//!
+//! The `mode`, `compression`, `file_extension`, `start_pattern`,
+//! `continuation_pattern` and `max_record_size` options are those of
+//! [`TextOptions`]. `STORED AS ACCESS_LOG` needs [`AccessLogFormatFactory`]
+//! on the session, which [`crate::registry::register_all`] installs.
+
+use std::any::Any;
+use std::borrow::Cow;
+use std::collections::HashMap;
+use std::sync::Arc;
+
+use arrow_array::builder::{
+    Float64Builder, Int32Builder, Int64Builder, StringBuilder, TimestampMillisecondBuilder,
+};
+use arrow_array::{ArrayRef, RecordBatch, RecordBatchOptions};
+use async_trait::async_trait;
+use chrono::DateTime;
+use datafusion::arrow::compute::cast;
+use datafusion::arrow::datatypes::{DataType, Field, SchemaRef, TimeUnit};
+use datafusion::catalog::Session;
+use datafusion::datasource::file_format::{FileFormat, FileFormatFactory};
+use datafusion::datasource::physical_plan::{FileScanConfig, FileSource};
+use datafusion::physical_plan::ExecutionPlan;
+use datafusion_common::{GetExt, Result, Statistics, config_err, plan_err};
+use datafusion_datasource::file_compression_type::FileCompressionType;
+use object_store::{ObjectMeta, ObjectStore};
+
+use crate::text_file::{
+    LineDecoder, LineFormat, ParseMode, RecordStart, TextOptions, TextSource, scan_plan,
+};
+
+const NAME: &str = "access_log";
+const TIMEZONE: &str = "UTC";
+
+/// Apache's Common Log Format, `%h %l %u %t "%r" %>s %b`.
+pub const COMMON: &str = r#"$remote_addr $remote_logname $remote_user [$time_local] "$request" $status $body_bytes_sent"#;
+
+/// Apache's Combined Log Format, which adds the referrer and user agent to
+/// [`COMMON`].
+pub const COMBINED: &str = r#"$remote_addr $remote_logname $remote_user [$time_local] "$request" $status $body_bytes_sent "$http_referer" "$http_user_agent""#;
+
+/// How the text of a field becomes a value.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Kind {
+    Text,
+    Int32,
+    Int64,
+    Float64,
+    TimeLocal,
+    TimeIso8601,
+    Msec,
+}
+
+impl Kind {
+    fn of(variable: &str) -> Self {
+        match variable {
+            "time_local" => Kind::TimeLocal,
+            "time_iso8601" => Kind::TimeIso8601,
+            "msec" => Kind::Msec,
+            "status" => Kind::Int32,
+            "body_bytes_sent"
+            | "bytes_sent"
+            | "request_length"
+            | "connection"
+            | "connection_requests" => Kind::Int64,
+            "request_time" => Kind::Float64,
+            _ => Kind::Text,
+        }
+    }
+
+    fn data_type(&self) -> DataType {
+        match self {
+            Kind::Text => DataType::Utf8,
+            Kind::Int32 => DataType::Int32,
+            Kind::Int64 => DataType::Int64,
+            Kind::Float64 => DataType::Float64,
+            Kind::TimeLocal | Kind::TimeIso8601 | Kind::Msec => {
+                DataType::Timestamp(TimeUnit::Millisecond, Some(TIMEZONE.into()))
+            }
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct LogField {
+    name: String,
+    kind: Kind,
+    /// Whether the field is written between double quotes, and may contain
+    /// escapes.
+    quoted: bool,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Segment {
+    Literal(String),
+    Field(usize),
+}
+
+/// A parsed `log_format`: the text between variables, and the variables.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LogFormat {
+    spec: String,
+    segments: Vec<Segment>,
+    fields: Vec<LogField>,
+}
+
+impl LogFormat {
+    /// Apache's Common Log Format; see [`COMMON`].
+    pub fn common() -> Self {
+        Self::nginx(COMMON).expect("COMMON is a valid log format")
+    }
+
+    /// Apache's Combined Log Format; see [`COMBINED`].
+    pub fn combined() -> Self {
+        Self::nginx(COMBINED).expect("COMBINED is a valid log format")
+    }
+
+    /// A preset by name, `common`, `clf` or `combined`, or else an Nginx
+    /// `log_format` string.
+    pub fn from_option(value: &str) -> Result<Self> {
+        match value.to_ascii_lowercase().as_str() {
+            "common" | "clf" => Ok(Self::common()),
+            "combined" => Ok(Self::combined()),
+            _ => Self::nginx(value),
+        }
+    }
+
+    /// Parses an Nginx `log_format` string, in which `$name` and `${name}`
+    /// are variables and everything else is literal text. Two variables must
+    /// have text between them, so that it is clear where one ends.
+    pub fn nginx(spec: &str) -> Result<Self> {
+        let mut segments = Vec::new();
+        let mut fields: Vec<LogField> = Vec::new();
+        let mut literal = String::new();
+        let mut i = 0;
+
+        while let Some(c) = spec[i..].chars().next() {
+            let rest = &spec[i + c.len_utf8()..];
+            let name = if c != '$' {
+                None
+            } else if let Some(braced) = rest.strip_prefix('{') {
+                let Some(close) = braced.find('}') else {
+                    return config_err!("Unclosed '${{' in log_format '{spec}'");
+                };
+                (close > 0).then(|| (&braced[..close], close + 2))
+            } else {
+                let len = rest
+                    .find(|c: char| !(c.is_ascii_alphanumeric() || c == '_'))
+                    .unwrap_or(rest.len());
+                (len > 0).then(|| (&rest[..len], len))
+            };
+            i += c.len_utf8();
+            let Some((name, consumed)) = name else {
+                literal.push(c);
+                continue;
+            };
+            i += consumed;
+
+            if literal.is_empty() && matches!(segments.last(), Some(Segment::Field(_))) {
+                return config_err!(
+                    "Variable ${name} directly follows another in log_format '{spec}'; \
+                     they must be separated by text"
+                );
+            }
+            if fields.iter().any(|field| field.name == name) {
+                return config_err!("Variable ${name} appears twice in log_format '{spec}'");
+            }
+            if !literal.is_empty() {
+                segments.push(Segment::Literal(std::mem::take(&mut literal)));
+            }
+            segments.push(Segment::Field(fields.len()));
+            fields.push(LogField {
+                name: name.to_string(),
+                kind: Kind::of(name),
+                quoted: false,
+            });
+        }
+        if !literal.is_empty() {
+            segments.push(Segment::Literal(literal));
+        }
+        if fields.is_empty() {
+            return config_err!("log_format '{spec}' has no variables");
+        }
+
+        for (i, segment) in segments.iter().enumerate() {
+            let Segment::Field(field) = segment else {
+                continue;
+            };
+            let opens =
+                i > 0 && matches!(&segments[i - 1], Segment::Literal(text) if text.ends_with('"'));
+            let closes = matches!(segments.get(i + 1), Some(Segment::Literal(text)) if text.starts_with('"'));
+            fields[*field].quoted = opens && closes;
+        }
+
+        Ok(Self {
+            spec: spec.to_string(),
+            segments,
+            fields,
+        })
+    }
+
+    /// The `log_format` string this was parsed from.
+    pub fn spec(&self) -> &str {
+        &self.spec
+    }
+
+    /// One column per variable, in order.
+    pub fn fields(&self) -> Vec<Field> {
+        self.fields
+            .iter()
+            .map(|field| Field::new(&field.name, field.kind.data_type(), true))
+            .collect()
+    }
+
+    /// Finds where each field is in `line`. Returns whether the line fits the
+    /// format.
+    fn split(&self, line: &str, spans: &mut [(usize, usize)]) -> bool {
+        let mut pos = 0;
+        for (i, segment) in self.segments.iter().enumerate() {
+            match segment {
+                Segment::Literal(text) => {
+                    if !line[pos..].starts_with(text.as_str()) {
+                        return false;
+                    }
+                    pos += text.len();
+                }
+                Segment::Field(field) => {
+                    let rest = &line[pos..];
+                    let len = match self.segments.get(i + 1) {
+                        Some(Segment::Literal(next)) if self.fields[*field].quoted => {
+                            find_unescaped(rest, next)
+                        }
+                        Some(Segment::Literal(next)) => rest.find(next.as_str()),
+                        _ => Some(rest.len()),
+                    };
+                    let Some(len) = len else {
+                        return false;
+                    };
+                    spans[*field] = (pos, pos + len);
+                    pos += len;
+                }
+            }
+        }
+        pos == line.len()
+    }
+}
+
+/// The first `needle` in `haystack` that does not follow a backslash escape.
+fn find_unescaped(haystack: &str, needle: &str) -> Option<usize> {
+    let bytes = haystack.as_bytes();
+    let mut i = 0;
+    while i < bytes.len() {
+        if bytes[i] == b'\\' {
+            i += 2;
+            continue;
+        }
+        if bytes[i..].starts_with(needle.as_bytes()) {
+            return Some(i);
+        }
+        i += 1;
+    }
+    None
+}
+
+/// Decodes the `\"`, `\\` and `\xHH` escapes of a quoted field.
+fn unescape(text: &str) -> Cow<'_, str> {
+    if !text.contains('\\') {
+        return Cow::Borrowed(text);
+    }
+    let bytes = text.as_bytes();
+    let mut out = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        match (bytes[i], bytes.get(i + 1)) {
+            (b'\\', Some(&escaped @ (b'"' | b'\\'))) => {
+                out.push(escaped);
+                i += 2;
+            }
+            (b'\\', Some(b'x')) => {
+                match text
+                    .get(i + 2..i + 4)
+                    .and_then(|hex| u8::from_str_radix(hex, 16).ok())
+                {
+                    Some(byte) => {
+                        out.push(byte);
+                        i += 4;
+                    }
+                    None => {
+                        out.push(b'\\');
+                        i += 1;
+                    }
+                }
+            }
+            (byte, _) => {
+                out.push(byte);
+                i += 1;
+            }
+        }
+    }
+    Cow::Owned(String::from_utf8_lossy(&out).into_owned())
+}
+
+enum Value<'a> {
+    Null,
+    Text(Cow<'a, str>),
+    Int(i64),
+    Float(f64),
+    Millis(i64),
+}
+
+impl LogField {
+    /// The value of `text`, or `None` when it is not valid for the field.
+    fn convert<'a>(&self, text: &'a str) -> Option<Value<'a>> {
+        if text == "-" {
+            return Some(Value::Null);
+        }
+        Some(match self.kind {
+            Kind::Text if self.quoted => Value::Text(unescape(text)),
+            Kind::Text => Value::Text(Cow::Borrowed(text)),
+            Kind::Int32 => Value::Int(text.parse::<i32>().ok()?.into()),
+            Kind::Int64 => Value::Int(text.parse().ok()?),
+            Kind::Float64 => Value::Float(text.parse().ok()?),
+            Kind::TimeLocal => Value::Millis(
+                DateTime::parse_from_str(text, "%d/%b/%Y:%H:%M:%S %z")
+                    .ok()?
+                    .timestamp_millis(),
+            ),
+            Kind::TimeIso8601 => {
+                Value::Millis(DateTime::parse_from_rfc3339(text).ok()?.timestamp_millis())
+            }
+            Kind::Msec => Value::Millis((text.parse::<f64>().ok()? * 1000.0).round() as i64),
+        })
+    }
+}
+
+/// A [`FileFormat`] for access logs; see the module documentation.
+#[derive(Debug, Clone)]
+pub struct AccessLogFormat {
+    log_format: LogFormat,
+    options: TextOptions,
+}
+
+impl Default for AccessLogFormat {
+    fn default() -> Self {
+        Self::new(LogFormat::combined())
+    }
+}
+
+impl AccessLogFormat {
+    pub fn new(log_format: LogFormat) -> Self {
+        Self {
+            log_format,
+            options: TextOptions::default(),
+        }
+    }
+
+    /// Builds a format from `CREATE EXTERNAL TABLE` options, with the
+    /// `format.` prefix DataFusion gives keys that have no namespace.
+    /// `log_format` defaults to `combined`.
+    pub fn from_options(options: &HashMap<String, String>) -> Result<Self> {
+        let mut format = Self::default();
+        for (key, value) in options {
+            match key.as_str() {
+                "format.log_format" => format.log_format = LogFormat::from_option(value)?,
+                _ if format.options.set(key, value)? => {}
+                _ => return config_err!("Unknown option '{key}' for STORED AS ACCESS_LOG"),
+            }
+        }
+        Ok(format)
+    }
+
+    pub fn with_mode(mut self, mode: ParseMode) -> Self {
+        self.options.mode = mode;
+        self
+    }
+
+    pub fn with_file_compression_type(mut self, compression: FileCompressionType) -> Self {
+        self.options.compression = compression;
+        self
+    }
+
+    pub fn with_file_extension(mut self, file_extension: impl Into<String>) -> Self {
+        self.options.file_extension = file_extension.into();
+        self
+    }
+
+    pub fn with_record_start(mut self, record_start: RecordStart) -> Self {
+        self.options.record_start = Some(record_start);
+        self
+    }
+
+    pub fn with_max_record_size(mut self, max_record_size: usize) -> Self {
+        self.options.max_record_size = max_record_size;
+        self
+    }
+
+    pub fn log_format(&self) -> &LogFormat {
+        &self.log_format
+    }
+
+    /// The schema inferred from the log format's variables.
+    pub fn schema(&self) -> SchemaRef {
+        self.options.schema(self.log_format.fields())
+    }
+}
+
+#[async_trait]
+impl FileFormat for AccessLogFormat {
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn get_ext(&self) -> String {
+        self.options.file_extension.clone()
+    }
+
+    fn get_ext_with_compression(&self, compression: &FileCompressionType) -> Result<String> {
+        Ok(self.options.ext_with_compression(compression))
+    }
+
+    fn compression_type(&self) -> Option<FileCompressionType> {
+        Some(self.options.compression)
+    }
+
+    async fn infer_schema(
+        &self,
+        _state: &dyn Session,
+        _store: &Arc<dyn ObjectStore>,
+        _objects: &[ObjectMeta],
+    ) -> Result<SchemaRef> {
+        Ok(self.schema())
+    }
+
+    async fn infer_stats(
+        &self,
+        _state: &dyn Session,
+        _store: &Arc<dyn ObjectStore>,
+        table_schema: SchemaRef,
+        _object: &ObjectMeta,
+    ) -> Result<Statistics> {
+        Ok(Statistics::new_unknown(&table_schema))
+    }
+
+    async fn create_physical_plan(
+        &self,
+        _state: &dyn Session,
+        conf: FileScanConfig,
+    ) -> Result<Arc<dyn ExecutionPlan>> {
+        Ok(scan_plan(conf, Arc::new(self.clone())))
+    }
+
+    fn file_source(&self) -> Arc<dyn FileSource> {
+        Arc::new(TextSource::new(Arc::new(self.clone())))
+    }
+}
+
+impl LineFormat for AccessLogFormat {
+    fn file_type(&self) -> &str {
+        NAME
+    }
+
+    fn options(&self) -> &TextOptions {
+        &self.options
+    }
+
+    fn decoder(
+        &self,
+        _file_schema: &SchemaRef,
+        projection: SchemaRef,
+    ) -> Result<Box<dyn LineDecoder>> {
+        let columns = projection
+            .fields()
+            .iter()
+            .map(|field| {
+                if self.options.is_corrupt_record(field) {
+                    return Ok(Column::CorruptRecord);
+                }
+                match self
+                    .log_format
+                    .fields
+                    .iter()
+                    .position(|log_field| &log_field.name == field.name())
+                {
+                    Some(index) => Ok(Column::Field(index)),
+                    None => plan_err!(
+                        "Column `{}` is not a variable of log_format '{}'",
+                        field.name(),
+                        self.log_format.spec
+                    ),
+                }
+            })
+            .collect::<Result<Vec<_>>>()?;
+        // Under PERMISSIVE, without the corrupt record column, every column
+        // only depends on its own field.
+        let unread = (self.options.mode != ParseMode::Permissive
+            || columns
+                .iter()
+                .any(|column| matches!(column, Column::CorruptRecord)))
+        .then(|| {
+            (0..self.log_format.fields.len())
+                .filter(|index| {
+                    !columns
+                        .iter()
+                        .any(|column| matches!(column, Column::Field(read) if read == index))
+                })
+                .collect()
+        });
+        Ok(Box::new(AccessLogDecoder {
+            log_format: self.log_format.clone(),
+            mode: self.options.mode,
+            schema: projection,
+            columns,
+            unread,
+        }))
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+enum Column {
+    Field(usize),
+    CorruptRecord,
+}
+
+enum Builder {
+    Text(StringBuilder),
+    Int32(Int32Builder),
+    Int64(Int64Builder),
+    Float64(Float64Builder),
+    Timestamp(TimestampMillisecondBuilder),
+}
+
+impl Builder {
+    fn new(data_type: &DataType) -> Self {
+        match data_type {
+            DataType::Int32 => Builder::Int32(Int32Builder::new()),
+            DataType::Int64 => Builder::Int64(Int64Builder::new()),
+            DataType::Float64 => Builder::Float64(Float64Builder::new()),
+            DataType::Timestamp(..) => {
+                Builder::Timestamp(TimestampMillisecondBuilder::new().with_timezone(TIMEZONE))
+            }
+            _ => Builder::Text(StringBuilder::new()),
+        }
+    }
+
+    fn append(&mut self, value: &Value<'_>) {
+        match (self, value) {
+            (Builder::Text(builder), Value::Text(text)) => builder.append_value(text),
+            (Builder::Int32(builder), Value::Int(int)) => builder.append_value(*int as i32),
+            (Builder::Int64(builder), Value::Int(int)) => builder.append_value(*int),
+            (Builder::Float64(builder), Value::Float(float)) => builder.append_value(*float),
+            (Builder::Timestamp(builder), Value::Millis(millis)) => builder.append_value(*millis),
+            (builder, _) => builder.append_null(),
+        }
+    }
+
+    fn append_null(&mut self) {
+        match self {
+            Builder::Text(builder) => builder.append_null(),
+            Builder::Int32(builder) => builder.append_null(),
+            Builder::Int64(builder) => builder.append_null(),
+            Builder::Float64(builder) => builder.append_null(),
+            Builder::Timestamp(builder) => builder.append_null(),
+        }
+    }
+
+    fn finish(&mut self) -> ArrayRef {
+        match self {
+            Builder::Text(builder) => Arc::new(builder.finish()),
+            Builder::Int32(builder) => Arc::new(builder.finish()),
+            Builder::Int64(builder) => Arc::new(builder.finish()),
+            Builder::Float64(builder) => Arc::new(builder.finish()),
+            Builder::Timestamp(builder) => Arc::new(builder.finish()),
+        }
+    }
+}
+
+struct AccessLogDecoder {
+    log_format: LogFormat,
+    mode: ParseMode,
+    schema: SchemaRef,
+    columns: Vec<Column>,
+    /// The fields the projection does not read, which are still converted
+    /// when whether a line is malformed decides the result.
+    unread: Option<Vec<usize>>,
+}
+
+impl LineDecoder for AccessLogDecoder {
+    fn decode(&mut self, lines: &[String]) -> Result<RecordBatch> {
+        let fields = &self.log_format.fields;
+        // Builders follow the field types; a declared column of another type
+        // is cast when the batch is finished.
+        let mut builders: Vec<Builder> = self
+            .columns
+            .iter()
+            .map(|column| match column {
+                Column::Field(index) => Builder::new(&fields[*index].kind.data_type()),
+                Column::CorruptRecord => Builder::new(&DataType::Utf8),
+            })
+            .collect();
+        let mut spans = vec![(0, 0); fields.len()];
+        let mut values = Vec::with_capacity(self.columns.len());
+        let mut num_rows = 0;
+
+        for line in lines {
+            let fits = self.log_format.split(line, &mut spans);
+            values.clear();
+            let mut malformed = !fits;
+            if fits {
+                for column in &self.columns {
+                    let value = match column {
+                        Column::Field(index) => {
+                            let (start, end) = spans[*index];
+                            fields[*index].convert(&line[start..end])
+                        }
+                        Column::CorruptRecord => Some(Value::Null),
+                    };
+                    malformed |= value.is_none();
+                    values.push(value.unwrap_or(Value::Null));
+                }
+                if let Some(unread) = &self.unread {
+                    malformed = malformed
+                        || unread.iter().any(|&index| {
+                            let (start, end) = spans[index];
+                            fields[index].convert(&line[start..end]).is_none()
+                        });
+                }
+            }
+            if malformed && !self.mode.keep_malformed(NAME, line)? {
+                continue;
+            }
+            num_rows += 1;
+            for (i, (column, builder)) in self.columns.iter().zip(&mut builders).enumerate() {
+                match column {
+                    Column::CorruptRecord if malformed => {
+                        builder.append(&Value::Text(Cow::Borrowed(line)))
+                    }
+                    Column::Field(_) if !fits => builder.append_null(),
+                    _ => builder.append(&values[i]),
+                }
+            }
+        }
+
+        let arrays = builders
+            .iter_mut()
+            .zip(self.schema.fields())
+            .map(|(builder, field)| {
+                let array = builder.finish();
+                if array.data_type() == field.data_type() {
+                    Ok(array)
+                } else {
+                    Ok(cast(&array, field.data_type())?)
+                }
+            })
+            .collect::<Result<Vec<_>>>()?;
+        Ok(RecordBatch::try_new_with_options(
+            Arc::clone(&self.schema),
+            arrays,
+            &RecordBatchOptions::new().with_row_count(Some(num_rows)),
+        )?)
+    }
+}
+
+/// Creates [`AccessLogFormat`]s for `STORED AS ACCESS_LOG`.
+#[derive(Debug, Default)]
+pub struct AccessLogFormatFactory;
+
+impl AccessLogFormatFactory {
+    pub fn new() -> Self {
+        Self
+    }
+}
+
+impl FileFormatFactory for AccessLogFormatFactory {
+    fn create(
+        &self,
+        _state: &dyn Session,
+        format_options: &HashMap<String, String>,
+    ) -> Result<Arc<dyn FileFormat>> {
+        Ok(Arc::new(AccessLogFormat::from_options(format_options)?))
+    }
+
+    fn default(&self) -> Arc<dyn FileFormat> {
+        Arc::new(AccessLogFormat::default())
+    }
+
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+}
+
+impl GetExt for AccessLogFormatFactory {
+    fn get_ext(&self) -> String {
+        NAME.to_string()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use arrow_array::cast::AsArray;
+    use arrow_array::types::{Int32Type, Int64Type, TimestampMillisecondType};
+    use arrow_array::{Array, StringArray};
+    use datafusion::arrow::datatypes::Schema;
+
+    const LINE: &str = r#"127.0.0.1 - frank [10/Oct/2000:13:55:36 -0700] "GET /a\"b.gif HTTP/1.0" 200 2326 "-" "Mozilla/4.08 [en] (Win98; I ;Nav)""#;
+
+    fn decode(format: &AccessLogFormat, columns: &[&str], lines: &[&str]) -> Result<RecordBatch> {
+        let file_schema = format.schema();
+        let projection = Arc::new(Schema::new(
+            columns
+                .iter()
+                .map(|name| file_schema.field_with_name(name).unwrap().clone())
+                .collect::<Vec<_>>(),
+        ));
+        let lines: Vec<String> = lines.iter().map(|line| line.to_string()).collect();
+        format.decoder(&file_schema, projection)?.decode(&lines)
+    }
+
+    fn strings(batch: &RecordBatch, column: usize) -> Vec<Option<&str>> {
+        let array: &StringArray = batch.column(column).as_string();
+        array.iter().collect()
+    }
+
+    #[test]
+    fn test_combined_line() {
+        let format = AccessLogFormat::default();
+        let columns = [
+            "remote_addr",
+            "remote_logname",
+            "remote_user",
+            "time_local",
+            "request",
+            "status",
+            "body_bytes_sent",
+            "http_referer",
+            "http_user_agent",
+        ];
+        let batch = decode(&format, &columns, &[LINE]).unwrap();
+        assert_eq!(strings(&batch, 0), [Some("127.0.0.1")]);
+        assert_eq!(strings(&batch, 1), [None]);
+        assert_eq!(strings(&batch, 2), [Some("frank")]);
+        let time = batch.column(3).as_primitive::<TimestampMillisecondType>();
+        assert_eq!(time.value(0), 971_211_336_000);
+        assert_eq!(
+            time.data_type(),
+            &DataType::Timestamp(TimeUnit::Millisecond, Some("UTC".into()))
+        );
+        assert_eq!(strings(&batch, 4), [Some(r#"GET /a"b.gif HTTP/1.0"#)]);
+        assert_eq!(batch.column(5).as_primitive::<Int32Type>().value(0), 200);
+        assert_eq!(batch.column(6).as_primitive::<Int64Type>().value(0), 2326);
+        assert_eq!(strings(&batch, 7), [None]);
+        assert_eq!(
+            strings(&batch, 8),
+            [Some("Mozilla/4.08 [en] (Win98; I ;Nav)")]
+        );
+    }
+
+    #[test]
+    fn test_nginx_log_format() {
+        let log_format = LogFormat::nginx(
+            r#"$remote_addr [$time_iso8601] "$request" ${status}ms=$msec rt=$request_time "$http_x_forwarded_for""#,
+        )
+        .unwrap();
+        let names: Vec<_> = log_format
+            .fields()
+            .iter()
+            .map(|field| field.name().clone())
+            .collect();
+        assert_eq!(
+            names,
+            [
+                "remote_addr",
+                "time_iso8601",
+                "request",
+                "status",
+                "msec",
+                "request_time",
+                "http_x_forwarded_for"
+            ]
+        );
+
+        let format = AccessLogFormat::new(log_format);
+        let line = r#"10.1.1.1 [2024-05-01T12:00:00+02:00] "GET / HTTP/1.1" 404ms=1714557600.250 rt=0.004 "1.2.3.4, \x5C""#;
+        let batch = decode(
+            &format,
+            &[
+                "time_iso8601",
+                "status",
+                "msec",
+                "request_time",
+                "http_x_forwarded_for",
+            ],
+            &[line],
+        )
+        .unwrap();
+        let time = batch.column(0).as_primitive::<TimestampMillisecondType>();
+        assert_eq!(time.value(0), 1_714_557_600_000);
+        assert_eq!(batch.column(1).as_primitive::<Int32Type>().value(0), 404);
+        let msec = batch.column(2).as_primitive::<TimestampMillisecondType>();
+        assert_eq!(msec.value(0), 1_714_557_600_250);
+        let request_time = batch
+            .column(3)
+            .as_primitive::<datafusion::arrow::datatypes::Float64Type>();
+        assert_eq!(request_time.value(0), 0.004);
+        assert_eq!(strings(&batch, 4), [Some(r"1.2.3.4, \")]);
+    }
+
+    #[test]
+    fn test_invalid_log_formats() {
+        for (spec, message) in [
+            ("$a$b", "directly follows another"),
+            ("$a - $a", "appears twice"),
+            ("no variables", "has no variables"),
+            ("${a", "Unclosed"),
+        ] {
+            let err = LogFormat::nginx(spec).unwrap_err();
+            assert!(err.to_string().contains(message), "{spec}: {err}");
+        }
+        // A `$` that does not start a name is literal text.
+        let log_format = LogFormat::nginx("$a costs $ 5 $b").unwrap();
+        assert_eq!(log_format.fields().len(), 2);
+    }
+
+    #[test]
+    fn test_columns_do_not_depend_on_the_projection() {
+        let format = AccessLogFormat::new(LogFormat::common());
+        let line = r#"1.2.3.4 - - [10/Oct/2000:13:55:36 -0700] "GET / HTTP/1.1" abc -"#;
+        for columns in [&["remote_addr"][..], &["remote_addr", "status"]] {
+            let batch = decode(&format, columns, &[line]).unwrap();
+            assert_eq!(strings(&batch, 0), [Some("1.2.3.4")], "{columns:?}");
+        }
+        let batch = decode(&format, &["remote_addr", "status"], &[line]).unwrap();
+        assert!(batch.column(1).is_null(0));
+
+        // A line that does not fit the format is NULL in every column, however
+        // few of them are read.
+        let truncated = "1.2.3.4 - - [garbage";
+        let batch = decode(&format, &["remote_addr"], &[truncated]).unwrap();
+        assert!(batch.column(0).is_null(0));
+
+        // Other modes find the bad field even when it is not read.
+        let format = format.with_mode(ParseMode::DropMalformed);
+        let batch = decode(&format, &["remote_addr"], &[line]).unwrap();
+        assert_eq!(batch.num_rows(), 0);
+    }
+
+    #[test]
+    fn test_corrupt_record_and_fail_fast() {
+        let format = AccessLogFormat::new(LogFormat::common()).with_mode(ParseMode::CorruptRecord);
+        let bad = r#"1.2.3.4 - - [10/Oct/2000:13:55:36 -0700] "GET / HTTP/1.1" OK 12"#;
+        let good = r#"1.2.3.4 - - [10/Oct/2000:13:55:36 -0700] "GET / HTTP/1.1" 200 12"#;
+        let batch = decode(&format, &["status", "_corrupt_record"], &[good, bad]).unwrap();
+        let status = batch.column(0).as_primitive::<Int32Type>();
+        assert_eq!(status.value(0), 200);
+        assert!(status.is_null(1));
+        assert_eq!(strings(&batch, 1), [None, Some(bad)]);
+
+        let format = format.with_mode(ParseMode::FailFast);
+        let err = decode(&format, &["status"], &[good, bad]).unwrap_err();
+        let err = crate::error::RegexpError::find(&err).unwrap();
+        assert!(err.to_string().contains("Parse Mode: FAILFAST"));
+    }
+
+    #[test]
+    fn test_from_options() {
+        let options: HashMap<String, String> =
+            [("format.log_format", "clf"), ("format.compression", "zstd")]
+                .into_iter()
+                .map(|(key, value)| (key.to_string(), value.to_string()))
+                .collect();
+        let format = AccessLogFormat::from_options(&options).unwrap();
+        assert_eq!(format.log_format(), &LogFormat::common());
+        assert_eq!(format.options.compression, FileCompressionType::ZSTD);
+
+        let options = HashMap::from([("format.delimiter".to_string(), ",".to_string())]);
+        let err = AccessLogFormat::from_options(&options).unwrap_err();
+        assert!(
+            err.to_string()
+                .contains("Unknown option 'format.delimiter'")
+        );
+    }
+}
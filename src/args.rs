@@ -0,0 +1,100 @@
+//! Argument handling shared by the functions in this crate.
+
+use arrow_array::{ArrayRef, StringArray};
+use datafusion_common::{Result, ScalarValue};
+use datafusion_expr::ColumnarValue;
+
+use crate::error::RegexpError;
+
+pub fn extract_input_and_pattern(
+    arg1: &ColumnarValue,
+    arg2: &ColumnarValue,
+    num_rows: usize,
+) -> Result<(ArrayRef, ArrayRef)> {
+    // looks like we need to check the first argument to check if
+    // it is a scalar because
+    // of the case SELECT regexp_extract('2023-12-25', '(\\d{4})-(\\d{2})-(\\d{2})', 1) FROM my_table;
+    // in this case optimizer will convert the first option to a scalar
+    Ok((to_array(arg1, num_rows)?, to_array(arg2, num_rows)?))
+}
+
+/// Broadcasts a scalar argument to an array of `num_rows`.
+pub fn to_array(arg: &ColumnarValue, num_rows: usize) -> Result<ArrayRef> {
+    match arg {
+        ColumnarValue::Array(array) => Ok(array.clone()),
+        ColumnarValue::Scalar(scalar) => scalar.to_array_of_size(num_rows),
+    }
+}
+
+pub fn as_string_array<'a>(
+    function: &'static str,
+    argument: &'static str,
+    array: &'a ArrayRef,
+) -> Result<&'a StringArray, RegexpError> {
+    array
+        .as_any()
+        .downcast_ref::<StringArray>()
+        .ok_or_else(|| RegexpError::UnexpectedInputType {
+            function,
+            argument,
+            expected: "STRING",
+            actual: array.data_type().to_string(),
+        })
+}
+
+/// Reads a literal group index, using `default` when the argument is absent.
+pub fn scalar_group_index(
+    function: &'static str,
+    arg: Option<&ColumnarValue>,
+    default: i64,
+) -> Result<i64, RegexpError> {
+    match arg {
+        None => Ok(default),
+        Some(ColumnarValue::Scalar(ScalarValue::Int64(Some(idx)))) => Ok(*idx),
+        Some(other) => Err(RegexpError::UnexpectedInputType {
+            function,
+            argument: "idx",
+            expected: "a non-null INT literal",
+            actual: other.data_type().to_string(),
+        }),
+    }
+}
+
+/// Reads an optional literal integer argument such as a position.
+///
+/// Returns `Ok(None)` for a NULL literal, which makes the whole call NULL.
+pub fn scalar_int64(
+    function: &'static str,
+    argument: &'static str,
+    arg: Option<&ColumnarValue>,
+    default: i64,
+) -> Result<Option<i64>, RegexpError> {
+    match arg {
+        None => Ok(Some(default)),
+        Some(ColumnarValue::Scalar(ScalarValue::Int64(value))) => Ok(*value),
+        Some(other) => Err(RegexpError::UnexpectedInputType {
+            function,
+            argument,
+            expected: "an INT literal",
+            actual: other.data_type().to_string(),
+        }),
+    }
+}
+
+/// Like [`scalar_int64`] with a default of 1, for a `position` or
+/// `occurrence` that must be positive.
+pub fn scalar_positive_int64(
+    function: &'static str,
+    argument: &'static str,
+    arg: Option<&ColumnarValue>,
+) -> Result<Option<i64>, RegexpError> {
+    match scalar_int64(function, argument, arg, 1)? {
+        Some(value) if value <= 0 => Err(RegexpError::ValueOutOfRange {
+            function,
+            argument,
+            value,
+            range: "(0, 9223372036854775807]",
+        }),
+        value => Ok(value),
+    }
+}
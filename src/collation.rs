@@ -0,0 +1,232 @@
+//! Spark collations for regex matching.
+//!
+//! A collation decides whether a pattern matches case-insensitively and
+//! whether both the pattern and the input are NFC-normalized before matching.
+//! Normalization can change byte offsets, so [`CollatedText`] keeps a map back
+//! to the original input and extracted values are always sliced from the text
+//! the caller passed in.
+
+use std::borrow::Cow;
+use std::fmt;
+use std::ops::Range;
+use std::str::FromStr;
+
+use datafusion_common::ScalarValue;
+use datafusion_expr::ColumnarValue;
+use unicode_normalization::char::canonical_combining_class;
+use unicode_normalization::{IsNormalized, UnicodeNormalization, is_nfc, is_nfc_quick};
+
+use crate::error::RegexpError;
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub enum Collation {
+    /// Byte-wise comparison, Spark's default.
+    #[default]
+    Utf8Binary,
+    /// Case-insensitive, no normalization.
+    Utf8Lcase,
+    /// Case-sensitive, NFC-normalized.
+    Unicode,
+    /// Case-insensitive, NFC-normalized.
+    UnicodeCi,
+}
+
+impl Collation {
+    pub fn name(&self) -> &'static str {
+        match self {
+            Collation::Utf8Binary => "UTF8_BINARY",
+            Collation::Utf8Lcase => "UTF8_LCASE",
+            Collation::Unicode => "UNICODE",
+            Collation::UnicodeCi => "UNICODE_CI",
+        }
+    }
+
+    /// Resolves an optional collation argument, falling back to `default`
+    /// when it is absent or NULL.
+    pub fn from_argument(
+        function: &'static str,
+        arg: Option<&ColumnarValue>,
+        default: Collation,
+    ) -> Result<Collation, RegexpError> {
+        match arg {
+            None | Some(ColumnarValue::Scalar(ScalarValue::Utf8(None))) => Ok(default),
+            Some(ColumnarValue::Scalar(ScalarValue::Utf8(Some(name)))) => name
+                .parse()
+                .map_err(|name| RegexpError::InvalidCollation { function, name }),
+            Some(other) => Err(RegexpError::UnexpectedInputType {
+                function,
+                argument: "collation",
+                expected: "a STRING literal",
+                actual: other.data_type().to_string(),
+            }),
+        }
+    }
+
+    pub fn is_case_insensitive(&self) -> bool {
+        matches!(self, Collation::Utf8Lcase | Collation::UnicodeCi)
+    }
+
+    pub fn is_normalizing(&self) -> bool {
+        matches!(self, Collation::Unicode | Collation::UnicodeCi)
+    }
+
+    /// The pattern as it should be handed to the regex compiler.
+    pub fn prepare_pattern<'a>(&self, pattern: &'a str) -> Cow<'a, str> {
+        if self.is_normalizing() && !is_nfc(pattern) {
+            Cow::Owned(pattern.nfc().collect())
+        } else {
+            Cow::Borrowed(pattern)
+        }
+    }
+
+    /// The input as it should be handed to the compiled regex.
+    pub fn prepare_text<'a>(&self, text: &'a str) -> CollatedText<'a> {
+        if !self.is_normalizing() || is_nfc(text) {
+            return CollatedText {
+                original: text,
+                matched: Cow::Borrowed(text),
+                boundaries: Vec::new(),
+            };
+        }
+
+        // Normalize one cluster at a time and remember where each cluster
+        // starts in both strings. A cluster ends before a starter that NFC
+        // never composes with what precedes it, so conjoining Hangul jamo
+        // and other composing starters stay with their cluster. Matches are
+        // mapped back to the original on cluster boundaries.
+        let mut matched = String::with_capacity(text.len());
+        let mut boundaries = vec![(0, 0)];
+        let mut cluster_start = 0;
+        for (offset, c) in text.char_indices().skip(1) {
+            if is_nfc_boundary_before(c) {
+                matched.extend(text[cluster_start..offset].nfc());
+                boundaries.push((matched.len(), offset));
+                cluster_start = offset;
+            }
+        }
+        matched.extend(text[cluster_start..].nfc());
+        boundaries.push((matched.len(), text.len()));
+
+        CollatedText {
+            original: text,
+            matched: Cow::Owned(matched),
+            boundaries,
+        }
+    }
+}
+
+/// Whether NFC normalization of a string splits cleanly before `c`: `c` is a
+/// starter that is unchanged by NFC and never composes with a preceding
+/// character.
+fn is_nfc_boundary_before(c: char) -> bool {
+    canonical_combining_class(c) == 0 && is_nfc_quick(std::iter::once(c)) == IsNormalized::Yes
+}
+
+impl fmt::Display for Collation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+impl FromStr for Collation {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_ascii_uppercase().as_str() {
+            "UTF8_BINARY" => Ok(Collation::Utf8Binary),
+            "UTF8_LCASE" => Ok(Collation::Utf8Lcase),
+            "UNICODE" => Ok(Collation::Unicode),
+            "UNICODE_CI" => Ok(Collation::UnicodeCi),
+            _ => Err(s.to_string()),
+        }
+    }
+}
+
+/// Input text prepared for matching under a [`Collation`].
+#[derive(Debug)]
+pub struct CollatedText<'a> {
+    original: &'a str,
+    matched: Cow<'a, str>,
+    /// `(matched_offset, original_offset)` pairs for every cluster start,
+    /// empty when `matched` is the original text.
+    boundaries: Vec<(usize, usize)>,
+}
+
+impl<'a> CollatedText<'a> {
+    /// The text the regex should run against.
+    pub fn as_str(&self) -> &str {
+        &self.matched
+    }
+
+    /// Maps a byte range of [`Self::as_str`] back to the original text,
+    /// widening it to whole clusters when it splits one.
+    pub fn original_range(&self, range: Range<usize>) -> Range<usize> {
+        if self.boundaries.is_empty() {
+            return range;
+        }
+        let start = match self.boundaries.binary_search_by_key(&range.start, |b| b.0) {
+            Ok(i) => self.boundaries[i].1,
+            Err(i) => self.boundaries[i - 1].1,
+        };
+        let end = match self.boundaries.binary_search_by_key(&range.end, |b| b.0) {
+            Ok(i) => self.boundaries[i].1,
+            Err(i) => self.boundaries[i].1,
+        };
+        start..end
+    }
+
+    /// The original text covered by a byte range of [`Self::as_str`].
+    pub fn original(&self, range: Range<usize>) -> &'a str {
+        &self.original[self.original_range(range)]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_collation_names() {
+        assert_eq!("utf8_lcase".parse(), Ok(Collation::Utf8Lcase));
+        assert_eq!("UNICODE_CI".parse(), Ok(Collation::UnicodeCi));
+        assert!("klingon".parse::<Collation>().is_err());
+    }
+
+    #[test]
+    fn test_nfd_text_maps_back_to_original() {
+        // "Café au lait" with a decomposed "é" (e + U+0301).
+        let original = "Cafe\u{301} au lait";
+        let text = Collation::Unicode.prepare_text(original);
+        assert_eq!(text.as_str(), "Caf\u{e9} au lait");
+
+        let found = text.as_str().find("\u{e9} au").unwrap();
+        let range = found..found + "\u{e9} au".len();
+        assert_eq!(text.original(range), "e\u{301} au");
+    }
+
+    #[test]
+    fn test_conjoining_jamo_compose() {
+        // "각x" spelled with conjoining jamo: U+1100 U+1161 U+11A8. Every jamo
+        // is a starter, but NFC composes them into one syllable.
+        let original = "\u{1100}\u{1161}\u{11a8}x";
+        for collation in [Collation::Unicode, Collation::UnicodeCi] {
+            let text = collation.prepare_text(original);
+            assert_eq!(text.as_str(), "\u{ac01}x");
+            assert!(is_nfc(text.as_str()));
+            assert_eq!(text.original(0..3), "\u{1100}\u{1161}\u{11a8}");
+        }
+
+        let pattern = Collation::UnicodeCi.prepare_pattern("\u{1100}\u{1161}");
+        assert_eq!(pattern, "\u{ac00}");
+        let text = Collation::UnicodeCi.prepare_text("\u{1100}\u{1161}");
+        assert!(regex::Regex::new(&pattern).unwrap().is_match(text.as_str()));
+    }
+
+    #[test]
+    fn test_binary_collation_is_untouched() {
+        let original = "Cafe\u{301}";
+        let text = Collation::Utf8Binary.prepare_text(original);
+        assert_eq!(text.as_str(), original);
+        assert_eq!(text.original(1..3), "af");
+    }
+}
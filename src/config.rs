@@ -0,0 +1,216 @@
+//! Session-level settings for the functions that take Java patterns, under
+//! `regexp.*`.
+//!
+//! ```sql
+//! SET regexp.legacy_group_index = true;
+//! SET regexp.dialect = 'rust';
+//! SET regexp.error_mode = 'null';
+//! SET regexp.size_limit = 1048576;
+//! SET regexp.collation = 'UTF8_LCASE';
+//! ```
+//!
+//! `dialect` and `size_limit` apply to every function that implements
+//! [`Configurable`], from `regexp_extract` and `regexp_replace` to `rlike`,
+//! `split`, `str_to_map` and `regexp_explain`; `collation` to those of them
+//! that take a collation; `legacy_group_index` and `error_mode` only to
+//! `regexp_extract`. The engine-specific variants, such as BigQuery's
+//! `regexp_extract`, keep the dialect of their engine.
+//!
+//! [`RegexpConfig`] is a DataFusion config extension; it must be registered
+//! on the session, as [`crate::registry`] does, before `SET` accepts these
+//! keys. A key that was never set leaves the value the function was built
+//! with. DataFusion does not pass the session config to a function when it
+//! runs, so the settings are applied by [`RegexpConfigRewrite`] when each
+//! query is planned: a `SET` affects the queries planned after it, and a plan
+//! built before it, such as a prepared statement's, keeps its settings.
+
+use std::fmt;
+use std::str::FromStr;
+use std::sync::Arc;
+
+use datafusion_common::config::{ConfigExtension, ConfigOptions};
+use datafusion_common::tree_node::Transformed;
+use datafusion_common::{DFSchema, DataFusionError, Result, config_field, extensions_options};
+use datafusion_expr::expr::ScalarFunction;
+use datafusion_expr::expr_rewriter::FunctionRewrite;
+use datafusion_expr::{Expr, ScalarUDF, ScalarUDFImpl};
+
+use crate::collation::Collation;
+use crate::dialect::RegexDialect;
+use crate::error::RegexpError;
+use crate::regexp_classify::RegexpClassify;
+use crate::regexp_count::RegexpCount;
+use crate::regexp_explain::RegexpExplain;
+use crate::regexp_extract::RegexpExtract;
+use crate::regexp_extract_all::RegexpExtractAll;
+use crate::regexp_extract_with_offsets::RegexpExtractWithOffsets;
+use crate::regexp_instr::RegexpInstr;
+use crate::regexp_replace::RegexpReplace;
+use crate::regexp_substr::RegexpSubstr;
+use crate::rlike::RLike;
+use crate::split::Split;
+use crate::str_to_map::StrToMap;
+
+/// What a function does with a row it cannot evaluate, such as one with an
+/// invalid pattern or group index.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub enum ErrorMode {
+    /// Fail the query, as Spark does.
+    #[default]
+    Fail,
+    /// Return NULL for that row and carry on.
+    Null,
+}
+
+impl ErrorMode {
+    pub fn name(&self) -> &'static str {
+        match self {
+            ErrorMode::Fail => "fail",
+            ErrorMode::Null => "null",
+        }
+    }
+
+    /// Turns a row error into `Ok(None)` under [`ErrorMode::Null`].
+    pub fn recover<T>(&self, result: Result<T, RegexpError>) -> Result<Option<T>, RegexpError> {
+        match (result, self) {
+            (Ok(value), _) => Ok(Some(value)),
+            (Err(_), ErrorMode::Null) => Ok(None),
+            (Err(err), ErrorMode::Fail) => Err(err),
+        }
+    }
+}
+
+impl fmt::Display for ErrorMode {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+impl FromStr for ErrorMode {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_ascii_lowercase().as_str() {
+            "fail" => Ok(ErrorMode::Fail),
+            "null" => Ok(ErrorMode::Null),
+            _ => Err(s.to_string()),
+        }
+    }
+}
+
+config_field!(ErrorMode, value => value.parse().map_err(|value| {
+    DataFusionError::Configuration(format!(
+        "Unknown regexp.error_mode '{value}', expected 'fail' or 'null'"
+    ))
+})?);
+
+config_field!(Collation, value => value.parse().map_err(|value| {
+    DataFusionError::Configuration(format!(
+        "Unknown regexp.collation '{value}', expected 'UTF8_BINARY', 'UTF8_LCASE', 'UNICODE' \
+         or 'UNICODE_CI'"
+    ))
+})?);
+
+config_field!(RegexDialect, value => value.parse().map_err(|value| {
+    DataFusionError::Configuration(format!(
+        "Unknown regexp.dialect '{value}', expected 'java' or 'rust'"
+    ))
+})?);
+
+extensions_options! {
+    /// Overrides for the `regexp.*` namespace. Unset keys are `None`.
+    pub struct RegexpConfig {
+        /// Let a negative group index yield an empty string instead of failing.
+        pub legacy_group_index: Option<bool>, default = None
+        /// The regex dialect patterns are written in: `java` or `rust`.
+        pub dialect: Option<RegexDialect>, default = None
+        /// What to do with rows that cannot be evaluated: `fail` or `null`.
+        pub error_mode: Option<ErrorMode>, default = None
+        /// The compiled size limit of each pattern, in bytes.
+        pub size_limit: Option<usize>, default = None
+        /// The collation used when a call does not pass one.
+        pub collation: Option<Collation>, default = None
+    }
+}
+
+impl ConfigExtension for RegexpConfig {
+    const PREFIX: &'static str = "regexp";
+}
+
+/// A function whose settings the session's [`RegexpConfig`] can override.
+pub trait Configurable: ScalarUDFImpl + Sized {
+    /// A copy with the keys set in `config` applied, sharing this instance's
+    /// cache and metrics, or `None` when they change nothing.
+    fn with_config(&self, config: &RegexpConfig) -> Option<Self>;
+}
+
+/// `func` rebuilt with `config` applied: `None` if it is not a `T`, and
+/// `Some(None)` if the config changes nothing.
+fn configure<T: Configurable + 'static>(
+    func: &ScalarUDF,
+    config: &RegexpConfig,
+) -> Option<Option<ScalarUDF>> {
+    let function = func.inner().as_any().downcast_ref::<T>()?;
+    Some(function.with_config(config).map(ScalarUDF::new_from_impl))
+}
+
+type Configure = fn(&ScalarUDF, &RegexpConfig) -> Option<Option<ScalarUDF>>;
+
+/// One entry for every [`Configurable`] function.
+const CONFIGURABLE: &[Configure] = &[
+    configure::<RegexpExtract>,
+    configure::<RegexpExtractAll>,
+    configure::<RegexpExtractWithOffsets>,
+    configure::<RegexpReplace>,
+    configure::<RegexpCount>,
+    configure::<RegexpInstr>,
+    configure::<RegexpSubstr>,
+    configure::<RLike>,
+    configure::<Split>,
+    configure::<StrToMap>,
+    configure::<RegexpClassify>,
+    configure::<RegexpExplain>,
+];
+
+/// Whether [`RegexpConfigRewrite`] applies the session's settings to `func`.
+pub(crate) fn is_configurable(func: &ScalarUDF) -> bool {
+    let config = RegexpConfig::default();
+    CONFIGURABLE
+        .iter()
+        .any(|configure| configure(func, &config).is_some())
+}
+
+/// Applies the session's [`RegexpConfig`] to every call of a function that
+/// takes Java patterns, replacing the function with one built with the
+/// overrides.
+#[derive(Debug, Default)]
+pub struct RegexpConfigRewrite;
+
+impl FunctionRewrite for RegexpConfigRewrite {
+    fn name(&self) -> &str {
+        "regexp_config"
+    }
+
+    fn rewrite(
+        &self,
+        expr: Expr,
+        _schema: &DFSchema,
+        config: &ConfigOptions,
+    ) -> Result<Transformed<Expr>> {
+        let Some(regexp_config) = config.extensions.get::<RegexpConfig>() else {
+            return Ok(Transformed::no(expr));
+        };
+        let Expr::ScalarFunction(ScalarFunction { func, args }) = &expr else {
+            return Ok(Transformed::no(expr));
+        };
+        let Some(Some(configured)) = CONFIGURABLE
+            .iter()
+            .find_map(|configure| configure(func, regexp_config))
+        else {
+            return Ok(Transformed::no(expr));
+        };
+        Ok(Transformed::yes(Expr::ScalarFunction(
+            ScalarFunction::new_udf(Arc::new(configured), args.clone()),
+        )))
+    }
+}
@@ -0,0 +1,742 @@
+//! Regex dialects and the translation of Java patterns to the `regex` crate.
+//!
+//! Spark patterns are `java.util.regex` patterns. Most of the syntax is shared
+//! with the `regex` crate, but some constructs mean different things (`\d` is
+//! ASCII-only in Java, `\<` is a literal) or are spelled differently
+//! (`\p{Alpha}`, `\Q...\E`). [`translate`] rewrites those and rejects the ones
+//! a finite-automaton engine cannot run, such as backreferences and
+//! lookaround. Every rewrite is recorded so callers can report it.
+//!
+//! Java's `$` (outside multi-line mode) and `\Z` also match just before a
+//! line terminator that ends the input. Without lookahead that assertion can
+//! only be written by matching the terminator, which is exact for deciding
+//! whether a pattern matches but not for the match itself, so [`translate`]
+//! rejects both and [`translate_for_matching`] accepts them. And `(?i)` folds
+//! only ASCII letters in Java, so the translation spells out both cases of
+//! each letter and keeps Unicode folding for `(?iu)`.
+
+use std::fmt;
+use std::str::FromStr;
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub enum RegexDialect {
+    /// `java.util.regex` syntax, as used by Spark and Trino.
+    #[default]
+    Java,
+    /// The `regex` crate's own syntax, close to RE2 and POSIX ERE.
+    Rust,
+}
+
+impl RegexDialect {
+    pub fn name(&self) -> &'static str {
+        match self {
+            RegexDialect::Java => "java",
+            RegexDialect::Rust => "rust",
+        }
+    }
+}
+
+impl fmt::Display for RegexDialect {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+impl FromStr for RegexDialect {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_ascii_lowercase().as_str() {
+            "java" | "spark" => Ok(RegexDialect::Java),
+            "rust" | "re2" => Ok(RegexDialect::Rust),
+            _ => Err(s.to_string()),
+        }
+    }
+}
+
+/// A pattern rewritten for the `regex` crate.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Translation {
+    pub pattern: String,
+    /// One entry per kind of construct that was rewritten, in order of first
+    /// appearance.
+    pub notes: Vec<String>,
+}
+
+impl Translation {
+    fn note(&mut self, note: &str) {
+        if !self.notes.iter().any(|n| n == note) {
+            self.notes.push(note.to_string());
+        }
+    }
+}
+
+/// Rewrites `pattern` from `dialect` into `regex` crate syntax.
+pub fn translate(pattern: &str, dialect: RegexDialect) -> Result<Translation, String> {
+    translate_as(pattern, dialect, false)
+}
+
+/// Like [`translate`], for callers that only ask whether the pattern matches
+/// and never read the match or its groups. Java's `$` and `\Z` are accepted
+/// and may match a final line terminator.
+pub fn translate_for_matching(pattern: &str, dialect: RegexDialect) -> Result<Translation, String> {
+    translate_as(pattern, dialect, true)
+}
+
+fn translate_as(
+    pattern: &str,
+    dialect: RegexDialect,
+    match_only: bool,
+) -> Result<Translation, String> {
+    match dialect {
+        RegexDialect::Rust => Ok(Translation {
+            pattern: pattern.to_string(),
+            notes: Vec::new(),
+        }),
+        RegexDialect::Java => translate_java(pattern, match_only),
+    }
+}
+
+const JAVA_LINE_TERMINATORS: &str = r"\n\r\x{85}\x{2028}\x{2029}";
+const HORIZONTAL_SPACE: &str = r"\t \xA0\x{1680}\x{180E}\x{2000}-\x{200A}\x{202F}\x{205F}\x{3000}";
+const VERTICAL_SPACE: &str = r"\n\x0B\f\r\x{85}\x{2028}\x{2029}";
+
+/// The `regex` crate spelling of a Java `\p{...}` name, or `None` when the
+/// name means the same thing in both.
+fn java_property(name: &str) -> Result<Option<&'static str>, String> {
+    let posix = match name {
+        "Lower" => "lower",
+        "Upper" => "upper",
+        "ASCII" => "ascii",
+        "Alpha" => "alpha",
+        "Digit" => "digit",
+        "Alnum" => "alnum",
+        "Punct" => "punct",
+        "Graph" => "graph",
+        "Print" => "print",
+        "Blank" => "blank",
+        "Cntrl" => "cntrl",
+        "XDigit" => "xdigit",
+        "Space" => "space",
+        "javaLowerCase" => return Ok(Some(r"\p{Lowercase}")),
+        "javaUpperCase" => return Ok(Some(r"\p{Uppercase}")),
+        "javaWhitespace" => return Ok(Some(r"\p{White_Space}")),
+        "javaAlphabetic" => return Ok(Some(r"\p{Alphabetic}")),
+        "javaDigit" => return Ok(Some(r"\p{Nd}")),
+        "javaLetter" => return Ok(Some(r"\p{L}")),
+        _ if name.starts_with("In") && name.len() > 2 => {
+            return Err(format!(
+                "Unicode block \\p{{{name}}} is not supported; use a script such as \\p{{{}}}",
+                &name[2..]
+            ));
+        }
+        _ => return Ok(None),
+    };
+    Ok(Some(match posix {
+        "lower" => "[[:lower:]]",
+        "upper" => "[[:upper:]]",
+        "ascii" => "[[:ascii:]]",
+        "alpha" => "[[:alpha:]]",
+        "digit" => "[[:digit:]]",
+        "alnum" => "[[:alnum:]]",
+        "punct" => "[[:punct:]]",
+        "graph" => "[[:graph:]]",
+        "print" => "[[:print:]]",
+        "blank" => "[[:blank:]]",
+        "cntrl" => "[[:cntrl:]]",
+        "xdigit" => "[[:xdigit:]]",
+        _ => "[[:space:]]",
+    }))
+}
+
+/// The `(?i)` and `(?u)` flags in effect in a group. Java folds case for
+/// ASCII letters only unless `u` (or `U`) is also set, while the `regex`
+/// crate always folds Unicode, so the `regex` crate's `i` flag is only used
+/// for Unicode folding and ASCII folding is spelled out letter by letter.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+struct CaseFolding {
+    insensitive: bool,
+    unicode: bool,
+}
+
+impl CaseFolding {
+    fn with_flags(mut self, on: &str, off: &str) -> Self {
+        for (flags, value) in [(on, true), (off, false)] {
+            for flag in flags.chars() {
+                match flag {
+                    'i' => self.insensitive = value,
+                    'u' | 'U' => self.unicode = value,
+                    _ => {}
+                }
+            }
+        }
+        self
+    }
+
+    fn ascii(&self) -> bool {
+        self.insensitive && !self.unicode
+    }
+
+    fn unicode(&self) -> bool {
+        self.insensitive && self.unicode
+    }
+}
+
+/// `c` and its other ASCII case, as class items.
+fn both_cases(c: char) -> String {
+    format!("{}{}", c.to_ascii_lowercase(), c.to_ascii_uppercase())
+}
+
+/// The ASCII letters of `pattern` matched in either case.
+fn fold_ascii_letters(pattern: &str) -> String {
+    let mut out = String::with_capacity(pattern.len());
+    for c in pattern.chars() {
+        if c.is_ascii_alphabetic() {
+            out.push_str(&format!("[{}]", both_cases(c)));
+        } else {
+            out.push(c);
+        }
+    }
+    out
+}
+
+/// The class range `lo-hi`, with the other case of the ASCII letters in it.
+fn fold_ascii_range(lo: char, hi: char) -> String {
+    let mut out = format!("{lo}-{hi}");
+    for (from, to) in [('a', 'z'), ('A', 'Z')] {
+        let start = lo.max(from);
+        let end = hi.min(to);
+        if start <= end {
+            let swap = |c: char| {
+                if c.is_ascii_lowercase() {
+                    c.to_ascii_uppercase()
+                } else {
+                    c.to_ascii_lowercase()
+                }
+            };
+            out.push_str(&format!("{}-{}", swap(start), swap(end)));
+        }
+    }
+    out
+}
+
+/// Wraps a class body as `[...]` or `[^...]`.
+fn class(body: &str, negated: bool) -> String {
+    if negated {
+        format!("[^{body}]")
+    } else {
+        format!("[{body}]")
+    }
+}
+
+fn translate_java(pattern: &str, match_only: bool) -> Result<Translation, String> {
+    let mut out = Translation {
+        pattern: String::with_capacity(pattern.len()),
+        notes: Vec::new(),
+    };
+    let chars: Vec<char> = pattern.chars().collect();
+
+    // Inline flags that change what `.` and the shorthand classes mean. They
+    // are applied to the whole pattern rather than to their group, which is
+    // exact for the usual `(?s)`-style prefix.
+    let flags: String = inline_flags(&chars);
+    let dot_all = flags.contains('s') || flags.contains('d');
+    let unicode_classes = flags.contains('U');
+    let multi_line = flags.contains('m');
+    // Java's `$` and `\Z` also match before a line terminator that ends the
+    // input. The `regex` crate has no lookahead, so the terminator has to be
+    // matched, which only callers that never read the match can allow.
+    let end_of_input = |anchor: &str| {
+        if match_only {
+            Ok(format!(r"(?:\r\n|[{JAVA_LINE_TERMINATORS}])?\z"))
+        } else {
+            Err(format!(
+                "{anchor} is not supported: Java also matches it before a final line \
+                 terminator, which needs lookahead; use \\z to anchor at the end of the input"
+            ))
+        }
+    };
+
+    // Unlike the flags above, case folding follows the groups it is set in.
+    let mut case_scopes = vec![CaseFolding::default()];
+    let mut class_depth = 0usize;
+    let mut i = 0;
+    while i < chars.len() {
+        let c = chars[i];
+        match c {
+            '\\' => {
+                let Some(&next) = chars.get(i + 1) else {
+                    return Err("Unexpected internal error: trailing backslash".to_string());
+                };
+                i += 2;
+                match next {
+                    'd' | 'D' | 'w' | 'W' | 's' | 'S' if !unicode_classes => {
+                        let body = match next.to_ascii_lowercase() {
+                            'd' => "0-9",
+                            'w' => "a-zA-Z_0-9",
+                            _ => r"\t\n\x0B\f\r ",
+                        };
+                        out.pattern
+                            .push_str(&class(body, next.is_ascii_uppercase()));
+                        out.note(r"\d, \w and \s are ASCII-only in Java");
+                    }
+                    'h' | 'H' => {
+                        out.pattern.push_str(&class(HORIZONTAL_SPACE, next == 'H'));
+                        out.note(r"\h expanded to horizontal whitespace");
+                    }
+                    'v' | 'V' => {
+                        out.pattern.push_str(&class(VERTICAL_SPACE, next == 'V'));
+                        out.note(r"\v expanded to vertical whitespace");
+                    }
+                    'R' => {
+                        if class_depth > 0 {
+                            return Err(r"\R is not allowed in a character class".to_string());
+                        }
+                        out.pattern
+                            .push_str(&format!(r"(?:\r\n|[{VERTICAL_SPACE}])"));
+                        out.note(r"\R expanded to a linebreak matcher");
+                    }
+                    'Q' => {
+                        let end = (i..chars.len().saturating_sub(1))
+                            .find(|&j| chars[j] == '\\' && chars[j + 1] == 'E');
+                        let quoted: String = chars[i..end.unwrap_or(chars.len())].iter().collect();
+                        let quoted = regex::escape(&quoted);
+                        if case_scopes.last().is_some_and(CaseFolding::ascii) {
+                            out.pattern.push_str(&fold_ascii_letters(&quoted));
+                        } else {
+                            out.pattern.push_str(&quoted);
+                        }
+                        out.note(r"\Q...\E quoting escaped");
+                        i = end.map_or(chars.len(), |j| j + 2);
+                    }
+                    'E' => {}
+                    'e' => out.pattern.push_str(r"\x1B"),
+                    'c' => {
+                        let Some(&control) = chars.get(i) else {
+                            return Err(r"Illegal control escape sequence \c".to_string());
+                        };
+                        out.pattern
+                            .push_str(&format!(r"\x{{{:X}}}", (control as u32) ^ 64));
+                        out.note(r"\cX control escapes rewritten as \x{..}");
+                        i += 1;
+                    }
+                    '0' => {
+                        let digits: String = chars[i..]
+                            .iter()
+                            .take(3)
+                            .take_while(|c| ('0'..='7').contains(*c))
+                            .collect();
+                        // Java reads at most three octal digits, and only
+                        // three when the value stays below 0o400.
+                        let digits = if digits.len() == 3 && digits.as_bytes()[0] > b'3' {
+                            &digits[..2]
+                        } else {
+                            digits.as_str()
+                        };
+                        if digits.is_empty() {
+                            return Err(r"Illegal octal escape sequence \0".to_string());
+                        }
+                        let value = u32::from_str_radix(digits, 8).unwrap();
+                        out.pattern.push_str(&format!(r"\x{{{value:X}}}"));
+                        out.note(r"\0 octal escapes rewritten as \x{..}");
+                        i += digits.len();
+                    }
+                    '1'..='9' | 'k' => {
+                        return Err("backreferences are not supported".to_string());
+                    }
+                    'G' | 'X' => {
+                        return Err(format!(r"\{next} is not supported"));
+                    }
+                    'Z' => {
+                        out.pattern.push_str(&end_of_input(r"\Z")?);
+                        out.note(r"$ and \Z also match a final line terminator");
+                    }
+                    // Hex digits are not letters to fold, but the letter they
+                    // spell is.
+                    'x' | 'u' => {
+                        let (digits, len) = match (next, chars.get(i)) {
+                            ('x', Some('{')) => {
+                                let close = chars[i..].iter().position(|&c| c == '}');
+                                let close = close.unwrap_or(chars.len() - i);
+                                (chars[(i + 1).min(chars.len())..i + close].iter(), close + 1)
+                            }
+                            ('x', _) => (chars[i..(i + 2).min(chars.len())].iter(), 2),
+                            _ => (chars[i..(i + 4).min(chars.len())].iter(), 4),
+                        };
+                        let letter = u32::from_str_radix(&digits.collect::<String>(), 16)
+                            .ok()
+                            .and_then(char::from_u32)
+                            .filter(char::is_ascii_alphabetic);
+                        let end = (i + len).min(chars.len());
+                        let range_start = class_depth > 0 && chars.get(end) == Some(&'-');
+                        match letter {
+                            Some(c)
+                                if !range_start
+                                    && case_scopes.last().is_some_and(CaseFolding::ascii) =>
+                            {
+                                if class_depth > 0 {
+                                    out.pattern.push_str(&both_cases(c));
+                                } else {
+                                    out.pattern.push_str(&format!("[{}]", both_cases(c)));
+                                }
+                            }
+                            _ => {
+                                out.pattern.push('\\');
+                                out.pattern.push(next);
+                                out.pattern.extend(&chars[i..end]);
+                            }
+                        }
+                        i = end;
+                    }
+                    '<' | '>' => {
+                        // Literals in Java, word boundaries in the regex crate.
+                        out.pattern.push(next);
+                    }
+                    'p' | 'P' => {
+                        let (name, consumed) = if chars.get(i) == Some(&'{') {
+                            let close = chars[i..]
+                                .iter()
+                                .position(|&c| c == '}')
+                                .ok_or_else(|| "Unclosed character family".to_string())?;
+                            (
+                                chars[i + 1..i + close].iter().collect::<String>(),
+                                close + 1,
+                            )
+                        } else {
+                            (chars.get(i).map(|c| c.to_string()).unwrap_or_default(), 1)
+                        };
+                        let negated = next == 'P';
+                        match java_property(&name)? {
+                            Some(replacement) => {
+                                let replacement = if negated {
+                                    format!("[^{replacement}]")
+                                } else {
+                                    replacement.to_string()
+                                };
+                                out.pattern.push_str(&replacement);
+                                out.note(r"Java \p{...} class names mapped");
+                            }
+                            None => {
+                                let name = name.strip_prefix("Is").unwrap_or(&name);
+                                out.pattern.push_str(&format!(r"\{next}{{{name}}}"));
+                            }
+                        }
+                        i += consumed;
+                    }
+                    other if !other.is_ascii() => out.pattern.push(other),
+                    other => {
+                        out.pattern.push('\\');
+                        out.pattern.push(other);
+                    }
+                }
+                continue;
+            }
+            '[' => {
+                class_depth += 1;
+                out.pattern.push('[');
+                i += 1;
+                if chars.get(i) == Some(&'^') {
+                    out.pattern.push('^');
+                    i += 1;
+                }
+                // A leading ']' is a literal in both dialects.
+                if chars.get(i) == Some(&']') {
+                    out.pattern.push_str(r"\]");
+                    i += 1;
+                }
+                continue;
+            }
+            ']' if class_depth > 0 => class_depth -= 1,
+            // `--` and `~~` are set operators in the regex crate only.
+            '-' | '~' if class_depth > 0 && chars.get(i + 1) == Some(&c) => {
+                out.pattern.push(c);
+                out.pattern.push('\\');
+                out.pattern.push(c);
+                i += 2;
+                continue;
+            }
+            '.' if class_depth == 0 && !dot_all => {
+                out.pattern.push_str(&format!("[^{JAVA_LINE_TERMINATORS}]"));
+                out.note("`.` excludes all Java line terminators");
+                i += 1;
+                continue;
+            }
+            '(' if class_depth == 0 => {
+                let scope = *case_scopes.last().unwrap();
+                if chars.get(i + 1) == Some(&'?') {
+                    let rest: String = chars[i + 2..].iter().take(2).collect();
+                    if rest.starts_with('=')
+                        || rest.starts_with('!')
+                        || rest == "<="
+                        || rest == "<!"
+                    {
+                        return Err("lookaround is not supported".to_string());
+                    }
+                    if rest.starts_with('>') {
+                        out.pattern.push_str("(?:");
+                        out.note("atomic groups treated as non-capturing groups");
+                        case_scopes.push(scope);
+                        i += 3;
+                        continue;
+                    }
+                    if let Some(len) = flag_group_len(&chars[i + 2..]) {
+                        let group: String = chars[i + 2..i + 1 + len].iter().collect();
+                        let scoped = chars[i + 1 + len] == ':';
+                        if group.contains(['d', 'U']) {
+                            out.note("flags d and U folded into the translation");
+                        }
+                        let (on, off) = group.split_once('-').unwrap_or((&group, ""));
+                        let folding = scope.with_flags(on, off);
+                        if folding.ascii() && !scope.ascii() {
+                            out.note("(?i) folds the case of ASCII letters only");
+                        }
+                        let kept = |flags: &str| -> String {
+                            flags
+                                .chars()
+                                .filter(|c| !matches!(c, 'd' | 'U' | 'i' | 'u'))
+                                .collect()
+                        };
+                        let (mut on, mut off) = (kept(on), kept(off));
+                        if folding.unicode() && !scope.unicode() {
+                            on.push('i');
+                        } else if !folding.unicode() && scope.unicode() {
+                            off.push('i');
+                        }
+                        if scoped {
+                            case_scopes.push(folding);
+                        } else {
+                            *case_scopes.last_mut().unwrap() = folding;
+                        }
+                        i += 2 + len;
+                        // "(?)" and "(?-)" are not valid in the regex crate.
+                        if on.is_empty() && off.is_empty() {
+                            if scoped {
+                                out.pattern.push_str("(?:");
+                            }
+                            continue;
+                        }
+                        out.pattern.push_str("(?");
+                        out.pattern.push_str(&on);
+                        if !off.is_empty() {
+                            out.pattern.push('-');
+                            out.pattern.push_str(&off);
+                        }
+                        out.pattern.push(if scoped { ':' } else { ')' });
+                        continue;
+                    }
+                    // Group names are copied as they are, not folded.
+                    if rest.starts_with('<')
+                        && let Some(close) = chars[i..].iter().position(|&c| c == '>')
+                    {
+                        out.pattern.extend(&chars[i..=i + close]);
+                        case_scopes.push(scope);
+                        i += close + 1;
+                        continue;
+                    }
+                }
+                case_scopes.push(scope);
+            }
+            ')' if class_depth == 0 && case_scopes.len() > 1 => {
+                case_scopes.pop();
+            }
+            '$' if class_depth == 0 && !multi_line => {
+                out.pattern.push_str(&end_of_input("$")?);
+                out.note(r"$ and \Z also match a final line terminator");
+                i += 1;
+                continue;
+            }
+            c if c.is_ascii_alphabetic() && case_scopes.last().is_some_and(CaseFolding::ascii) => {
+                if class_depth == 0 {
+                    out.pattern.push_str(&format!("[{}]", both_cases(c)));
+                    i += 1;
+                    continue;
+                }
+                match (chars.get(i + 1), chars.get(i + 2)) {
+                    (Some('-'), Some(&hi)) if !matches!(hi, ']' | '[' | '\\') => {
+                        out.pattern.push_str(&fold_ascii_range(c, hi));
+                        i += 3;
+                    }
+                    // A range to an escape is left as it is.
+                    (Some('-'), Some(&hi)) if hi != ']' => {
+                        out.pattern.push(c);
+                        i += 1;
+                    }
+                    _ => {
+                        out.pattern.push_str(&both_cases(c));
+                        i += 1;
+                    }
+                }
+                continue;
+            }
+            '*' | '+' | '?' | '}'
+                if class_depth == 0 && chars.get(i + 1) == Some(&'+') && !is_escaped(&chars, i) =>
+            {
+                out.pattern.push(c);
+                out.note("possessive quantifiers treated as greedy");
+                i += 2;
+                continue;
+            }
+            _ => {}
+        }
+        out.pattern.push(c);
+        i += 1;
+    }
+    Ok(out)
+}
+
+/// Whether the character at `i` is preceded by an odd number of backslashes.
+fn is_escaped(chars: &[char], i: usize) -> bool {
+    chars[..i].iter().rev().take_while(|&&c| c == '\\').count() % 2 == 1
+}
+
+/// Length of an inline flag group body such as `is)` or `i-m:`, counted
+/// from just after `(?`, including the closing `)` or `:`.
+fn flag_group_len(rest: &[char]) -> Option<usize> {
+    let end = rest
+        .iter()
+        .position(|&c| !(c.is_ascii_alphabetic() || c == '-'))?;
+    match rest[end] {
+        ')' | ':' if end > 0 || rest[end] == ')' => Some(end + 1),
+        _ => None,
+    }
+}
+
+/// Every flag letter switched on anywhere in the pattern.
+fn inline_flags(chars: &[char]) -> String {
+    let mut flags = String::new();
+    for i in 0..chars.len().saturating_sub(1) {
+        if chars[i] == '('
+            && chars[i + 1] == '?'
+            && !is_escaped(chars, i)
+            && let Some(len) = flag_group_len(&chars[i + 2..])
+        {
+            flags.extend(
+                chars[i + 2..i + 2 + len]
+                    .iter()
+                    .take_while(|&&c| c != '-')
+                    .filter(|c| c.is_ascii_alphabetic()),
+            );
+        }
+    }
+    flags
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use regex::Regex;
+
+    fn java(pattern: &str) -> Regex {
+        let translation = translate_for_matching(pattern, RegexDialect::Java).unwrap();
+        Regex::new(&translation.pattern).unwrap()
+    }
+
+    #[test]
+    fn test_shorthand_classes_are_ascii() {
+        let regex = java(r"^\d+$");
+        assert!(regex.is_match("123"));
+        assert!(!regex.is_match("١٢٣"));
+        assert!(java(r"[\w-]+").is_match("a-b"));
+    }
+
+    #[test]
+    fn test_quoting_and_posix_classes() {
+        assert!(java(r"\Qa.b\E").is_match("a.b"));
+        assert!(!java(r"\Qa.b\E").is_match("axb"));
+        assert!(java(r"^\p{Alpha}+\P{Digit}$").is_match("abc-"));
+        assert!(java(r"\p{IsLatin}").is_match("é"));
+    }
+
+    #[test]
+    fn test_dot_and_line_terminators() {
+        assert!(!java("a.b").is_match("a\rb"));
+        assert!(java("(?s)a.b").is_match("a\rb"));
+    }
+
+    #[test]
+    fn test_literal_angle_brackets_and_escapes() {
+        assert!(java(r"\<b\>").is_match("<b>"));
+        assert!(java(r"\0101\cI").is_match("A\t"));
+    }
+
+    #[test]
+    fn test_flag_groups() {
+        assert!(java("(?iU)abc").is_match("ABC"));
+        assert!(java("(?i:a)b").is_match("Ab"));
+        assert!(java(r"(?<year>\d{4})").is_match("2024"));
+    }
+
+    #[test]
+    fn test_end_of_input_allows_a_final_line_terminator() {
+        for pattern in ["abc$", r"abc\Z"] {
+            let regex = java(pattern);
+            for input in ["abc", "abc\n", "abc\r\n", "abc\u{2028}"] {
+                assert!(regex.is_match(input), "{pattern} on {input:?}");
+            }
+            assert!(!regex.is_match("abc\n\n"), "{pattern}");
+            assert!(!regex.is_match("abc\nx"), "{pattern}");
+        }
+        assert!(!java(r"abc\z").is_match("abc\n"));
+        assert!(java("[$]").is_match("$"));
+        assert!(java(r"\$").is_match("$"));
+    }
+
+    #[test]
+    fn test_end_of_input_is_rejected_where_the_match_is_read() {
+        for pattern in ["abc$", r"abc\Z", "(a|b$)c"] {
+            let err = translate(pattern, RegexDialect::Java).unwrap_err();
+            assert!(err.contains(r"use \z"), "{pattern}: {err}");
+        }
+        for pattern in [r"abc\z", "[$]", r"\$", "(?m)^abc$"] {
+            assert!(translate(pattern, RegexDialect::Java).is_ok(), "{pattern}");
+        }
+    }
+
+    #[test]
+    fn test_case_insensitive_is_ascii_only() {
+        assert!(java("(?i)straße").is_match("STRAßE"));
+        assert!(!java("(?i)é").is_match("É"));
+        assert!(!java("(?i)k").is_match("\u{212A}"));
+        assert!(java("(?iu)é").is_match("É"));
+        assert!(java("(?iU)é").is_match("É"));
+        assert!(java("(?i)[a-c][X-Z_]").is_match("Bx"));
+        assert!(java(r"(?i)\Qa.b\E").is_match("A.B"));
+        assert!(java(r"(?i)\x41\u0042").is_match("ab"));
+    }
+
+    #[test]
+    fn test_case_insensitive_follows_groups() {
+        assert!(java("(?i:a)b").is_match("Ab"));
+        assert!(!java("(?i:a)b").is_match("AB"));
+        assert!(java("(a(?i)b)c").is_match("aBc"));
+        assert!(!java("(a(?i)b)c").is_match("aBC"));
+        assert!(!java("(?i)a(?-i)b").is_match("AB"));
+        assert!(java("(?iu)é(?-u)é").is_match("Éé"));
+        assert!(!java("(?iu)é(?-u)é").is_match("ÉÉ"));
+        let regex = java(r"(?i)(?<Name>x)");
+        assert_eq!(
+            regex.captures("X").unwrap().name("Name").unwrap().as_str(),
+            "X"
+        );
+    }
+
+    #[test]
+    fn test_unsupported_constructs() {
+        assert!(translate(r"(a)\1", RegexDialect::Java).is_err());
+        assert!(translate(r"a(?=b)", RegexDialect::Java).is_err());
+        assert!(translate(r"(?<!a)b", RegexDialect::Java).is_err());
+        assert!(translate(r"\p{InGreek}", RegexDialect::Java).is_err());
+    }
+
+    #[test]
+    fn test_notes_are_recorded_once() {
+        let translation = translate(r"\d+\d+\Qx\E", RegexDialect::Java).unwrap();
+        assert_eq!(translation.notes.len(), 2);
+        assert!(
+            translate("abc", RegexDialect::Java)
+                .unwrap()
+                .notes
+                .is_empty()
+        );
+    }
+}
@@ -0,0 +1,374 @@
+//! Typed errors raised by the regex functions in this crate.
+//!
+//! Every variant maps onto a Spark error class and SQLSTATE so services can
+//! tell user errors from bugs. The enum converts into
+//! `DataFusionError::External`, so it can be recovered from a query error with
+//! [`RegexpError::find`].
+
+use std::error::Error;
+use std::fmt;
+
+use datafusion_common::DataFusionError;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum RegexpError {
+    /// The pattern could not be compiled.
+    InvalidPattern {
+        function: &'static str,
+        pattern: String,
+        row: usize,
+        message: String,
+    },
+    /// The requested capture group does not exist in the pattern.
+    InvalidGroupIndex {
+        function: &'static str,
+        pattern: String,
+        index: i64,
+        group_count: usize,
+        row: usize,
+    },
+    /// The replacement string is not valid Java `Matcher` replacement syntax.
+    InvalidReplacement {
+        function: &'static str,
+        replacement: String,
+        row: usize,
+        message: String,
+    },
+    /// A literal argument is outside the range the function accepts.
+    ValueOutOfRange {
+        function: &'static str,
+        argument: &'static str,
+        value: i64,
+        range: &'static str,
+    },
+    /// A LIKE pattern misuses its escape character: `next` is the character
+    /// it precedes, or `None` when the pattern ends with it.
+    InvalidEscape {
+        function: &'static str,
+        pattern: String,
+        row: usize,
+        next: Option<char>,
+    },
+    /// A map was built with the same key twice under the `EXCEPTION` policy.
+    DuplicatedMapKey {
+        function: &'static str,
+        key: String,
+        row: usize,
+    },
+    /// A redaction category that is neither built in nor registered.
+    UnknownCategory {
+        function: &'static str,
+        name: String,
+        known: Vec<String>,
+    },
+    /// A regex parameters string with a letter the function does not know.
+    InvalidRegexParameters {
+        function: &'static str,
+        parameters: String,
+        letter: char,
+    },
+    /// A URL that `java.net.URI` would reject, under ANSI mode.
+    InvalidUrl {
+        function: &'static str,
+        url: String,
+        row: usize,
+    },
+    /// The collation name is not one this crate supports.
+    InvalidCollation {
+        function: &'static str,
+        name: String,
+    },
+    /// A line of a text file that does not fit its format, under `FAILFAST`.
+    MalformedRecord {
+        format: &'static str,
+        record: String,
+    },
+    /// The function was called with too few or too many arguments.
+    WrongNumArgs {
+        function: &'static str,
+        expected: &'static str,
+        actual: usize,
+    },
+    /// An argument was not of the type the function expects.
+    UnexpectedInputType {
+        function: &'static str,
+        argument: &'static str,
+        expected: &'static str,
+        actual: String,
+    },
+    /// A condition that should be unreachable for well-formed plans.
+    Internal {
+        function: &'static str,
+        message: String,
+    },
+}
+
+impl RegexpError {
+    /// The Spark error class, e.g. `INVALID_PARAMETER_VALUE.REGEX_GROUP_INDEX`.
+    pub fn error_class(&self) -> &'static str {
+        match self {
+            RegexpError::InvalidPattern { .. } => "INVALID_PARAMETER_VALUE.PATTERN",
+            RegexpError::InvalidGroupIndex { .. } => "INVALID_PARAMETER_VALUE.REGEX_GROUP_INDEX",
+            RegexpError::InvalidReplacement { .. } => "INVALID_PARAMETER_VALUE.REPLACEMENT",
+            RegexpError::ValueOutOfRange { .. } => "DATATYPE_MISMATCH.VALUE_OUT_OF_RANGE",
+            RegexpError::InvalidEscape { next: Some(_), .. } => "INVALID_FORMAT.ESC_IN_THE_MIDDLE",
+            RegexpError::InvalidEscape { next: None, .. } => "INVALID_FORMAT.ESC_AT_THE_END",
+            RegexpError::DuplicatedMapKey { .. } => "DUPLICATED_MAP_KEY",
+            RegexpError::UnknownCategory { .. } => "INVALID_PARAMETER_VALUE.CATEGORY",
+            RegexpError::InvalidRegexParameters { .. } => {
+                "INVALID_PARAMETER_VALUE.REGEX_PARAMETERS"
+            }
+            RegexpError::InvalidUrl { .. } => "INVALID_URL",
+            RegexpError::InvalidCollation { .. } => "COLLATION_INVALID_NAME",
+            RegexpError::MalformedRecord { .. } => "MALFORMED_RECORD_IN_PARSING.WITHOUT_SUGGESTION",
+            RegexpError::WrongNumArgs { .. } => "WRONG_NUM_ARGS.WITHOUT_SUGGESTION",
+            RegexpError::UnexpectedInputType { .. } => "DATATYPE_MISMATCH.UNEXPECTED_INPUT_TYPE",
+            RegexpError::Internal { .. } => "INTERNAL_ERROR",
+        }
+    }
+
+    /// The SQLSTATE Spark reports for [`Self::error_class`].
+    pub fn sql_state(&self) -> &'static str {
+        match self {
+            RegexpError::InvalidPattern { .. }
+            | RegexpError::InvalidGroupIndex { .. }
+            | RegexpError::InvalidReplacement { .. }
+            | RegexpError::UnknownCategory { .. }
+            | RegexpError::InvalidRegexParameters { .. }
+            | RegexpError::MalformedRecord { .. } => "22023",
+            RegexpError::ValueOutOfRange { .. } => "42K09",
+            RegexpError::InvalidEscape { .. } => "42601",
+            RegexpError::DuplicatedMapKey { .. } => "23505",
+            RegexpError::InvalidUrl { .. } => "22P02",
+            RegexpError::InvalidCollation { .. } => "42704",
+            RegexpError::WrongNumArgs { .. } => "42605",
+            RegexpError::UnexpectedInputType { .. } => "42K09",
+            RegexpError::Internal { .. } => "XX000",
+        }
+    }
+
+    /// Whether the error was caused by the query rather than by this crate.
+    pub fn is_user_error(&self) -> bool {
+        !matches!(self, RegexpError::Internal { .. })
+    }
+
+    /// The offending pattern, when the error is tied to one.
+    pub fn pattern(&self) -> Option<&str> {
+        match self {
+            RegexpError::InvalidPattern { pattern, .. }
+            | RegexpError::InvalidGroupIndex { pattern, .. }
+            | RegexpError::InvalidEscape { pattern, .. } => Some(pattern),
+            _ => None,
+        }
+    }
+
+    /// The row of the batch being evaluated when the error was raised.
+    pub fn row(&self) -> Option<usize> {
+        match self {
+            RegexpError::InvalidPattern { row, .. }
+            | RegexpError::InvalidGroupIndex { row, .. }
+            | RegexpError::InvalidReplacement { row, .. }
+            | RegexpError::InvalidEscape { row, .. }
+            | RegexpError::DuplicatedMapKey { row, .. }
+            | RegexpError::InvalidUrl { row, .. } => Some(*row),
+            _ => None,
+        }
+    }
+
+    /// Finds a `RegexpError` anywhere in the source chain of a DataFusion error.
+    pub fn find(err: &DataFusionError) -> Option<&RegexpError> {
+        let mut current: Option<&(dyn Error + 'static)> = Some(err);
+        while let Some(e) = current {
+            if let Some(regexp_error) = e.downcast_ref::<RegexpError>() {
+                return Some(regexp_error);
+            }
+            current = e.source();
+        }
+        None
+    }
+}
+
+impl fmt::Display for RegexpError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "[{}] ", self.error_class())?;
+        match self {
+            RegexpError::InvalidPattern {
+                function,
+                pattern,
+                row,
+                message,
+            } => write!(
+                f,
+                "The value of parameter(s) `regexp` in `{function}` is invalid: \
+                 Error compiling regex '{pattern}' at row {row}: {message}"
+            )?,
+            RegexpError::InvalidGroupIndex {
+                function,
+                pattern,
+                index,
+                group_count,
+                row,
+            } => write!(
+                f,
+                "The value of parameter(s) `idx` in `{function}` is invalid: \
+                 Group index must be a non-negative integer. Expects group index between 0 \
+                 and {group_count}, but got {index} (pattern '{pattern}', row {row})."
+            )?,
+            RegexpError::InvalidReplacement {
+                function,
+                replacement,
+                row,
+                message,
+            } => write!(
+                f,
+                "The value of parameter(s) `rep` in `{function}` is invalid: \
+                 '{replacement}' at row {row}: {message}"
+            )?,
+            RegexpError::ValueOutOfRange {
+                function,
+                argument,
+                value,
+                range,
+            } => write!(
+                f,
+                "Parameter `{argument}` of `{function}` must be within {range}, \
+                 but got {value}."
+            )?,
+            RegexpError::InvalidEscape {
+                function,
+                pattern,
+                row,
+                next: Some(next),
+            } => write!(
+                f,
+                "The format is invalid: '{pattern}' in `{function}` at row {row}. The escape \
+                 character is not allowed to precede '{next}'."
+            )?,
+            RegexpError::InvalidEscape {
+                function,
+                pattern,
+                row,
+                next: None,
+            } => write!(
+                f,
+                "The format is invalid: '{pattern}' in `{function}` at row {row}. The escape \
+                 character is not allowed to end with."
+            )?,
+            RegexpError::DuplicatedMapKey { function, key, row } => write!(
+                f,
+                "Duplicate map key {key} was found in `{function}` at row {row}, please \
+                 check the input data. If you want to remove the duplicated keys, you can \
+                 set the map key dedup policy to \"LAST_WIN\" so that the key inserted at \
+                 last takes precedence."
+            )?,
+            RegexpError::UnknownCategory {
+                function,
+                name,
+                known,
+            } => write!(
+                f,
+                "The value of parameter(s) `categories` in `{function}` is invalid: \
+                 unknown category '{name}'. Known categories: [{}].",
+                known.join(", ")
+            )?,
+            RegexpError::InvalidRegexParameters {
+                function,
+                parameters,
+                letter,
+            } => write!(
+                f,
+                "The value of parameter(s) `regex_parameters` in `{function}` is invalid: \
+                 unknown parameter '{letter}' in '{parameters}'. Known parameters: \
+                 [c, i, m, e, s]."
+            )?,
+            RegexpError::InvalidUrl { function, url, row } => write!(
+                f,
+                "The url is invalid: {url} (`{function}`, row {row}). Use `try_parse_url` to \
+                 tolerate invalid URL and return NULL instead."
+            )?,
+            RegexpError::InvalidCollation { function, name } => write!(
+                f,
+                "The value `{name}` passed to `{function}` does not represent a correct \
+                 collation name. Suggested valid collation names: \
+                 [UTF8_BINARY, UTF8_LCASE, UNICODE, UNICODE_CI]."
+            )?,
+            RegexpError::MalformedRecord { format, record } => write!(
+                f,
+                "Malformed records are detected in record parsing: {record}. Parse Mode: \
+                 FAILFAST. To process malformed records as null result, try setting the \
+                 `{format}` option 'mode' as 'PERMISSIVE'."
+            )?,
+            RegexpError::WrongNumArgs {
+                function,
+                expected,
+                actual,
+            } => write!(
+                f,
+                "The `{function}` requires {expected} parameters but the actual number is \
+                 {actual}."
+            )?,
+            RegexpError::UnexpectedInputType {
+                function,
+                argument,
+                expected,
+                actual,
+            } => write!(
+                f,
+                "Parameter `{argument}` of `{function}` requires {expected}, but got {actual}."
+            )?,
+            RegexpError::Internal { function, message } => write!(f, "{function}: {message}")?,
+        }
+        write!(f, " SQLSTATE: {}", self.sql_state())
+    }
+}
+
+impl Error for RegexpError {}
+
+impl From<RegexpError> for DataFusionError {
+    fn from(err: RegexpError) -> Self {
+        DataFusionError::External(Box::new(err))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_error_class_and_sql_state() {
+        let err = RegexpError::InvalidGroupIndex {
+            function: "regexp_extract",
+            pattern: "(a)".to_string(),
+            index: -1,
+            group_count: 1,
+            row: 3,
+        };
+        assert_eq!(
+            err.error_class(),
+            "INVALID_PARAMETER_VALUE.REGEX_GROUP_INDEX"
+        );
+        assert_eq!(err.sql_state(), "22023");
+        assert!(err.is_user_error());
+        assert_eq!(err.pattern(), Some("(a)"));
+        assert_eq!(err.row(), Some(3));
+        assert!(
+            err.to_string()
+                .starts_with("[INVALID_PARAMETER_VALUE.REGEX_GROUP_INDEX]")
+        );
+        assert!(err.to_string().ends_with("SQLSTATE: 22023"));
+    }
+
+    #[test]
+    fn test_find_through_context() {
+        let err = RegexpError::Internal {
+            function: "regexp_extract",
+            message: "boom".to_string(),
+        };
+        let df_err = DataFusionError::from(err.clone()).context("while evaluating");
+        assert_eq!(RegexpError::find(&df_err), Some(&err));
+        assert_eq!(
+            RegexpError::find(&DataFusionError::Execution("other".to_string())),
+            None
+        );
+    }
+}
@@ -0,0 +1,813 @@
+//! Logstash grok patterns.
+//!
+//! A grok pattern is a regex in which `%{NAME}` stands for a named pattern
+//! from a [`GrokLibrary`], `%{NAME:field}` also captures the text it matched
+//! as `field`, and `%{NAME:field:int}` or `%{NAME:field:float}` converts the
+//! capture. [`Grok`] expands a pattern into a single Java-dialect regex,
+//! compiles it through the shared [`PatternCache`] and returns the fields as
+//! a struct.
+
+use std::any::Any;
+use std::collections::HashMap;
+use std::fmt;
+use std::path::Path;
+use std::str::FromStr;
+use std::sync::Arc;
+
+use arrow_array::builder::{Float64Builder, Int64Builder, NullBufferBuilder, StringBuilder};
+use arrow_array::{Array, ArrayRef, StringArray, StructArray};
+use datafusion_common::arrow::datatypes::{DataType, Field, FieldRef, Fields};
+use datafusion_common::{Result, ScalarValue};
+use datafusion_expr::{
+    ColumnarValue, ReturnFieldArgs, ScalarFunctionArgs, ScalarUDFImpl, Signature, Volatility,
+};
+use regex::Regex;
+
+use crate::args::{as_string_array, to_array};
+use crate::error::RegexpError;
+use crate::metrics::{BatchMetrics, RegexpMetrics};
+use crate::pattern::{PatternCache, PatternOptions};
+
+const NAME: &str = "grok";
+const GENERATED_GROUP_PREFIX: &str = "_grok";
+
+/// The patterns bundled with this crate, in grok's pattern file format.
+///
+/// They follow the Logstash `legacy` set, rewritten where the originals rely
+/// on lookaround or atomic groups, which the `regex` crate cannot run.
+pub const STANDARD_PATTERNS: &str = r##"
+# Basics
+USERNAME [a-zA-Z0-9._-]+
+USER %{USERNAME}
+EMAILLOCALPART [a-zA-Z0-9!#$%&'*+/=?^_`{|}~-]+(?:\.[a-zA-Z0-9!#$%&'*+/=?^_`{|}~-]+)*
+EMAILADDRESS %{EMAILLOCALPART}@%{HOSTNAME}
+INT [+-]?[0-9]+
+BASE10NUM [+-]?(?:[0-9]+(?:\.[0-9]*)?|\.[0-9]+)
+NUMBER %{BASE10NUM}
+BASE16NUM [+-]?(?:0x)?[0-9A-Fa-f]+
+BASE16FLOAT \b[+-]?(?:0x)?(?:[0-9A-Fa-f]+(?:\.[0-9A-Fa-f]*)?|\.[0-9A-Fa-f]+)\b
+POSINT \b[1-9][0-9]*\b
+NONNEGINT \b[0-9]+\b
+WORD \b\w+\b
+NOTSPACE \S+
+SPACE \s*
+DATA .*?
+GREEDYDATA .*
+QUOTEDSTRING "(?:\\.|[^\\"])*"|'(?:\\.|[^\\'])*'|`(?:\\.|[^\\`])*`
+QS %{QUOTEDSTRING}
+UUID [A-Fa-f0-9]{8}-(?:[A-Fa-f0-9]{4}-){3}[A-Fa-f0-9]{12}
+
+# Networking
+CISCOMAC (?:[A-Fa-f0-9]{4}\.){2}[A-Fa-f0-9]{4}
+WINDOWSMAC (?:[A-Fa-f0-9]{2}-){5}[A-Fa-f0-9]{2}
+COMMONMAC (?:[A-Fa-f0-9]{2}:){5}[A-Fa-f0-9]{2}
+MAC %{CISCOMAC}|%{WINDOWSMAC}|%{COMMONMAC}
+IPV4OCTET 25[0-5]|2[0-4][0-9]|1[0-9][0-9]|[1-9]?[0-9]
+IPV4 \b%{IPV4OCTET}(?:\.%{IPV4OCTET}){3}\b
+IPV6H16 [0-9A-Fa-f]{1,4}
+IPV6LS32 %{IPV6H16}:%{IPV6H16}|%{IPV4}
+IPV6 (?:(?:%{IPV6H16}:){6}%{IPV6LS32}|::(?:%{IPV6H16}:){5}%{IPV6LS32}|(?:%{IPV6H16})?::(?:%{IPV6H16}:){4}%{IPV6LS32}|(?:(?:%{IPV6H16}:){0,1}%{IPV6H16})?::(?:%{IPV6H16}:){3}%{IPV6LS32}|(?:(?:%{IPV6H16}:){0,2}%{IPV6H16})?::(?:%{IPV6H16}:){2}%{IPV6LS32}|(?:(?:%{IPV6H16}:){0,3}%{IPV6H16})?::%{IPV6H16}:%{IPV6LS32}|(?:(?:%{IPV6H16}:){0,4}%{IPV6H16})?::%{IPV6LS32}|(?:(?:%{IPV6H16}:){0,5}%{IPV6H16})?::%{IPV6H16}|(?:(?:%{IPV6H16}:){0,6}%{IPV6H16})?::)(?:%[0-9A-Za-z.]+)?
+IP %{IPV6}|%{IPV4}
+HOSTNAME \b[0-9A-Za-z][0-9A-Za-z-]{0,62}(?:\.[0-9A-Za-z][0-9A-Za-z-]{0,62})*(?:\.?|\b)
+HOST %{HOSTNAME}
+IPORHOST %{IP}|%{HOSTNAME}
+HOSTPORT %{IPORHOST}:%{POSINT}
+
+# Paths and URIs
+PATH %{UNIXPATH}|%{WINPATH}
+UNIXPATH (?:/(?:[\w%!$@:.,+~-]+|\\.)*)+
+TTY /dev/(?:pts|tty[pq]?)(?:\w+)?/?[0-9]+
+WINPATH (?:[A-Za-z]+:|\\)(?:\\[^\\?*]*)+
+URIPROTO [A-Za-z][A-Za-z0-9+.-]+
+URIHOST %{IPORHOST}(?::%{POSINT})?
+URIPATH (?:/[A-Za-z0-9$.+!*'(){},~:;=@#%&_-]*)+
+URIQUERY [A-Za-z0-9$.+!*'|(){},~@#%&/=:;_?<>\[\]-]*
+URIPARAM \?%{URIQUERY}
+URIPATHPARAM %{URIPATH}(?:%{URIPARAM})?
+URI %{URIPROTO}://(?:%{USER}(?::[^@]*)?@)?(?:%{URIHOST})?(?:%{URIPATHPARAM})?
+
+# Dates and times
+MONTH \b(?:[Jj]an(?:uary|uar)?|[Ff]eb(?:ruary|ruar)?|[Mm]a(?:r|erz)(?:ch)?|[Aa]pr(?:il)?|[Mm]a[yi]|[Jj]un[ei]?|[Jj]ul[yi]?|[Aa]ug(?:ust)?|[Ss]ep(?:tember)?|[Oo][ck]t(?:ober)?|[Nn]ov(?:ember)?|[Dd]e[cz](?:ember)?)\b
+MONTHNUM 1[0-2]|0?[1-9]
+MONTHNUM2 0[1-9]|1[0-2]
+MONTHDAY 3[01]|[12][0-9]|0?[1-9]
+DAY Mon(?:day)?|Tue(?:sday)?|Wed(?:nesday)?|Thu(?:rsday)?|Fri(?:day)?|Sat(?:urday)?|Sun(?:day)?
+YEAR (?:[0-9][0-9]){1,2}
+HOUR 2[0123]|[01]?[0-9]
+MINUTE [0-5][0-9]
+SECOND (?:60|[0-5]?[0-9])(?:[:.,][0-9]+)?
+TIME %{HOUR}:%{MINUTE}(?::%{SECOND})?
+DATE_US %{MONTHNUM}[/-]%{MONTHDAY}[/-]%{YEAR}
+DATE_EU %{MONTHDAY}[./-]%{MONTHNUM}[./-]%{YEAR}
+DATE %{DATE_US}|%{DATE_EU}
+DATESTAMP %{DATE}[- ]%{TIME}
+TZ [APMCE][SD]T|UTC
+ISO8601_TIMEZONE Z|[+-]%{HOUR}(?::?%{MINUTE})
+ISO8601_SECOND %{SECOND}
+TIMESTAMP_ISO8601 %{YEAR}-%{MONTHNUM}-%{MONTHDAY}[T ]%{HOUR}:?%{MINUTE}(?::?%{SECOND})?%{ISO8601_TIMEZONE}?
+DATESTAMP_RFC822 %{DAY} %{MONTH} %{MONTHDAY} %{YEAR} %{TIME} %{TZ}
+HTTPDATE %{MONTHDAY}/%{MONTH}/%{YEAR}:%{TIME} %{INT}
+SYSLOGTIMESTAMP %{MONTH} +%{MONTHDAY} %{TIME}
+
+# Logs
+LOGLEVEL [Aa]lert|ALERT|[Tt]race|TRACE|[Dd]ebug|DEBUG|[Nn]otice|NOTICE|[Ii]nfo?(?:rmation)?|INFO?(?:RMATION)?|[Ww]arn?(?:ing)?|WARN?(?:ING)?|[Ee]rr?(?:or)?|ERR?(?:OR)?|[Cc]rit?(?:ical)?|CRIT?(?:ICAL)?|[Ff]atal|FATAL|[Ss]evere|SEVERE|EMERG(?:ENCY)?|[Ee]merg(?:ency)?
+PROG [\x21-\x5a\x5c\x5e-\x7e]+
+SYSLOGPROG %{PROG:program}(?:\[%{POSINT:pid}\])?
+SYSLOGHOST %{IPORHOST}
+SYSLOGFACILITY <%{NONNEGINT:facility}.%{NONNEGINT:priority}>
+SYSLOGBASE %{SYSLOGTIMESTAMP:timestamp} (?:%{SYSLOGFACILITY} )?%{SYSLOGHOST:logsource} %{SYSLOGPROG}:
+HTTPDUSER %{EMAILADDRESS}|%{USER}
+COMMONAPACHELOG %{IPORHOST:clientip} %{HTTPDUSER:ident} %{HTTPDUSER:auth} \[%{HTTPDATE:timestamp}\] "(?:%{WORD:verb} %{NOTSPACE:request}(?: HTTP/%{NUMBER:httpversion})?|%{DATA:rawrequest})" %{NUMBER:response} (?:%{NUMBER:bytes}|-)
+COMBINEDAPACHELOG %{COMMONAPACHELOG} %{QS:referrer} %{QS:agent}
+"##;
+
+/// The type a `%{NAME:field:type}` capture is converted to.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub enum GrokType {
+    #[default]
+    String,
+    /// `int`: a 64-bit integer. Decimals are truncated, as Logstash does.
+    Int,
+    /// `float`: a 64-bit float.
+    Float,
+}
+
+impl GrokType {
+    pub fn name(&self) -> &'static str {
+        match self {
+            GrokType::String => "string",
+            GrokType::Int => "int",
+            GrokType::Float => "float",
+        }
+    }
+
+    pub fn data_type(&self) -> DataType {
+        match self {
+            GrokType::String => DataType::Utf8,
+            GrokType::Int => DataType::Int64,
+            GrokType::Float => DataType::Float64,
+        }
+    }
+}
+
+impl fmt::Display for GrokType {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+impl FromStr for GrokType {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_ascii_lowercase().as_str() {
+            "string" => Ok(GrokType::String),
+            "int" => Ok(GrokType::Int),
+            "float" => Ok(GrokType::Float),
+            _ => Err(s.to_string()),
+        }
+    }
+}
+
+/// A field captured by a grok pattern.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct GrokField {
+    pub name: String,
+    pub grok_type: GrokType,
+}
+
+/// A grok pattern expanded into a single regex.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct GrokExpansion {
+    /// The regex, in the Java dialect.
+    pub pattern: String,
+    /// The captured fields, in order of first appearance.
+    pub fields: Vec<GrokField>,
+    /// For each generated group `_grok{i}`, the index of its field. A field
+    /// captured more than once takes the first group that participated.
+    /// Java group names have no `_`, so these never clash with a named group
+    /// written in the pattern.
+    pub groups: Vec<usize>,
+}
+
+impl GrokExpansion {
+    fn capture(&mut self, name: &str, grok_type: GrokType) -> String {
+        let field = match self.fields.iter().position(|field| field.name == name) {
+            Some(field) => field,
+            None => {
+                self.fields.push(GrokField {
+                    name: name.to_string(),
+                    grok_type,
+                });
+                self.fields.len() - 1
+            }
+        };
+        self.groups.push(field);
+        format!("{GENERATED_GROUP_PREFIX}{}", self.groups.len() - 1)
+    }
+}
+
+/// Named patterns that grok patterns can refer to.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct GrokLibrary {
+    patterns: HashMap<String, String>,
+}
+
+impl GrokLibrary {
+    /// A library with only the [standard patterns](STANDARD_PATTERNS).
+    pub fn standard() -> Self {
+        let mut library = Self::default();
+        library
+            .add_definitions(STANDARD_PATTERNS)
+            .expect("the standard patterns are well-formed");
+        library
+    }
+
+    /// Defines `name`, replacing an existing definition.
+    pub fn add_pattern(&mut self, name: impl Into<String>, definition: impl Into<String>) {
+        self.patterns.insert(name.into(), definition.into());
+    }
+
+    /// Adds the definitions of a pattern file: one `NAME definition` per
+    /// line, with blank lines and lines starting with `#` ignored.
+    pub fn add_definitions(&mut self, definitions: &str) -> Result<(), String> {
+        for (number, line) in definitions.lines().enumerate() {
+            let line = line.trim_start();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            match line.split_once(char::is_whitespace) {
+                Some((name, definition)) if !definition.trim().is_empty() => {
+                    self.add_pattern(name, definition.trim())
+                }
+                _ => {
+                    return Err(format!(
+                        "line {}: expected 'NAME definition', got '{line}'",
+                        number + 1
+                    ));
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// The definition of `name`, if any.
+    pub fn get(&self, name: &str) -> Option<&str> {
+        self.patterns.get(name).map(String::as_str)
+    }
+
+    /// Expands every `%{...}` reference in `grok`, recursively.
+    pub fn expand(&self, grok: &str) -> Result<GrokExpansion, String> {
+        let mut expansion = GrokExpansion {
+            pattern: String::with_capacity(grok.len()),
+            fields: Vec::new(),
+            groups: Vec::new(),
+        };
+        self.expand_into(grok, &mut Vec::new(), &mut expansion)?;
+        Ok(expansion)
+    }
+
+    fn expand_into<'a>(
+        &'a self,
+        grok: &str,
+        stack: &mut Vec<&'a str>,
+        out: &mut GrokExpansion,
+    ) -> Result<(), String> {
+        let mut rest = grok;
+        while let Some(start) = rest.find("%{") {
+            out.pattern.push_str(&rest[..start]);
+            let reference = &rest[start + 2..];
+            let end = reference
+                .find('}')
+                .ok_or_else(|| format!("unclosed reference '%{{{reference}'"))?;
+            rest = &reference[end + 1..];
+
+            let mut parts = reference[..end].splitn(3, ':');
+            let name = parts.next().unwrap_or_default();
+            let field = parts.next().filter(|field| !field.is_empty());
+            let grok_type = parts
+                .next()
+                .map(|t| {
+                    t.parse::<GrokType>()
+                        .map_err(|t| format!("unknown type '{t}' in %{{{}}}", &reference[..end]))
+                })
+                .transpose()?
+                .unwrap_or_default();
+
+            let (name, definition) = self
+                .patterns
+                .get_key_value(name)
+                .ok_or_else(|| format!("unknown pattern %{{{name}}}"))?;
+            if stack.contains(&name.as_str()) {
+                return Err(format!(
+                    "pattern %{{{name}}} refers to itself through {}",
+                    stack.join(" -> ")
+                ));
+            }
+            match field {
+                Some(field) => {
+                    let group = out.capture(field, grok_type);
+                    out.pattern.push_str(&format!("(?<{group}>"));
+                }
+                None => out.pattern.push_str("(?:"),
+            }
+            stack.push(name);
+            self.expand_into(definition, stack, out)?;
+            stack.pop();
+            out.pattern.push(')');
+        }
+        out.pattern.push_str(rest);
+        Ok(())
+    }
+}
+
+/// A grok pattern ready to run: the compiled regex and, for each field, the
+/// groups that capture it.
+struct CompiledGrok {
+    regex: Arc<Regex>,
+    fields: Vec<GrokField>,
+    groups: Vec<Vec<usize>>,
+}
+
+/// `grok(str, grok_pattern)`: the fields `grok_pattern` captures from `str`,
+/// as a struct with one nullable field per capture.
+///
+/// `grok_pattern` must be a literal, since it decides the return type. Like
+/// Logstash, it matches anywhere in `str`; anchor it with `^` and `\z` to
+/// match whole lines (`$` is rejected, see [`crate::dialect`]). Named groups written directly in the pattern, such as
+/// `(?<queue_id>[0-9A-F]{10})`, are returned as string fields too.
+///
+/// The result is NULL when `str` is NULL or the pattern does not match. A
+/// field is NULL when its group took no part in the match or its value does
+/// not convert to the requested type.
+///
+/// ```sql
+/// SELECT grok(http_log, '%{IPORHOST:client} - %{WORD:method} %{URIPATHPARAM:path} %{NUMBER:status:int}').status
+/// FROM logs
+/// ```
+#[derive(Debug, Clone)]
+pub struct Grok {
+    signature: Signature,
+    library: Arc<GrokLibrary>,
+    cache: Arc<PatternCache>,
+    metrics: Arc<RegexpMetrics>,
+}
+
+impl Default for Grok {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Grok {
+    /// `grok` with the [standard patterns](STANDARD_PATTERNS).
+    pub fn new() -> Self {
+        Self::with_library(GrokLibrary::standard())
+    }
+
+    /// `grok` with only the patterns of `library`.
+    pub fn with_library(library: GrokLibrary) -> Self {
+        Self {
+            signature: Signature::exact(
+                vec![DataType::Utf8, DataType::Utf8],
+                Volatility::Immutable,
+            ),
+            library: Arc::new(library),
+            cache: Arc::new(PatternCache::default()),
+            metrics: Arc::new(RegexpMetrics::new()),
+        }
+    }
+
+    /// Defines `name`, replacing an existing definition.
+    pub fn with_pattern(mut self, name: impl Into<String>, definition: impl Into<String>) -> Self {
+        Arc::make_mut(&mut self.library).add_pattern(name, definition);
+        self
+    }
+
+    /// Adds the definitions of the pattern file at `path`, in the format of
+    /// [`GrokLibrary::add_definitions`].
+    pub fn with_patterns_file(mut self, path: impl AsRef<Path>) -> std::io::Result<Self> {
+        let definitions = std::fs::read_to_string(path.as_ref())?;
+        Arc::make_mut(&mut self.library)
+            .add_definitions(&definitions)
+            .map_err(|e| {
+                std::io::Error::new(
+                    std::io::ErrorKind::InvalidData,
+                    format!("{}: {e}", path.as_ref().display()),
+                )
+            })?;
+        Ok(self)
+    }
+
+    /// The patterns grok patterns can refer to.
+    pub fn library(&self) -> &GrokLibrary {
+        &self.library
+    }
+
+    /// Execution metrics, shared by every clone of this instance.
+    pub fn metrics(&self) -> &RegexpMetrics {
+        &self.metrics
+    }
+
+    fn compile(
+        &self,
+        grok: &str,
+        row: usize,
+        metrics: &mut BatchMetrics,
+    ) -> Result<CompiledGrok, RegexpError> {
+        let invalid = |message: String| RegexpError::InvalidPattern {
+            function: NAME,
+            pattern: grok.to_string(),
+            row,
+            message,
+        };
+        let expansion = self.library.expand(grok).map_err(invalid)?;
+        let regex = self
+            .cache
+            .get_or_compile(
+                NAME,
+                &expansion.pattern,
+                PatternOptions::default(),
+                row,
+                metrics,
+            )
+            .map_err(|e| match e {
+                RegexpError::InvalidPattern { message, .. } => invalid(message),
+                other => other,
+            })?;
+
+        let mut fields = expansion.fields;
+        let mut groups = vec![Vec::new(); fields.len()];
+        for (index, name) in regex.capture_names().enumerate() {
+            let Some(name) = name else { continue };
+            let field = match name
+                .strip_prefix(GENERATED_GROUP_PREFIX)
+                .and_then(|i| i.parse::<usize>().ok())
+                .and_then(|generated| expansion.groups.get(generated))
+            {
+                Some(&field) => field,
+                None => match fields.iter().position(|field| field.name == name) {
+                    Some(field) => field,
+                    None => {
+                        fields.push(GrokField {
+                            name: name.to_string(),
+                            grok_type: GrokType::String,
+                        });
+                        groups.push(Vec::new());
+                        fields.len() - 1
+                    }
+                },
+            };
+            groups[field].push(index);
+        }
+        Ok(CompiledGrok {
+            regex,
+            fields,
+            groups,
+        })
+    }
+
+    fn fields(compiled: &CompiledGrok) -> Fields {
+        compiled
+            .fields
+            .iter()
+            .map(|field| Field::new(&field.name, field.grok_type.data_type(), true))
+            .collect()
+    }
+
+    fn literal_pattern(arg: Option<&ScalarValue>) -> Result<&str, RegexpError> {
+        match arg {
+            Some(ScalarValue::Utf8(Some(grok))) => Ok(grok),
+            other => Err(RegexpError::UnexpectedInputType {
+                function: NAME,
+                argument: "grok_pattern",
+                expected: "a non-null STRING literal",
+                actual: other.map_or("a column".to_string(), |v| v.data_type().to_string()),
+            }),
+        }
+    }
+}
+
+enum FieldBuilder {
+    String(StringBuilder),
+    Int(Int64Builder),
+    Float(Float64Builder),
+}
+
+impl FieldBuilder {
+    fn new(grok_type: GrokType, capacity: usize) -> Self {
+        match grok_type {
+            GrokType::String => FieldBuilder::String(StringBuilder::with_capacity(capacity, 0)),
+            GrokType::Int => FieldBuilder::Int(Int64Builder::with_capacity(capacity)),
+            GrokType::Float => FieldBuilder::Float(Float64Builder::with_capacity(capacity)),
+        }
+    }
+
+    fn append(&mut self, value: Option<&str>) {
+        match self {
+            FieldBuilder::String(builder) => builder.append_option(value),
+            FieldBuilder::Int(builder) => builder.append_option(value.and_then(|value| {
+                value
+                    .parse::<i64>()
+                    .ok()
+                    .or_else(|| value.parse::<f64>().ok().map(|v| v.trunc() as i64))
+            })),
+            FieldBuilder::Float(builder) => {
+                builder.append_option(value.and_then(|value| value.parse::<f64>().ok()))
+            }
+        }
+    }
+
+    fn finish(&mut self) -> ArrayRef {
+        match self {
+            FieldBuilder::String(builder) => Arc::new(builder.finish()),
+            FieldBuilder::Int(builder) => Arc::new(builder.finish()),
+            FieldBuilder::Float(builder) => Arc::new(builder.finish()),
+        }
+    }
+}
+
+impl ScalarUDFImpl for Grok {
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn name(&self) -> &str {
+        NAME
+    }
+
+    fn signature(&self) -> &Signature {
+        &self.signature
+    }
+
+    fn return_type(&self, _arg_types: &[DataType]) -> Result<DataType> {
+        Err(RegexpError::Internal {
+            function: NAME,
+            message: "return_field_from_args should be called instead".to_string(),
+        }
+        .into())
+    }
+
+    fn return_field_from_args(&self, args: ReturnFieldArgs) -> Result<FieldRef> {
+        let grok = Self::literal_pattern(args.scalar_arguments.get(1).copied().flatten())?;
+        let compiled = self.compile(grok, 0, &mut BatchMetrics::start(NAME, 0, &self.metrics))?;
+        Ok(Arc::new(Field::new(
+            NAME,
+            DataType::Struct(Self::fields(&compiled)),
+            true,
+        )))
+    }
+
+    fn invoke_with_args(&self, args: ScalarFunctionArgs) -> Result<ColumnarValue> {
+        let num_rows = args.number_rows;
+        let grok = match &args.args[1] {
+            ColumnarValue::Scalar(scalar) => Self::literal_pattern(Some(scalar))?,
+            ColumnarValue::Array(_) => Self::literal_pattern(None)?,
+        };
+        let input_array_ref: ArrayRef = to_array(&args.args[0], num_rows)?;
+        let input_array: &StringArray = as_string_array(NAME, "str", &input_array_ref)?;
+
+        let mut batch_metrics = BatchMetrics::start(NAME, num_rows, &self.metrics);
+        let compiled = self.compile(grok, 0, &mut batch_metrics)?;
+        let mut builders: Vec<FieldBuilder> = compiled
+            .fields
+            .iter()
+            .map(|field| FieldBuilder::new(field.grok_type, num_rows))
+            .collect();
+        let mut nulls = NullBufferBuilder::new(num_rows);
+
+        for i in 0..num_rows {
+            let captures = if input_array.is_null(i) {
+                None
+            } else {
+                batch_metrics.rows_processed += 1;
+                compiled.regex.captures(input_array.value(i))
+            };
+            let Some(captures) = captures else {
+                // The children of a null struct slot still need a value.
+                builders.iter_mut().for_each(|builder| builder.append(None));
+                nulls.append_null();
+                continue;
+            };
+            batch_metrics.rows_matched += 1;
+            for (builder, groups) in builders.iter_mut().zip(&compiled.groups) {
+                let value = groups
+                    .iter()
+                    .find_map(|&group| captures.get(group))
+                    .map(|m| m.as_str());
+                builder.append(value);
+            }
+            nulls.append_non_null();
+        }
+
+        let array = StructArray::try_new(
+            Self::fields(&compiled),
+            builders.iter_mut().map(FieldBuilder::finish).collect(),
+            nulls.finish(),
+        )?;
+        Ok(ColumnarValue::Array(Arc::new(array)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use arrow_array::cast::AsArray;
+    use arrow_array::types::{Float64Type, Int64Type};
+
+    fn run_test(udf: &Grok, input: Vec<Option<&str>>, grok: &str) -> Result<StructArray> {
+        let num_rows = input.len();
+        let grok = ScalarValue::from(grok);
+        let return_field = udf.return_field_from_args(ReturnFieldArgs {
+            arg_fields: &[],
+            scalar_arguments: &[None, Some(&grok)],
+        })?;
+        let args = ScalarFunctionArgs {
+            args: vec![
+                ColumnarValue::Array(Arc::new(StringArray::from(input))),
+                ColumnarValue::Scalar(grok),
+            ],
+            number_rows: num_rows,
+            arg_fields: vec![],
+            return_field,
+        };
+        let array = udf.invoke_with_args(args)?.to_array(num_rows)?;
+        Ok(array.as_struct().clone())
+    }
+
+    #[test]
+    fn test_http_log() {
+        let result = run_test(
+            &Grok::new(),
+            vec![
+                Some("1.2.3.4 - GET /index.html?v=2 200"),
+                Some("No match here"),
+                None,
+            ],
+            "%{IPORHOST:client} - %{WORD:method} %{URIPATHPARAM:path} %{NUMBER:status:int}",
+        )
+        .unwrap();
+
+        let names: Vec<&str> = result.column_names();
+        assert_eq!(names, vec!["client", "method", "path", "status"]);
+        assert_eq!(result.column(0).as_string::<i32>().value(0), "1.2.3.4");
+        assert_eq!(result.column(1).as_string::<i32>().value(0), "GET");
+        assert_eq!(
+            result.column(2).as_string::<i32>().value(0),
+            "/index.html?v=2"
+        );
+        assert_eq!(result.column(3).as_primitive::<Int64Type>().value(0), 200);
+        assert!(result.is_null(1));
+        assert!(result.is_null(2));
+    }
+
+    #[test]
+    fn test_combined_apache_log() {
+        let line = r#"127.0.0.1 - frank [10/Oct/2000:13:55:36 -0700] "GET /apache_pb.gif HTTP/1.0" 200 2326 "http://www.example.com/start.html" "Mozilla/4.08""#;
+        let result = run_test(&Grok::new(), vec![Some(line)], "%{COMBINEDAPACHELOG}").unwrap();
+        let value = |name: &str| {
+            result
+                .column_by_name(name)
+                .unwrap()
+                .as_string::<i32>()
+                .value(0)
+                .to_string()
+        };
+        assert_eq!(value("clientip"), "127.0.0.1");
+        assert_eq!(value("auth"), "frank");
+        assert_eq!(value("timestamp"), "10/Oct/2000:13:55:36 -0700");
+        assert_eq!(value("request"), "/apache_pb.gif");
+        assert_eq!(value("bytes"), "2326");
+        assert_eq!(value("agent"), "\"Mozilla/4.08\"");
+        assert!(result.column_by_name("rawrequest").unwrap().is_null(0));
+    }
+
+    #[test]
+    fn test_ip_patterns() {
+        let udf = Grok::new();
+        for (text, expected) in [
+            ("2001:db8::ff00:42:8329", "2001:db8::ff00:42:8329"),
+            ("::ffff:192.0.2.1", "::ffff:192.0.2.1"),
+            ("10.0.0.256", "10.0.0.256"),
+            ("host-1.example.com", "host-1.example.com"),
+        ] {
+            let result = run_test(&udf, vec![Some(text)], "^%{IPORHOST:host}").unwrap();
+            assert_eq!(
+                result.column(0).as_string::<i32>().value(0),
+                expected,
+                "{text}"
+            );
+        }
+    }
+
+    #[test]
+    fn test_types_and_raw_named_groups() {
+        let result = run_test(
+            &Grok::new(),
+            vec![
+                Some("took 3.75s x=12.9 id=ABC123"),
+                Some("took xs x=1 id=Z"),
+            ],
+            r"took %{NUMBER:secs:float}s x=%{NUMBER:x:int} id=(?<id>\w+)",
+        )
+        .unwrap();
+        assert_eq!(
+            result.column(0).data_type(),
+            &DataType::Float64,
+            "secs is a float"
+        );
+        assert_eq!(
+            result.column(0).as_primitive::<Float64Type>().value(0),
+            3.75
+        );
+        assert_eq!(result.column(1).as_primitive::<Int64Type>().value(0), 12);
+        assert_eq!(result.column(2).as_string::<i32>().value(0), "ABC123");
+        assert!(result.is_null(1));
+    }
+
+    #[test]
+    fn test_raw_groups_named_like_generated_ones() {
+        let result = run_test(
+            &Grok::new(),
+            vec![Some("a b 7")],
+            r"(?<grok5>\w+) (?<grok0>\w+) %{INT:n:int}",
+        )
+        .unwrap();
+        let names: Vec<_> = result.fields().iter().map(|f| f.name().as_str()).collect();
+        assert_eq!(names, ["n", "grok5", "grok0"]);
+        assert_eq!(result.column(0).as_primitive::<Int64Type>().value(0), 7);
+        assert_eq!(result.column(1).as_string::<i32>().value(0), "a");
+        assert_eq!(result.column(2).as_string::<i32>().value(0), "b");
+
+        let result = run_test(&Grok::new(), vec![Some("x")], r"(?<grok99>\w+)").unwrap();
+        assert_eq!(result.column(0).as_string::<i32>().value(0), "x");
+    }
+
+    #[test]
+    fn test_repeated_field_takes_first_participating_group() {
+        let result = run_test(
+            &Grok::new(),
+            vec![Some("user=bob"), Some("uid=7")],
+            "(?:user=%{WORD:who}|uid=%{INT:who})",
+        )
+        .unwrap();
+        assert_eq!(result.num_columns(), 1);
+        let who = result.column(0).as_string::<i32>();
+        assert_eq!((who.value(0), who.value(1)), ("bob", "7"));
+    }
+
+    #[test]
+    fn test_custom_patterns() {
+        let mut library = GrokLibrary::standard();
+        library
+            .add_definitions("# postfix\nQUEUEID [0-9A-F]{10,11}\nPOSTFIX %{QUEUEID:queue_id}: %{GREEDYDATA:message}\n")
+            .unwrap();
+        let udf = Grok::with_library(library).with_pattern("QUEUEID", "[0-9A-F]{4}");
+        let result = run_test(&udf, vec![Some("BEF2: removed")], "%{POSTFIX}").unwrap();
+        assert_eq!(result.column(0).as_string::<i32>().value(0), "BEF2");
+        assert_eq!(result.column(1).as_string::<i32>().value(0), "removed");
+
+        assert!(
+            GrokLibrary::default()
+                .add_definitions("MISSING_DEFINITION")
+                .unwrap_err()
+                .starts_with("line 1")
+        );
+    }
+
+    #[test]
+    fn test_expansion_errors() {
+        let udf = Grok::new()
+            .with_pattern("A", "%{B}")
+            .with_pattern("B", "x%{A}");
+        for (grok, message) in [
+            ("%{NOPE:x}", "unknown pattern %{NOPE}"),
+            ("%{WORD:x:bool}", "unknown type 'bool'"),
+            ("%{WORD", "unclosed reference"),
+            ("%{A}", "refers to itself through A -> B"),
+        ] {
+            let err = run_test(&udf, vec![Some("x")], grok).unwrap_err();
+            let err = RegexpError::find(&err).unwrap();
+            assert_eq!(err.error_class(), "INVALID_PARAMETER_VALUE.PATTERN");
+            assert_eq!(err.pattern(), Some(grok));
+            assert!(err.to_string().contains(message), "{err}");
+        }
+    }
+
+    #[test]
+    fn test_standard_patterns_compile() {
+        let library = GrokLibrary::standard();
+        for line in STANDARD_PATTERNS.lines() {
+            let Some((name, _)) = line.split_once(' ') else {
+                continue;
+            };
+            if name.starts_with('#') {
+                continue;
+            }
+            let expansion = library.expand(&format!("%{{{name}}}")).unwrap();
+            crate::dialect::translate(&expansion.pattern, crate::dialect::RegexDialect::Java)
+                .and_then(|t| Regex::new(&t.pattern).map_err(|e| e.to_string()))
+                .unwrap_or_else(|e| panic!("{name}: {e}"));
+        }
+    }
+}
@@ -1 +1,31 @@
+pub mod access_log;
+pub mod args;
+pub mod collation;
+pub mod config;
+pub mod dialect;
+pub mod error;
+pub mod grok;
+pub mod like;
+pub mod metrics;
+pub mod parse_url;
+pub mod pattern;
+pub mod redact;
+pub mod regex_format;
+pub mod regexp_classify;
+pub mod regexp_count;
+pub mod regexp_explain;
 pub mod regexp_extract;
+pub mod regexp_extract_all;
+pub mod regexp_extract_bigquery;
+pub mod regexp_extract_trino;
+pub mod regexp_extract_with_offsets;
+pub mod regexp_instr;
+pub mod regexp_replace;
+pub mod regexp_substr;
+pub mod regexp_substr_snowflake;
+pub mod registry;
+pub mod rlike;
+pub mod split;
+pub mod sql;
+pub mod str_to_map;
+pub mod text_file;
@@ -0,0 +1,624 @@
+//! Spark's LIKE family: `like`, `ilike` and the multi-pattern `like_any`,
+//! `like_all`, `not_like_any` and `not_like_all` (plus their `ilike`
+//! counterparts).
+//!
+//! LIKE patterns are translated to anchored regexes the way Spark's
+//! `StringUtils.escapeLikeRegex` does and compiled through the shared
+//! [`PatternCache`]. The multi-pattern functions compile all their patterns
+//! into one [`RegexSet`], so each row is scanned once.
+
+use std::any::Any;
+use std::borrow::Cow;
+use std::sync::Arc;
+
+use arrow_array::builder::BooleanBuilder;
+use arrow_array::{Array, ArrayRef, StringArray};
+use datafusion_common::arrow::datatypes::DataType;
+use datafusion_common::{Result, ScalarValue};
+use datafusion_expr::{
+    ColumnarValue, ScalarFunctionArgs, ScalarUDFImpl, Signature, TypeSignature, Volatility,
+};
+use regex::{Regex, RegexSet};
+
+use crate::args::{as_string_array, extract_input_and_pattern, to_array};
+use crate::dialect::RegexDialect;
+use crate::error::RegexpError;
+use crate::metrics::{BatchMetrics, RegexpMetrics};
+use crate::pattern::{PatternCache, PatternOptions};
+
+/// Spark's default LIKE escape character.
+pub const DEFAULT_ESCAPE: char = '\\';
+
+/// Translates a LIKE pattern into an anchored `regex` crate pattern.
+///
+/// `_` matches one character and `%` any number, including newlines. The
+/// escape character may only precede `_`, `%` or itself; otherwise the
+/// error holds the character it preceded, or `None` when the pattern ends
+/// with it.
+pub fn like_to_regex(pattern: &str, escape: char) -> Result<String, Option<char>> {
+    let mut out = String::with_capacity(pattern.len() + 8);
+    out.push_str("(?s)^");
+    let mut chars = pattern.chars();
+    let mut buf = [0; 4];
+    while let Some(c) = chars.next() {
+        if c == escape {
+            match chars.next() {
+                Some(next) if next == '_' || next == '%' || next == escape => {
+                    out.push_str(&regex::escape(next.encode_utf8(&mut buf)));
+                }
+                next => return Err(next),
+            }
+            continue;
+        }
+        match c {
+            '_' => out.push('.'),
+            '%' => out.push_str(".*"),
+            c => out.push_str(&regex::escape(c.encode_utf8(&mut buf))),
+        }
+    }
+    out.push_str(r"\z");
+    Ok(out)
+}
+
+/// Spark's `ILIKE` is `LIKE` over lower-cased operands.
+fn fold_case(value: &str, case_insensitive: bool) -> Cow<'_, str> {
+    if case_insensitive {
+        Cow::Owned(value.to_lowercase())
+    } else {
+        Cow::Borrowed(value)
+    }
+}
+
+fn options() -> PatternOptions {
+    PatternOptions {
+        dialect: RegexDialect::Rust,
+        ..Default::default()
+    }
+}
+
+/// Spark's `str LIKE pattern [ESCAPE escape]` as `like(str, pattern[,
+/// escape])`, and `ILIKE` as `ilike(...)`.
+///
+/// The whole input must match. A NULL `str` or `pattern` yields NULL; the
+/// escape character defaults to `\` and must be a single-character literal.
+#[derive(Debug, Clone)]
+pub struct Like {
+    signature: Signature,
+    name: &'static str,
+    case_insensitive: bool,
+    cache: Arc<PatternCache>,
+    metrics: Arc<RegexpMetrics>,
+}
+
+impl Default for Like {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Like {
+    /// Case-sensitive `like`.
+    pub fn new() -> Self {
+        Self {
+            signature: Signature::one_of(
+                vec![
+                    TypeSignature::Exact(vec![DataType::Utf8, DataType::Utf8]),
+                    TypeSignature::Exact(vec![DataType::Utf8, DataType::Utf8, DataType::Utf8]),
+                ],
+                Volatility::Immutable,
+            ),
+            name: "like",
+            case_insensitive: false,
+            cache: Arc::new(PatternCache::default()),
+            metrics: Arc::new(RegexpMetrics::new()),
+        }
+    }
+
+    /// Case-insensitive `ilike`.
+    pub fn ilike() -> Self {
+        Self {
+            name: "ilike",
+            case_insensitive: true,
+            ..Self::new()
+        }
+    }
+
+    /// Execution metrics, shared by every clone of this instance.
+    pub fn metrics(&self) -> &RegexpMetrics {
+        &self.metrics
+    }
+}
+
+impl ScalarUDFImpl for Like {
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn name(&self) -> &str {
+        self.name
+    }
+
+    fn signature(&self) -> &Signature {
+        &self.signature
+    }
+
+    fn return_type(&self, _arg_types: &[DataType]) -> Result<DataType> {
+        Ok(DataType::Boolean)
+    }
+
+    fn invoke_with_args(&self, args: ScalarFunctionArgs) -> Result<ColumnarValue> {
+        let num_rows = args.number_rows;
+        let escape = escape_argument(self.name, args.args.get(2))?;
+
+        let (input_array_ref, pattern_array_ref): (ArrayRef, ArrayRef) =
+            extract_input_and_pattern(&args.args[0], &args.args[1], num_rows)?;
+        let input_array: &StringArray = as_string_array(self.name, "str", &input_array_ref)?;
+        let pattern_array: &StringArray =
+            as_string_array(self.name, "pattern", &pattern_array_ref)?;
+
+        let mut builder = BooleanBuilder::with_capacity(num_rows);
+        let mut batch_metrics = BatchMetrics::start(self.name, num_rows, &self.metrics);
+        // Consecutive rows with the same pattern reuse the last regex.
+        let mut last: Option<(&str, Arc<Regex>)> = None;
+
+        for i in 0..num_rows {
+            if input_array.is_null(i) || pattern_array.is_null(i) {
+                builder.append_null();
+                continue;
+            }
+
+            let pattern: &str = pattern_array.value(i);
+            let compiled_regex = match &last {
+                Some((last_pattern, regex)) if *last_pattern == pattern => Arc::clone(regex),
+                _ => {
+                    let folded = fold_case(pattern, self.case_insensitive);
+                    let regex_pattern = like_to_regex(&folded, escape).map_err(|next| {
+                        RegexpError::InvalidEscape {
+                            function: self.name,
+                            pattern: pattern.to_string(),
+                            row: i,
+                            next,
+                        }
+                    })?;
+                    let regex = self.cache.get_or_compile(
+                        self.name,
+                        &regex_pattern,
+                        options(),
+                        i,
+                        &mut batch_metrics,
+                    )?;
+                    last = Some((pattern, Arc::clone(&regex)));
+                    regex
+                }
+            };
+            batch_metrics.rows_processed += 1;
+
+            let text = fold_case(input_array.value(i), self.case_insensitive);
+            let matched = compiled_regex.is_match(&text);
+            if matched {
+                batch_metrics.rows_matched += 1;
+            }
+            builder.append_value(matched);
+        }
+
+        Ok(ColumnarValue::Array(Arc::new(builder.finish())))
+    }
+}
+
+fn escape_argument(function: &'static str, arg: Option<&ColumnarValue>) -> Result<char> {
+    let escape = match arg {
+        None => return Ok(DEFAULT_ESCAPE),
+        Some(ColumnarValue::Scalar(ScalarValue::Utf8(Some(escape)))) => {
+            let mut chars = escape.chars();
+            chars.next().filter(|_| chars.next().is_none())
+        }
+        Some(_) => None,
+    };
+    escape.ok_or_else(|| {
+        RegexpError::UnexpectedInputType {
+            function,
+            argument: "escape",
+            expected: "a single-character STRING literal",
+            actual: arg.unwrap().data_type().to_string(),
+        }
+        .into()
+    })
+}
+
+/// Whether a multi-pattern LIKE needs any or all of its patterns to match.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum LikeQuantifier {
+    Any,
+    All,
+}
+
+/// Spark's `str [NOT] [I]LIKE ANY|ALL (p1, p2, ...)` as `like_any(str, p1,
+/// p2, ...)` and its siblings.
+///
+/// There must be at least one pattern; patterns must be literals and use the
+/// default escape character. NULL
+/// patterns follow Spark: `like_any` is true if some pattern matches, else
+/// NULL if some pattern is NULL, else false; `like_all` is false if some
+/// pattern does not match, else NULL if some pattern is NULL, else true. The
+/// `not_` variants negate each match instead of the result, so
+/// `not_like_any` is true when some pattern does not match.
+#[derive(Debug, Clone)]
+pub struct LikeMulti {
+    signature: Signature,
+    name: &'static str,
+    quantifier: LikeQuantifier,
+    negated: bool,
+    case_insensitive: bool,
+    cache: Arc<PatternCache>,
+    metrics: Arc<RegexpMetrics>,
+}
+
+impl LikeMulti {
+    pub fn new(quantifier: LikeQuantifier, negated: bool, case_insensitive: bool) -> Self {
+        let name = match (negated, case_insensitive, quantifier) {
+            (false, false, LikeQuantifier::Any) => "like_any",
+            (false, false, LikeQuantifier::All) => "like_all",
+            (true, false, LikeQuantifier::Any) => "not_like_any",
+            (true, false, LikeQuantifier::All) => "not_like_all",
+            (false, true, LikeQuantifier::Any) => "ilike_any",
+            (false, true, LikeQuantifier::All) => "ilike_all",
+            (true, true, LikeQuantifier::Any) => "not_ilike_any",
+            (true, true, LikeQuantifier::All) => "not_ilike_all",
+        };
+        Self {
+            signature: Signature::variadic(vec![DataType::Utf8], Volatility::Immutable),
+            name,
+            quantifier,
+            negated,
+            case_insensitive,
+            cache: Arc::new(PatternCache::default()),
+            metrics: Arc::new(RegexpMetrics::new()),
+        }
+    }
+
+    pub fn like_any() -> Self {
+        Self::new(LikeQuantifier::Any, false, false)
+    }
+
+    pub fn like_all() -> Self {
+        Self::new(LikeQuantifier::All, false, false)
+    }
+
+    pub fn not_like_any() -> Self {
+        Self::new(LikeQuantifier::Any, true, false)
+    }
+
+    pub fn not_like_all() -> Self {
+        Self::new(LikeQuantifier::All, true, false)
+    }
+
+    /// Every combination of quantifier, negation and case sensitivity.
+    pub fn all_variants() -> Vec<Self> {
+        let mut variants = Vec::new();
+        for case_insensitive in [false, true] {
+            for negated in [false, true] {
+                for quantifier in [LikeQuantifier::Any, LikeQuantifier::All] {
+                    variants.push(Self::new(quantifier, negated, case_insensitive));
+                }
+            }
+        }
+        variants
+    }
+
+    /// Execution metrics, shared by every clone of this instance.
+    pub fn metrics(&self) -> &RegexpMetrics {
+        &self.metrics
+    }
+
+    /// Rejects a call without patterns, which Spark's grammar does not allow.
+    fn check_arity(&self, num_args: usize) -> Result<()> {
+        if num_args < 2 {
+            return Err(RegexpError::WrongNumArgs {
+                function: self.name,
+                expected: "at least 2",
+                actual: num_args,
+            }
+            .into());
+        }
+        Ok(())
+    }
+
+    /// Translates the non-NULL patterns and compiles them into one set,
+    /// reporting an invalid escape against `row`.
+    fn compile(
+        &self,
+        patterns: &[&str],
+        row: usize,
+        batch_metrics: &mut BatchMetrics,
+    ) -> Result<Arc<RegexSet>> {
+        let regexes = patterns
+            .iter()
+            .map(|pattern| {
+                let folded = fold_case(pattern, self.case_insensitive);
+                like_to_regex(&folded, DEFAULT_ESCAPE).map_err(|next| RegexpError::InvalidEscape {
+                    function: self.name,
+                    pattern: pattern.to_string(),
+                    row,
+                    next,
+                })
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+        Ok(self
+            .cache
+            .get_or_compile_set(self.name, &regexes, options(), batch_metrics)?)
+    }
+
+    /// Combines per-pattern results following Spark's `LikeAllBase`.
+    fn evaluate(&self, matched: usize, patterns: usize, has_null: bool) -> Option<bool> {
+        // Patterns whose (possibly negated) test passed.
+        let passed = if self.negated {
+            patterns - matched
+        } else {
+            matched
+        };
+        match self.quantifier {
+            LikeQuantifier::Any if passed > 0 => Some(true),
+            LikeQuantifier::All if passed < patterns => Some(false),
+            _ if has_null => None,
+            LikeQuantifier::Any => Some(false),
+            LikeQuantifier::All => Some(true),
+        }
+    }
+}
+
+impl ScalarUDFImpl for LikeMulti {
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn name(&self) -> &str {
+        self.name
+    }
+
+    fn signature(&self) -> &Signature {
+        &self.signature
+    }
+
+    fn return_type(&self, arg_types: &[DataType]) -> Result<DataType> {
+        self.check_arity(arg_types.len())?;
+        Ok(DataType::Boolean)
+    }
+
+    fn invoke_with_args(&self, args: ScalarFunctionArgs) -> Result<ColumnarValue> {
+        let num_rows = args.number_rows;
+        self.check_arity(args.args.len())?;
+        let mut batch_metrics = BatchMetrics::start(self.name, num_rows, &self.metrics);
+
+        let mut patterns = Vec::with_capacity(args.args.len() - 1);
+        let mut has_null = false;
+        for arg in args.args.iter().skip(1) {
+            match arg {
+                ColumnarValue::Scalar(ScalarValue::Utf8(None)) => has_null = true,
+                ColumnarValue::Scalar(ScalarValue::Utf8(Some(pattern))) => {
+                    patterns.push(pattern.as_str())
+                }
+                other => {
+                    return Err(RegexpError::UnexpectedInputType {
+                        function: self.name,
+                        argument: "patterns",
+                        expected: "STRING literals",
+                        actual: other.data_type().to_string(),
+                    }
+                    .into());
+                }
+            }
+        }
+
+        let input_array_ref: ArrayRef = to_array(&args.args[0], num_rows)?;
+        let input_array: &StringArray = as_string_array(self.name, "str", &input_array_ref)?;
+        let mut builder = BooleanBuilder::with_capacity(num_rows);
+        // Compiled at the first row that needs it, so that an invalid escape
+        // is reported against that row.
+        let mut compiled: Option<Arc<RegexSet>> = None;
+
+        for i in 0..num_rows {
+            if input_array.is_null(i) {
+                builder.append_null();
+                continue;
+            }
+            if compiled.is_none() {
+                compiled = Some(self.compile(&patterns, i, &mut batch_metrics)?);
+            }
+            let set = compiled.as_ref().unwrap();
+            batch_metrics.rows_processed += 1;
+
+            let text = fold_case(input_array.value(i), self.case_insensitive);
+            let matched = set.matches(&text).iter().count();
+            let result = self.evaluate(matched, patterns.len(), has_null);
+            if result == Some(true) {
+                batch_metrics.rows_matched += 1;
+            }
+            builder.append_option(result);
+        }
+
+        Ok(ColumnarValue::Array(Arc::new(builder.finish())))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use arrow_array::BooleanArray;
+    use datafusion_common::arrow::datatypes::Field;
+
+    fn invoke(udf: &dyn ScalarUDFImpl, args: Vec<ColumnarValue>) -> Result<Vec<Option<bool>>> {
+        let num_rows = match &args[0] {
+            ColumnarValue::Array(array) => array.len(),
+            ColumnarValue::Scalar(_) => 1,
+        };
+        let args = ScalarFunctionArgs {
+            args,
+            number_rows: num_rows,
+            arg_fields: vec![],
+            return_field: Arc::new(Field::new("result", DataType::Boolean, true)),
+        };
+        let array = udf.invoke_with_args(args)?.to_array(num_rows)?;
+        let booleans = array.as_any().downcast_ref::<BooleanArray>().unwrap();
+        Ok(booleans.iter().collect())
+    }
+
+    fn strings(values: Vec<Option<&str>>) -> ColumnarValue {
+        ColumnarValue::Array(Arc::new(StringArray::from(values)))
+    }
+
+    fn literal(value: Option<&str>) -> ColumnarValue {
+        ColumnarValue::Scalar(ScalarValue::Utf8(value.map(str::to_string)))
+    }
+
+    #[test]
+    fn test_like_to_regex_escapes() {
+        assert_eq!(like_to_regex("a_%", '\\').unwrap(), r"(?s)^a..*\z");
+        assert_eq!(like_to_regex(r"100\%", '\\').unwrap(), r"(?s)^100%\z");
+        assert_eq!(like_to_regex("a.b//", '/').unwrap(), r"(?s)^a\.b/\z");
+        assert_eq!(like_to_regex("/a", '/'), Err(Some('a')));
+        assert_eq!(like_to_regex("a/", '/'), Err(None));
+    }
+
+    #[test]
+    fn test_like_matches_whole_input() {
+        let result = invoke(
+            &Like::new(),
+            vec![
+                strings(vec![Some("abc"), Some("xabc"), Some("a\nc"), None]),
+                literal(Some("a_c")),
+            ],
+        )
+        .unwrap();
+        assert_eq!(result, vec![Some(true), Some(false), Some(true), None]);
+    }
+
+    #[test]
+    fn test_like_custom_escape() {
+        let result = invoke(
+            &Like::new(),
+            vec![
+                strings(vec![Some("50%"), Some("50x")]),
+                literal(Some("50/%")),
+                literal(Some("/")),
+            ],
+        )
+        .unwrap();
+        assert_eq!(result, vec![Some(true), Some(false)]);
+    }
+
+    #[test]
+    fn test_like_invalid_escape_errors() {
+        let err = invoke(
+            &Like::new(),
+            vec![
+                strings(vec![Some("x")]),
+                literal(Some("/x")),
+                literal(Some("/")),
+            ],
+        )
+        .unwrap_err();
+        let err = RegexpError::find(&err).unwrap();
+        assert_eq!(err.error_class(), "INVALID_FORMAT.ESC_IN_THE_MIDDLE");
+
+        let err = invoke(
+            &Like::new(),
+            vec![
+                strings(vec![Some("x")]),
+                literal(Some("x")),
+                literal(Some("//")),
+            ],
+        )
+        .unwrap_err();
+        assert_eq!(
+            RegexpError::find(&err).unwrap().error_class(),
+            "DATATYPE_MISMATCH.UNEXPECTED_INPUT_TYPE"
+        );
+    }
+
+    #[test]
+    fn test_ilike_lowercases_both_sides() {
+        let result = invoke(
+            &Like::ilike(),
+            vec![strings(vec![Some("Spark SQL")]), literal(Some("%sql"))],
+        )
+        .unwrap();
+        assert_eq!(result, vec![Some(true)]);
+    }
+
+    #[test]
+    fn test_like_any_and_all() {
+        let input = || strings(vec![Some("foo bar"), Some("foo"), Some("baz"), None]);
+        let any = invoke(
+            &LikeMulti::like_any(),
+            vec![input(), literal(Some("%bar")), literal(Some("foo%"))],
+        )
+        .unwrap();
+        assert_eq!(any, vec![Some(true), Some(true), Some(false), None]);
+
+        let all = invoke(
+            &LikeMulti::like_all(),
+            vec![input(), literal(Some("%bar")), literal(Some("foo%"))],
+        )
+        .unwrap();
+        assert_eq!(all, vec![Some(true), Some(false), Some(false), None]);
+
+        let not_any = invoke(
+            &LikeMulti::not_like_any(),
+            vec![input(), literal(Some("%bar")), literal(Some("foo%"))],
+        )
+        .unwrap();
+        assert_eq!(not_any, vec![Some(false), Some(true), Some(true), None]);
+
+        let not_all = invoke(
+            &LikeMulti::not_like_all(),
+            vec![input(), literal(Some("%bar")), literal(Some("foo%"))],
+        )
+        .unwrap();
+        assert_eq!(not_all, vec![Some(false), Some(false), Some(true), None]);
+    }
+
+    #[test]
+    fn test_like_any_null_pattern() {
+        let input = || strings(vec![Some("foo"), Some("bar")]);
+        let any = invoke(
+            &LikeMulti::like_any(),
+            vec![input(), literal(Some("f%")), literal(None)],
+        )
+        .unwrap();
+        assert_eq!(any, vec![Some(true), None]);
+
+        let all = invoke(
+            &LikeMulti::like_all(),
+            vec![input(), literal(Some("f%")), literal(None)],
+        )
+        .unwrap();
+        assert_eq!(all, vec![None, Some(false)]);
+    }
+
+    #[test]
+    fn test_like_any_invalid_escape_reports_first_evaluated_row() {
+        let err = invoke(
+            &LikeMulti::like_any(),
+            vec![
+                strings(vec![None, Some("x")]),
+                literal(Some("x%")),
+                literal(Some("x\\")),
+            ],
+        )
+        .unwrap_err();
+        let err = RegexpError::find(&err).unwrap();
+        assert_eq!(err.error_class(), "INVALID_FORMAT.ESC_AT_THE_END");
+        assert_eq!(err.row(), Some(1));
+    }
+
+    #[test]
+    fn test_like_any_requires_a_pattern() {
+        let udf = LikeMulti::like_any();
+        let err = invoke(&udf, vec![strings(vec![Some("x")])]).unwrap_err();
+        assert_eq!(
+            RegexpError::find(&err).unwrap().error_class(),
+            "WRONG_NUM_ARGS.WITHOUT_SUGGESTION"
+        );
+        assert!(udf.return_type(&[DataType::Utf8]).is_err());
+    }
+}
@@ -4,9 +4,9 @@ use datafusion::arrow::array::StringArray;
 use datafusion::arrow::datatypes::{DataType, Field, Schema};
 use datafusion::arrow::record_batch::RecordBatch;
 use datafusion::datasource::MemTable;
+use datafusion::execution::FunctionRegistry;
 use datafusion::prelude::*;
-use datafusion_expr::ScalarUDF;
-use regexp_extract_datafusion::regexp_extract::RegexpExtract;
+use regexp_extract_datafusion::registry::register_all;
 use std::sync::Arc;
 
 #[tokio::main]
@@ -16,9 +16,9 @@ async fn main() -> datafusion::error::Result<()> {
     // 1. Create a SessionContext
     let ctx = SessionContext::new();
 
-    // 2. Create and register the User Defined Function
-    let udf = ScalarUDF::new_from_impl(RegexpExtract::new());
-    ctx.register_udf(udf.clone());
+    // 2. Register the crate's functions and look up `regexp_extract`
+    register_all(&ctx)?;
+    let udf = ctx.udf("regexp_extract")?;
 
     // 3. Create a MemTable with some sample data
     let schema = Arc::new(Schema::new(vec![Field::new(
@@ -0,0 +1,180 @@
+//! Execution metrics shared by the regex functions in this crate.
+//!
+//! Each function instance owns an `Arc<RegexpMetrics>`. Counters are collected
+//! per batch in a [`BatchMetrics`], folded into the shared atomics when it is
+//! dropped and recorded on a `regexp_batch` tracing span, so a batch that
+//! fails on a row still counts the work done before the error.
+
+use std::sync::Arc;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::{Duration, Instant};
+
+use tracing::span::EnteredSpan;
+
+#[derive(Debug, Default)]
+pub struct RegexpMetrics {
+    rows_processed: AtomicU64,
+    rows_matched: AtomicU64,
+    compilations: AtomicU64,
+    cache_hits: AtomicU64,
+    cache_misses: AtomicU64,
+    compile_nanos: AtomicU64,
+    match_nanos: AtomicU64,
+}
+
+/// A point-in-time copy of [`RegexpMetrics`].
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct MetricsSnapshot {
+    pub rows_processed: u64,
+    pub rows_matched: u64,
+    pub compilations: u64,
+    pub cache_hits: u64,
+    pub cache_misses: u64,
+    pub compile_time: Duration,
+    /// Time spent in batches outside pattern compilation. Besides matching,
+    /// this includes reading the inputs and building the output, so it is an
+    /// upper bound on the time spent matching.
+    pub match_time: Duration,
+}
+
+impl RegexpMetrics {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn snapshot(&self) -> MetricsSnapshot {
+        MetricsSnapshot {
+            rows_processed: self.rows_processed.load(Ordering::Relaxed),
+            rows_matched: self.rows_matched.load(Ordering::Relaxed),
+            compilations: self.compilations.load(Ordering::Relaxed),
+            cache_hits: self.cache_hits.load(Ordering::Relaxed),
+            cache_misses: self.cache_misses.load(Ordering::Relaxed),
+            compile_time: Duration::from_nanos(self.compile_nanos.load(Ordering::Relaxed)),
+            match_time: Duration::from_nanos(self.match_nanos.load(Ordering::Relaxed)),
+        }
+    }
+
+    pub fn reset(&self) {
+        for counter in [
+            &self.rows_processed,
+            &self.rows_matched,
+            &self.compilations,
+            &self.cache_hits,
+            &self.cache_misses,
+            &self.compile_nanos,
+            &self.match_nanos,
+        ] {
+            counter.store(0, Ordering::Relaxed);
+        }
+    }
+
+    fn add(&self, batch: &BatchMetrics) {
+        self.rows_processed
+            .fetch_add(batch.rows_processed, Ordering::Relaxed);
+        self.rows_matched
+            .fetch_add(batch.rows_matched, Ordering::Relaxed);
+        self.compilations
+            .fetch_add(batch.compilations, Ordering::Relaxed);
+        self.cache_hits
+            .fetch_add(batch.cache_hits, Ordering::Relaxed);
+        self.cache_misses
+            .fetch_add(batch.cache_misses, Ordering::Relaxed);
+        self.compile_nanos
+            .fetch_add(batch.compile_time.as_nanos() as u64, Ordering::Relaxed);
+        self.match_nanos
+            .fetch_add(batch.match_time().as_nanos() as u64, Ordering::Relaxed);
+    }
+}
+
+/// Counters for a single batch, kept off the shared atomics while rows are
+/// being processed and folded into them when the batch is dropped.
+#[derive(Debug)]
+pub struct BatchMetrics {
+    span: EnteredSpan,
+    started: Instant,
+    metrics: Arc<RegexpMetrics>,
+    pub rows_processed: u64,
+    pub rows_matched: u64,
+    pub compilations: u64,
+    pub cache_hits: u64,
+    pub cache_misses: u64,
+    pub compile_time: Duration,
+}
+
+impl BatchMetrics {
+    /// Opens and enters the `regexp_batch` span for one invocation of
+    /// `function`, whose counters go to `metrics`.
+    pub fn start(function: &'static str, num_rows: usize, metrics: &Arc<RegexpMetrics>) -> Self {
+        let span = tracing::debug_span!(
+            "regexp_batch",
+            function,
+            num_rows,
+            rows_matched = tracing::field::Empty,
+            compilations = tracing::field::Empty,
+            cache_hits = tracing::field::Empty,
+            cache_misses = tracing::field::Empty,
+            compile_us = tracing::field::Empty,
+            match_us = tracing::field::Empty,
+        )
+        .entered();
+        Self {
+            span,
+            started: Instant::now(),
+            metrics: Arc::clone(metrics),
+            rows_processed: 0,
+            rows_matched: 0,
+            compilations: 0,
+            cache_hits: 0,
+            cache_misses: 0,
+            compile_time: Duration::ZERO,
+        }
+    }
+
+    /// Time spent in the batch that was not spent compiling patterns; see
+    /// [`MetricsSnapshot::match_time`].
+    fn match_time(&self) -> Duration {
+        self.started.elapsed().saturating_sub(self.compile_time)
+    }
+}
+
+impl Drop for BatchMetrics {
+    /// Records the batch on its span and folds it into the function's
+    /// metrics, whether the batch finished or returned an error.
+    fn drop(&mut self) {
+        self.metrics.add(self);
+        self.span.record("rows_matched", self.rows_matched);
+        self.span.record("compilations", self.compilations);
+        self.span.record("cache_hits", self.cache_hits);
+        self.span.record("cache_misses", self.cache_misses);
+        self.span
+            .record("compile_us", self.compile_time.as_micros() as u64);
+        self.span
+            .record("match_us", self.match_time().as_micros() as u64);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_batches_accumulate_and_reset() {
+        let metrics = Arc::new(RegexpMetrics::new());
+        for _ in 0..2 {
+            let mut batch = BatchMetrics::start("regexp_extract", 3, &metrics);
+            batch.rows_processed += 3;
+            batch.rows_matched += 2;
+            batch.cache_misses += 1;
+            batch.compilations += 1;
+        }
+
+        let snapshot = metrics.snapshot();
+        assert_eq!(snapshot.rows_processed, 6);
+        assert_eq!(snapshot.rows_matched, 4);
+        assert_eq!(snapshot.compilations, 2);
+        assert_eq!(snapshot.cache_misses, 2);
+
+        metrics.reset();
+        assert_eq!(metrics.snapshot(), MetricsSnapshot::default());
+    }
+}
@@ -0,0 +1,563 @@
+use std::any::Any;
+use std::net::Ipv6Addr;
+use std::sync::Arc;
+
+use arrow_array::builder::StringBuilder;
+use arrow_array::{Array, ArrayRef, StringArray};
+use datafusion_common::Result;
+use datafusion_common::arrow::datatypes::DataType;
+use datafusion_expr::{
+    ColumnarValue, ScalarFunctionArgs, ScalarUDFImpl, Signature, TypeSignature, Volatility,
+};
+use regex::Regex;
+
+use crate::args::{as_string_array, to_array};
+use crate::error::RegexpError;
+use crate::metrics::{BatchMetrics, RegexpMetrics};
+use crate::pattern::{PatternCache, PatternOptions};
+
+/// Spark's `parse_url(url, partToExtract[, key])`: one part of `url`, parsed
+/// the way `java.net.URI` parses it.
+///
+/// `partToExtract` is one of `HOST`, `PATH`, `QUERY`, `REF`, `PROTOCOL`,
+/// `FILE`, `AUTHORITY` and `USERINFO`, in upper case; any other value yields
+/// NULL. Parts are returned raw, without decoding `%` escapes. With `key`,
+/// which only applies to `QUERY`, the result is the value of the first
+/// `key=value` pair of the query. As in Spark, `key` is a Java regex, so keys
+/// containing regex metacharacters must be quoted.
+///
+/// A URL that `java.net.URI` rejects yields NULL, or an `INVALID_URL` error
+/// [in ANSI mode](Self::with_ansi_mode). `try_parse_url` never raises it. A
+/// NULL argument yields NULL.
+#[derive(Debug, Clone)]
+pub struct ParseUrl {
+    signature: Signature,
+    name: &'static str,
+    ansi_mode: bool,
+    cache: Arc<PatternCache>,
+    metrics: Arc<RegexpMetrics>,
+}
+
+impl Default for ParseUrl {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl ParseUrl {
+    /// `parse_url`, returning NULL for an invalid URL until
+    /// [`with_ansi_mode`](Self::with_ansi_mode) is set.
+    pub fn new() -> Self {
+        Self {
+            signature: Signature::one_of(
+                vec![
+                    TypeSignature::Exact(vec![DataType::Utf8, DataType::Utf8]),
+                    TypeSignature::Exact(vec![DataType::Utf8, DataType::Utf8, DataType::Utf8]),
+                ],
+                Volatility::Immutable,
+            ),
+            name: "parse_url",
+            ansi_mode: false,
+            cache: Arc::new(PatternCache::default()),
+            metrics: Arc::new(RegexpMetrics::new()),
+        }
+    }
+
+    /// `try_parse_url`, which always returns NULL for an invalid URL.
+    pub fn try_parse_url() -> Self {
+        Self {
+            name: "try_parse_url",
+            ..Self::new()
+        }
+    }
+
+    /// Whether an invalid URL is an error, as under `spark.sql.ansi.enabled`.
+    /// Has no effect on `try_parse_url`.
+    pub fn with_ansi_mode(mut self, ansi_mode: bool) -> Self {
+        self.ansi_mode = ansi_mode && self.name == "parse_url";
+        self
+    }
+
+    /// Execution metrics, shared by every clone of this instance.
+    pub fn metrics(&self) -> &RegexpMetrics {
+        &self.metrics
+    }
+}
+
+impl ScalarUDFImpl for ParseUrl {
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn name(&self) -> &str {
+        self.name
+    }
+
+    fn signature(&self) -> &Signature {
+        &self.signature
+    }
+
+    fn return_type(&self, _arg_types: &[DataType]) -> Result<DataType> {
+        Ok(DataType::Utf8)
+    }
+
+    fn invoke_with_args(&self, args: ScalarFunctionArgs) -> Result<ColumnarValue> {
+        let num_rows = args.number_rows;
+        let url_array_ref: ArrayRef = to_array(&args.args[0], num_rows)?;
+        let part_array_ref: ArrayRef = to_array(&args.args[1], num_rows)?;
+        let key_array_ref: Option<ArrayRef> = args
+            .args
+            .get(2)
+            .map(|arg| to_array(arg, num_rows))
+            .transpose()?;
+        let url_array: &StringArray = as_string_array(self.name, "url", &url_array_ref)?;
+        let part_array: &StringArray =
+            as_string_array(self.name, "partToExtract", &part_array_ref)?;
+        let key_array: Option<&StringArray> = key_array_ref
+            .as_ref()
+            .map(|array| as_string_array(self.name, "key", array))
+            .transpose()?;
+
+        let mut string_builder = StringBuilder::new();
+        let mut batch_metrics = BatchMetrics::start(self.name, num_rows, &self.metrics);
+        let mut last_key: Option<(&str, Arc<Regex>)> = None;
+
+        for i in 0..num_rows {
+            if url_array.is_null(i)
+                || part_array.is_null(i)
+                || key_array.is_some_and(|array| array.is_null(i))
+            {
+                string_builder.append_null();
+                continue;
+            }
+            batch_metrics.rows_processed += 1;
+
+            let url = url_array.value(i);
+            let part = part_array.value(i);
+            let Some(uri) = JavaUri::parse(url) else {
+                if self.ansi_mode {
+                    return Err(RegexpError::InvalidUrl {
+                        function: self.name,
+                        url: url.to_string(),
+                        row: i,
+                    }
+                    .into());
+                }
+                string_builder.append_null();
+                continue;
+            };
+
+            let value = match key_array {
+                None => uri.part(part),
+                Some(_) if part != "QUERY" => None,
+                Some(array) => {
+                    let key = array.value(i);
+                    if last_key.as_ref().is_none_or(|(last, _)| *last != key) {
+                        let regex = self.cache.get_or_compile(
+                            self.name,
+                            &format!("(&|^){key}=([^&]*)"),
+                            PatternOptions::default(),
+                            i,
+                            &mut batch_metrics,
+                        )?;
+                        last_key = Some((key, regex));
+                    }
+                    let (_, regex) = last_key.as_ref().unwrap();
+                    uri.query
+                        .and_then(|query| regex.captures(query))
+                        .and_then(|captures| captures.get(2))
+                        .map(|m| m.as_str().into())
+                }
+            };
+            match value {
+                Some(value) => {
+                    batch_metrics.rows_matched += 1;
+                    string_builder.append_value(value);
+                }
+                None => string_builder.append_null(),
+            }
+        }
+
+        Ok(ColumnarValue::Array(Arc::new(string_builder.finish())))
+    }
+}
+
+/// The raw components of a URI, as `java.net.URI` reports them.
+///
+/// The parser follows `java.net.URI`'s RFC 2396 grammar, including its
+/// deviations: an authority that is not a valid `[userinfo@]host[:port]` is
+/// kept as a registry-based authority without a host, and an empty authority
+/// is allowed before a path, query or fragment.
+#[derive(Debug, Default, PartialEq, Eq)]
+struct JavaUri<'a> {
+    scheme: Option<&'a str>,
+    authority: Option<&'a str>,
+    user_info: Option<&'a str>,
+    host: Option<&'a str>,
+    /// `None` for opaque URIs such as `mailto:a@b.c`, `""` for an empty path.
+    path: Option<&'a str>,
+    query: Option<&'a str>,
+    fragment: Option<&'a str>,
+}
+
+const MARK: &str = "-_.!~*'()";
+const RESERVED: &str = ";/?:@&=+$,[]";
+
+fn is_unreserved(c: char) -> bool {
+    c.is_ascii_alphanumeric() || MARK.contains(c)
+}
+
+fn is_uric(c: char) -> bool {
+    is_unreserved(c) || RESERVED.contains(c)
+}
+
+fn is_path(c: char) -> bool {
+    is_unreserved(c) || ":@&=+$,;/".contains(c)
+}
+
+fn is_user_info(c: char) -> bool {
+    is_unreserved(c) || ";:&=+$,".contains(c)
+}
+
+fn is_reg_name(c: char) -> bool {
+    is_unreserved(c) || "$,;:@&=+".contains(c)
+}
+
+fn is_server(c: char) -> bool {
+    is_user_info(c) || ".:@[]".contains(c)
+}
+
+/// Whether every character of `s` is in `class`, an `%XX` escape or, like
+/// RFC 2396's "other" category in `java.net.URI`, a non-ASCII character
+/// that is neither a space nor a control character.
+fn all_chars(s: &str, class: fn(char) -> bool) -> bool {
+    let mut chars = s.chars();
+    while let Some(c) = chars.next() {
+        let valid = match c {
+            '%' => (0..2).all(|_| chars.next().is_some_and(|h| h.is_ascii_hexdigit())),
+            c if c.is_ascii() => class(c),
+            c => !c.is_whitespace() && !c.is_control(),
+        };
+        if !valid {
+            return false;
+        }
+    }
+    true
+}
+
+impl<'a> JavaUri<'a> {
+    fn parse(input: &'a str) -> Option<Self> {
+        let mut uri = JavaUri::default();
+        let end_of_scheme = input.find(['/', '?', '#', ':']);
+        let rest = match end_of_scheme {
+            Some(colon) if input[colon..].starts_with(':') => {
+                let scheme = &input[..colon];
+                let mut chars = scheme.chars();
+                if !chars.next()?.is_ascii_alphabetic()
+                    || !chars.all(|c| c.is_ascii_alphanumeric() || "+-.".contains(c))
+                {
+                    return None;
+                }
+                uri.scheme = Some(scheme);
+                &input[colon + 1..]
+            }
+            _ => input,
+        };
+
+        let (ssp, fragment) = match rest.split_once('#') {
+            Some((ssp, fragment)) => (ssp, Some(fragment)),
+            None => (rest, None),
+        };
+        if uri.scheme.is_some() && !ssp.starts_with('/') {
+            // An opaque URI such as `mailto:a@b.c`.
+            if ssp.is_empty() || !all_chars(ssp, is_uric) {
+                return None;
+            }
+        } else {
+            uri.parse_hierarchical(ssp, fragment.is_some())?;
+        }
+        if let Some(fragment) = fragment {
+            if !all_chars(fragment, is_uric) {
+                return None;
+            }
+            uri.fragment = Some(fragment);
+        }
+        Some(uri)
+    }
+
+    fn parse_hierarchical(&mut self, ssp: &'a str, has_fragment: bool) -> Option<()> {
+        let mut rest = ssp;
+        if let Some(after) = rest.strip_prefix("//") {
+            let end = after.find(['/', '?']).unwrap_or(after.len());
+            if end > 0 {
+                self.parse_authority(&after[..end])?;
+            } else if end == after.len() && !has_fragment {
+                // `http://` has nothing after the empty authority.
+                return None;
+            }
+            rest = &after[end..];
+        }
+        let (path, query) = match rest.split_once('?') {
+            Some((path, query)) => (path, Some(query)),
+            None => (rest, None),
+        };
+        if !all_chars(path, is_path) || !query.is_none_or(|query| all_chars(query, is_uric)) {
+            return None;
+        }
+        self.path = Some(path);
+        self.query = query;
+        Some(())
+    }
+
+    fn parse_authority(&mut self, authority: &'a str) -> Option<()> {
+        let server_chars = if authority.contains(']') {
+            all_chars(authority, |c| is_server(c) || c == '%')
+        } else {
+            all_chars(authority, is_server)
+        };
+        let reg_chars = all_chars(authority, is_reg_name);
+        if server_chars && self.parse_server(authority).is_some() {
+            self.authority = Some(authority);
+            return Some(());
+        }
+        self.user_info = None;
+        self.host = None;
+        reg_chars.then(|| self.authority = Some(authority))
+    }
+
+    fn parse_server(&mut self, authority: &'a str) -> Option<()> {
+        let mut rest = authority;
+        if let Some((user_info, after)) = rest.split_once('@') {
+            if !all_chars(user_info, is_user_info) {
+                return None;
+            }
+            self.user_info = Some(user_info);
+            rest = after;
+        }
+
+        let port = if let Some(bracketed) = rest.strip_prefix('[') {
+            let close = bracketed.find(']').filter(|&close| close > 0)?;
+            let (address, scope) = match bracketed[..close].split_once('%') {
+                Some((address, scope)) => (address, Some(scope)),
+                None => (&bracketed[..close], None),
+            };
+            address.parse::<Ipv6Addr>().ok()?;
+            if scope.is_some_and(|scope| {
+                scope.is_empty()
+                    || !scope
+                        .chars()
+                        .all(|c| c.is_ascii_alphanumeric() || "_.".contains(c))
+            }) {
+                return None;
+            }
+            self.host = Some(&rest[..close + 2]);
+            &bracketed[close + 1..]
+        } else {
+            let end = rest.find(':').unwrap_or(rest.len());
+            let host = &rest[..end];
+            if !is_ipv4(host) && !is_hostname(host) {
+                return None;
+            }
+            self.host = Some(host);
+            &rest[end..]
+        };
+
+        match port.strip_prefix(':') {
+            None if port.is_empty() => Some(()),
+            Some(digits) if digits.chars().all(|c| c.is_ascii_digit()) => {
+                (digits.is_empty() || digits.parse::<i32>().is_ok()).then_some(())
+            }
+            _ => None,
+        }
+    }
+
+    /// `partToExtract` of the URI, or `None` for an unknown part.
+    fn part(&self, part: &str) -> Option<String> {
+        let value = match part {
+            "HOST" => self.host,
+            "PATH" => self.path,
+            "QUERY" => self.query,
+            "REF" => self.fragment,
+            "PROTOCOL" => self.scheme,
+            "FILE" => {
+                return match self.query {
+                    Some(query) => self.path.map(|path| format!("{path}?{query}")),
+                    None => self.path.map(str::to_string),
+                };
+            }
+            "AUTHORITY" => self.authority,
+            "USERINFO" => self.user_info,
+            _ => None,
+        };
+        value.map(str::to_string)
+    }
+}
+
+/// A dotted-quad address with four decimal octets up to 255.
+fn is_ipv4(host: &str) -> bool {
+    let octets: Vec<&str> = host.split('.').collect();
+    octets.len() == 4
+        && octets.iter().all(|octet| {
+            !octet.is_empty()
+                && octet.chars().all(|c| c.is_ascii_digit())
+                && octet.parse::<u16>().is_ok_and(|value| value <= 255)
+        })
+}
+
+/// Dot-separated labels of letters, digits and inner dashes. When there is
+/// more than one label, the last must start with a letter. A trailing dot is
+/// allowed.
+fn is_hostname(host: &str) -> bool {
+    let labels: Vec<&str> = host.strip_suffix('.').unwrap_or(host).split('.').collect();
+    let valid_label = |label: &&str| {
+        label.starts_with(|c: char| c.is_ascii_alphanumeric())
+            && !label.ends_with('-')
+            && label.chars().all(|c| c.is_ascii_alphanumeric() || c == '-')
+    };
+    labels.iter().all(valid_label)
+        && (labels.len() == 1
+            || labels[labels.len() - 1].starts_with(|c: char| c.is_ascii_alphabetic()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use arrow_array::cast::AsArray;
+    use datafusion_common::ScalarValue;
+    use datafusion_common::arrow::datatypes::Field;
+
+    fn run_test(
+        udf: &ParseUrl,
+        url: &str,
+        part: &str,
+        key: Option<&str>,
+    ) -> Result<Option<String>> {
+        let mut args = vec![
+            ColumnarValue::Array(Arc::new(StringArray::from(vec![url]))),
+            ColumnarValue::Scalar(ScalarValue::from(part)),
+        ];
+        if let Some(key) = key {
+            args.push(ColumnarValue::Scalar(ScalarValue::from(key)));
+        }
+        let args = ScalarFunctionArgs {
+            args,
+            number_rows: 1,
+            arg_fields: vec![],
+            return_field: Arc::new(Field::new("result", DataType::Utf8, true)),
+        };
+        let array = udf.invoke_with_args(args)?.to_array(1)?;
+        Ok(array
+            .as_string::<i32>()
+            .iter()
+            .next()
+            .unwrap()
+            .map(str::to_string))
+    }
+
+    fn parse_url(url: &str, part: &str) -> Option<String> {
+        run_test(&ParseUrl::new(), url, part, None).unwrap()
+    }
+
+    #[test]
+    fn test_all_parts() {
+        let url = "https://user:pw@www.example.com:8080/a/b%20c.html?x=1&y=2#frag";
+        let parts = [
+            ("HOST", Some("www.example.com")),
+            ("PATH", Some("/a/b%20c.html")),
+            ("QUERY", Some("x=1&y=2")),
+            ("REF", Some("frag")),
+            ("PROTOCOL", Some("https")),
+            ("FILE", Some("/a/b%20c.html?x=1&y=2")),
+            ("AUTHORITY", Some("user:pw@www.example.com:8080")),
+            ("USERINFO", Some("user:pw")),
+            ("host", None),
+            ("PORT", None),
+        ];
+        for (part, expected) in parts {
+            assert_eq!(parse_url(url, part).as_deref(), expected, "{part}");
+        }
+    }
+
+    #[test]
+    fn test_query_key() {
+        let udf = ParseUrl::new();
+        let url = "http://spark.apache.org/path?query=1&k2=&query=3";
+        let value = |key: &str| run_test(&udf, url, "QUERY", Some(key)).unwrap();
+        assert_eq!(value("query").as_deref(), Some("1"));
+        assert_eq!(value("k2").as_deref(), Some(""));
+        assert_eq!(value("k3"), None);
+        // The key is a regex, as in Spark.
+        assert_eq!(value("k.").as_deref(), Some(""));
+        assert_eq!(
+            run_test(&udf, url, "HOST", Some("query")).unwrap(),
+            None,
+            "a key only applies to QUERY"
+        );
+    }
+
+    #[test]
+    fn test_java_uri_edge_cases() {
+        // Empty and missing components.
+        assert_eq!(parse_url("http://host", "PATH").as_deref(), Some(""));
+        assert_eq!(
+            parse_url("http://host?a=1", "FILE").as_deref(),
+            Some("?a=1")
+        );
+        assert_eq!(parse_url("file:///etc/hosts", "AUTHORITY"), None);
+        assert_eq!(
+            parse_url("file:///etc/hosts", "PATH").as_deref(),
+            Some("/etc/hosts")
+        );
+        assert_eq!(parse_url("/relative/path?q", "QUERY").as_deref(), Some("q"));
+        // Opaque URIs have no path.
+        assert_eq!(
+            parse_url("mailto:a@b.com", "PROTOCOL").as_deref(),
+            Some("mailto")
+        );
+        assert_eq!(parse_url("mailto:a@b.com", "PATH"), None);
+        // IPv6 hosts keep their brackets.
+        assert_eq!(
+            parse_url("http://[::1]:80/", "HOST").as_deref(),
+            Some("[::1]")
+        );
+        // Registry-based authorities have no host.
+        assert_eq!(parse_url("http://a_b.com/x", "HOST"), None);
+        assert_eq!(
+            parse_url("http://a_b.com/x", "AUTHORITY").as_deref(),
+            Some("a_b.com")
+        );
+        assert_eq!(parse_url("http://1.2.3.999/", "HOST"), None);
+        assert_eq!(parse_url("http://host:abc/", "HOST"), None);
+        assert_eq!(
+            parse_url("http://1.2.3.4:80/", "HOST").as_deref(),
+            Some("1.2.3.4")
+        );
+    }
+
+    #[test]
+    fn test_invalid_urls() {
+        for url in [
+            "http://",
+            "http://host/a b",
+            "http://host/%zz",
+            "1http://host",
+            "http://host/#a#b",
+            "http://[::1/",
+            "http://host/<script>",
+        ] {
+            assert_eq!(parse_url(url, "HOST"), None, "{url}");
+        }
+
+        let ansi = ParseUrl::new().with_ansi_mode(true);
+        let err = run_test(&ansi, "http://host/a b", "HOST", None).unwrap_err();
+        let err = RegexpError::find(&err).unwrap();
+        assert_eq!(err.error_class(), "INVALID_URL");
+        assert_eq!(err.sql_state(), "22P02");
+
+        let try_parse_url = ParseUrl::try_parse_url().with_ansi_mode(true);
+        assert_eq!(
+            run_test(&try_parse_url, "http://host/a b", "HOST", None).unwrap(),
+            None
+        );
+    }
+}
@@ -0,0 +1,537 @@
+//! Compiled-pattern handling shared by the regex functions in this crate.
+//!
+//! Patterns are compiled once and kept in a bounded [`PatternCache`] that
+//! lives as long as the function instance, so literal patterns are compiled
+//! once per query rather than once per row. Entries are keyed by the pattern
+//! and the [`PatternOptions`] it was compiled with.
+
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use std::time::Instant;
+
+use regex::{Captures, Match, Regex, RegexBuilder, RegexSet, RegexSetBuilder};
+
+use crate::collation::Collation;
+use crate::config::RegexpConfig;
+use crate::dialect::{RegexDialect, Translation, translate, translate_for_matching};
+use crate::error::RegexpError;
+use crate::metrics::BatchMetrics;
+
+pub const DEFAULT_CACHE_CAPACITY: usize = 128;
+
+/// Everything besides the pattern text that affects how it is compiled.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub struct PatternOptions {
+    pub collation: Collation,
+    pub dialect: RegexDialect,
+    /// The compiled size limit in bytes; `None` keeps the `regex` crate's.
+    pub size_limit: Option<usize>,
+    /// Whether the caller only asks whether the pattern matches, which lets
+    /// Java's `$` and `\Z` through; see [`translate_for_matching`].
+    pub match_only: bool,
+}
+
+impl PatternOptions {
+    /// These options with the keys set in the session's `config` applied.
+    pub fn with_config(self, config: &RegexpConfig) -> Self {
+        Self {
+            collation: config.collation.unwrap_or(self.collation),
+            dialect: config.dialect.unwrap_or(self.dialect),
+            size_limit: config.size_limit.or(self.size_limit),
+            ..self
+        }
+    }
+
+    /// `pattern` as it is handed to the regex compiler: translated from its
+    /// dialect and prepared for the collation, with the translation notes.
+    pub fn translate(&self, pattern: &str) -> Result<Translation, String> {
+        let mut translated = if self.match_only {
+            translate_for_matching(pattern, self.dialect)?
+        } else {
+            translate(pattern, self.dialect)?
+        };
+        translated.pattern = self
+            .collation
+            .prepare_pattern(&translated.pattern)
+            .into_owned();
+        Ok(translated)
+    }
+
+    fn prepare(&self, pattern: &str) -> Result<String, String> {
+        Ok(self.translate(pattern)?.pattern)
+    }
+
+    fn compile(&self, pattern: &str) -> Result<Regex, String> {
+        let mut builder = RegexBuilder::new(&self.prepare(pattern)?);
+        builder.case_insensitive(self.collation.is_case_insensitive());
+        if let Some(size_limit) = self.size_limit {
+            builder.size_limit(size_limit);
+        }
+        builder.build().map_err(|e| e.to_string())
+    }
+
+    fn compile_set(&self, patterns: &[String]) -> Result<RegexSet, (usize, String)> {
+        let prepared = patterns
+            .iter()
+            .enumerate()
+            .map(|(i, pattern)| self.prepare(pattern).map_err(|e| (i, e)))
+            .collect::<Result<Vec<_>, _>>()?;
+        let mut builder = RegexSetBuilder::new(&prepared);
+        builder.case_insensitive(self.collation.is_case_insensitive());
+        if let Some(size_limit) = self.size_limit {
+            builder.size_limit(size_limit);
+        }
+        builder.build().map_err(|e| {
+            // The set error does not say which pattern failed; find it.
+            let failed = patterns
+                .iter()
+                .position(|pattern| self.compile(pattern).is_err())
+                .unwrap_or(0);
+            (failed, e.to_string())
+        })
+    }
+}
+
+type SetKey = (PatternOptions, Vec<String>);
+
+#[derive(Debug)]
+pub struct PatternCache {
+    capacity: usize,
+    entries: Mutex<HashMap<PatternOptions, HashMap<String, Arc<Regex>>>>,
+    sets: Mutex<HashMap<SetKey, Arc<RegexSet>>>,
+}
+
+impl Default for PatternCache {
+    fn default() -> Self {
+        Self::new(DEFAULT_CACHE_CAPACITY)
+    }
+}
+
+impl PatternCache {
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            capacity,
+            entries: Mutex::new(HashMap::new()),
+            sets: Mutex::new(HashMap::new()),
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries
+            .lock()
+            .unwrap()
+            .values()
+            .map(HashMap::len)
+            .sum()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Returns the compiled form of `pattern`, compiling it on a miss.
+    ///
+    /// `function` and `row` are only used to give errors their context.
+    pub fn get_or_compile(
+        &self,
+        function: &'static str,
+        pattern: &str,
+        options: PatternOptions,
+        row: usize,
+        metrics: &mut BatchMetrics,
+    ) -> Result<Arc<Regex>, RegexpError> {
+        if let Some(regex) = self
+            .entries
+            .lock()
+            .unwrap()
+            .get(&options)
+            .and_then(|patterns| patterns.get(pattern))
+        {
+            metrics.cache_hits += 1;
+            return Ok(Arc::clone(regex));
+        }
+        metrics.cache_misses += 1;
+
+        let started = Instant::now();
+        let compiled = options.compile(pattern);
+        metrics.compile_time += started.elapsed();
+        metrics.compilations += 1;
+
+        let regex = Arc::new(compiled.map_err(|e| RegexpError::InvalidPattern {
+            function,
+            pattern: pattern.to_string(),
+            row,
+            message: e,
+        })?);
+
+        if self.capacity > 0 {
+            let mut entries = self.entries.lock().unwrap();
+            if entries.values().map(HashMap::len).sum::<usize>() >= self.capacity {
+                // No recency tracking: evicting any entry keeps the cache
+                // bounded, and hot patterns are recompiled on their next miss.
+                if let Some(patterns) = entries.values_mut().find(|p| !p.is_empty())
+                    && let Some(key) = patterns.keys().next().cloned()
+                {
+                    patterns.remove(&key);
+                }
+            }
+            entries
+                .entry(options)
+                .or_default()
+                .insert(pattern.to_string(), Arc::clone(&regex));
+        }
+        Ok(regex)
+    }
+
+    /// Returns a [`RegexSet`] matching all of `patterns` in one pass,
+    /// compiling it on a miss. Sets are cached apart from single patterns,
+    /// with the same capacity.
+    ///
+    /// An invalid pattern is reported with its own text.
+    pub fn get_or_compile_set(
+        &self,
+        function: &'static str,
+        patterns: &[String],
+        options: PatternOptions,
+        metrics: &mut BatchMetrics,
+    ) -> Result<Arc<RegexSet>, RegexpError> {
+        let key = (options, patterns.to_vec());
+        if let Some(set) = self.sets.lock().unwrap().get(&key) {
+            metrics.cache_hits += 1;
+            return Ok(Arc::clone(set));
+        }
+        metrics.cache_misses += 1;
+
+        let started = Instant::now();
+        let compiled = options.compile_set(patterns);
+        metrics.compile_time += started.elapsed();
+        metrics.compilations += 1;
+
+        let set = Arc::new(
+            compiled.map_err(|(failed, message)| RegexpError::InvalidPattern {
+                function,
+                pattern: patterns[failed].clone(),
+                row: 0,
+                message,
+            })?,
+        );
+
+        if self.capacity > 0 {
+            let mut sets = self.sets.lock().unwrap();
+            if sets.len() >= self.capacity
+                && let Some(evicted) = sets.keys().next().cloned()
+            {
+                sets.remove(&evicted);
+            }
+            sets.insert(key, Arc::clone(&set));
+        }
+        Ok(set)
+    }
+}
+
+/// Resolves patterns row by row within one batch.
+///
+/// Consecutive rows with the same pattern, which is always the case for a
+/// literal pattern, reuse the previous row's regex without touching the cache.
+pub struct BatchPatterns<'a> {
+    function: &'static str,
+    cache: &'a PatternCache,
+    options: PatternOptions,
+    flags: &'static str,
+    last: Option<(&'a str, Arc<Regex>)>,
+}
+
+impl<'a> BatchPatterns<'a> {
+    pub fn new(function: &'static str, cache: &'a PatternCache, options: PatternOptions) -> Self {
+        Self {
+            function,
+            cache,
+            options,
+            flags: "",
+            last: None,
+        }
+    }
+
+    /// Prepends inline `flags` such as `(?m)` to every pattern. Errors still
+    /// report the pattern as written.
+    pub fn with_flags(mut self, flags: &'static str) -> Self {
+        self.flags = flags;
+        self
+    }
+
+    pub fn get(
+        &mut self,
+        pattern: &'a str,
+        row: usize,
+        metrics: &mut BatchMetrics,
+    ) -> Result<Arc<Regex>, RegexpError> {
+        if let Some((last_pattern, regex)) = &self.last
+            && *last_pattern == pattern
+        {
+            return Ok(Arc::clone(regex));
+        }
+        let regex = if self.flags.is_empty() {
+            self.cache
+                .get_or_compile(self.function, pattern, self.options, row, metrics)?
+        } else {
+            let flagged = format!("{}{pattern}", self.flags);
+            self.cache
+                .get_or_compile(self.function, &flagged, self.options, row, metrics)
+                .map_err(|err| match err {
+                    RegexpError::InvalidPattern {
+                        function,
+                        row,
+                        message,
+                        ..
+                    } => RegexpError::InvalidPattern {
+                        function,
+                        pattern: pattern.to_string(),
+                        row,
+                        message,
+                    },
+                    other => other,
+                })?
+        };
+        self.last = Some((pattern, Arc::clone(&regex)));
+        Ok(regex)
+    }
+}
+
+/// Rejects negative group indexes the way Spark does, per row, so the error
+/// can name the pattern. Indexes past the last group are left to the caller.
+pub fn check_group_index(
+    function: &'static str,
+    pattern: &str,
+    regex: &Regex,
+    idx: i64,
+    row: usize,
+) -> Result<(), RegexpError> {
+    if idx < 0 {
+        return Err(RegexpError::InvalidGroupIndex {
+            function,
+            pattern: pattern.to_string(),
+            index: idx,
+            group_count: regex.captures_len() - 1,
+            row,
+        });
+    }
+    Ok(())
+}
+
+/// The byte offset of the `chars`-th character of `text`, or `None` when
+/// `text` has fewer characters. Spark positions count characters, the
+/// `regex` crate counts bytes.
+pub fn byte_offset_of_char(text: &str, chars: usize) -> Option<usize> {
+    text.char_indices()
+        .map(|(offset, _)| offset)
+        .chain(std::iter::once(text.len()))
+        .nth(chars)
+}
+
+/// The byte offset a search from the 1-based character `position` of `text`
+/// starts at, or `None` when `position` is past the end. Position 1 always
+/// searches, so that an empty value can match.
+pub fn search_start(text: &str, position: i64) -> Option<usize> {
+    byte_offset_of_char(text, (position - 1) as usize)
+        .filter(|&start| start < text.len() || position == 1)
+}
+
+/// The number of characters in `text` before byte offset `byte`, which must
+/// lie on a character boundary. The inverse of [`byte_offset_of_char`].
+pub fn char_offset_of_byte(text: &str, byte: usize) -> usize {
+    text[..byte].chars().count()
+}
+
+/// Successive matches in the order Java's `Matcher.find` reports them.
+///
+/// Unlike `Regex::find_iter`, an empty match directly after a non-empty one is
+/// reported; after an empty match the search resumes one character later.
+pub fn java_find_iter<'r, 'h>(regex: &'r Regex, haystack: &'h str) -> JavaMatches<'r, 'h> {
+    JavaMatches {
+        regex,
+        haystack,
+        next_start: Some(0),
+    }
+}
+
+/// Like [`java_find_iter`], yielding capture groups.
+pub fn java_captures_iter<'r, 'h>(regex: &'r Regex, haystack: &'h str) -> JavaCaptures<'r, 'h> {
+    JavaCaptures {
+        regex,
+        haystack,
+        next_start: Some(0),
+    }
+}
+
+/// Where the search after `m` starts, or `None` once the input is exhausted.
+fn resume_after(haystack: &str, m: &Match<'_>) -> Option<usize> {
+    if !m.is_empty() {
+        return Some(m.end());
+    }
+    haystack[m.end()..]
+        .chars()
+        .next()
+        .map(|c| m.end() + c.len_utf8())
+}
+
+pub struct JavaMatches<'r, 'h> {
+    regex: &'r Regex,
+    haystack: &'h str,
+    next_start: Option<usize>,
+}
+
+impl<'h> Iterator for JavaMatches<'_, 'h> {
+    type Item = Match<'h>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let m = self.regex.find_at(self.haystack, self.next_start?)?;
+        self.next_start = resume_after(self.haystack, &m);
+        Some(m)
+    }
+}
+
+pub struct JavaCaptures<'r, 'h> {
+    regex: &'r Regex,
+    haystack: &'h str,
+    next_start: Option<usize>,
+}
+
+impl<'h> Iterator for JavaCaptures<'_, 'h> {
+    type Item = Captures<'h>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let captures = self.regex.captures_at(self.haystack, self.next_start?)?;
+        self.next_start = resume_after(self.haystack, &captures.get(0).unwrap());
+        Some(captures)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_cache_hits_and_eviction() {
+        let cache = PatternCache::new(2);
+        let mut metrics = BatchMetrics::start("test", 0, &Arc::default());
+
+        let options = PatternOptions::default();
+
+        cache
+            .get_or_compile("test", "a+", options, 0, &mut metrics)
+            .unwrap();
+        cache
+            .get_or_compile("test", "a+", options, 1, &mut metrics)
+            .unwrap();
+        assert_eq!((metrics.cache_hits, metrics.cache_misses), (1, 1));
+
+        cache
+            .get_or_compile("test", "b+", options, 2, &mut metrics)
+            .unwrap();
+        cache
+            .get_or_compile("test", "c+", options, 3, &mut metrics)
+            .unwrap();
+        assert_eq!(cache.len(), 2);
+        assert_eq!(metrics.compilations, 3);
+    }
+
+    #[test]
+    fn test_sets_are_cached_and_report_the_bad_pattern() {
+        let cache = PatternCache::default();
+        let mut metrics = BatchMetrics::start("test", 0, &Arc::default());
+        let patterns = vec!["a+".to_string(), r"\d".to_string()];
+
+        let set = cache
+            .get_or_compile_set("test", &patterns, PatternOptions::default(), &mut metrics)
+            .unwrap();
+        cache
+            .get_or_compile_set("test", &patterns, PatternOptions::default(), &mut metrics)
+            .unwrap();
+        assert_eq!(set.matches("x1").into_iter().collect::<Vec<_>>(), vec![1]);
+        assert_eq!((metrics.cache_hits, metrics.compilations), (1, 1));
+
+        let bad = vec!["ok".to_string(), "(".to_string()];
+        let err = cache
+            .get_or_compile_set("test", &bad, PatternOptions::default(), &mut metrics)
+            .unwrap_err();
+        assert_eq!(err.pattern(), Some("("));
+    }
+
+    #[test]
+    fn test_batch_patterns_reuse_previous_row() {
+        let cache = PatternCache::default();
+        let mut metrics = BatchMetrics::start("test", 3, &Arc::default());
+        let mut patterns = BatchPatterns::new("test", &cache, PatternOptions::default());
+
+        for row in 0..3 {
+            patterns.get(r"\d+", row, &mut metrics).unwrap();
+        }
+        assert_eq!((metrics.cache_hits, metrics.cache_misses), (0, 1));
+    }
+
+    #[test]
+    fn test_batch_patterns_with_flags() {
+        let cache = PatternCache::default();
+        let mut metrics = BatchMetrics::start("test", 2, &Arc::default());
+        let mut patterns =
+            BatchPatterns::new("test", &cache, PatternOptions::default()).with_flags("(?s)");
+
+        assert!(
+            patterns
+                .get("a.b", 0, &mut metrics)
+                .unwrap()
+                .is_match("a\nb")
+        );
+        let err = patterns.get("(", 1, &mut metrics).unwrap_err();
+        assert_eq!((err.pattern(), err.row()), (Some("("), Some(1)));
+    }
+
+    #[test]
+    fn test_search_start() {
+        assert_eq!(search_start("", 1), Some(0));
+        assert_eq!(search_start("", 2), None);
+        assert_eq!(search_start("h\u{e9}llo", 3), Some(3));
+        assert_eq!(search_start("abc", 4), None);
+    }
+
+    #[test]
+    fn test_options_are_part_of_the_key() {
+        let cache = PatternCache::default();
+        let mut metrics = BatchMetrics::start("test", 0, &Arc::default());
+        let lcase = PatternOptions {
+            collation: Collation::Utf8Lcase,
+            ..Default::default()
+        };
+
+        let binary = cache
+            .get_or_compile("test", "abc", PatternOptions::default(), 0, &mut metrics)
+            .unwrap();
+        let folded = cache
+            .get_or_compile("test", "abc", lcase, 0, &mut metrics)
+            .unwrap();
+        assert!(!binary.is_match("ABC"));
+        assert!(folded.is_match("ABC"));
+        assert_eq!(metrics.compilations, 2);
+    }
+
+    #[test]
+    fn test_char_and_byte_offsets_round_trip() {
+        let text = "你好-世界";
+        assert_eq!(byte_offset_of_char(text, 3), Some(7));
+        assert_eq!(char_offset_of_byte(text, 7), 3);
+        assert_eq!(char_offset_of_byte(text, text.len()), 5);
+    }
+
+    #[test]
+    fn test_java_find_reports_empty_match_after_non_empty_one() {
+        let regex = Regex::new("a*").unwrap();
+        let matches: Vec<_> = java_find_iter(&regex, "aab").map(|m| m.range()).collect();
+        assert_eq!(matches, vec![0..2, 2..2, 3..3]);
+    }
+
+    #[test]
+    fn test_java_find_steps_over_multibyte_characters() {
+        let regex = Regex::new("").unwrap();
+        assert_eq!(java_find_iter(&regex, "你好").count(), 3);
+    }
+}
@@ -0,0 +1,673 @@
+//! PII redaction with a built-in pattern library.
+//!
+//! [`Redact`] finds sensitive values with the same cached, dialect-aware
+//! regexes as the extraction functions and rewrites them according to each
+//! [`Category`]'s [`Masking`]. The built-in categories are:
+//!
+//! | Category | Matches | Default masking |
+//! |----------|---------|-----------------|
+//! | `bearer_token` | the token after `Bearer ` | `[REDACTED:bearer_token]` |
+//! | `email` | `local@domain.tld` | `[REDACTED:email]` |
+//! | `credit_card` | 13 to 19 digits, optionally grouped, passing the Luhn check | last 4 digits kept |
+//! | `ipv6` | any address `std::net::Ipv6Addr` accepts | `[REDACTED:ipv6]` |
+//! | `ipv4` | dotted quads with octets up to 255 | `[REDACTED:ipv4]` |
+//! | `phone` | 7 to 15 digits in common international and local layouts | last 4 digits kept |
+
+use std::any::Any;
+use std::net::{Ipv4Addr, Ipv6Addr};
+use std::ops::Range;
+use std::sync::Arc;
+
+use arrow_array::builder::StringBuilder;
+use arrow_array::{Array, ArrayRef, StringArray};
+use datafusion_common::Result;
+use datafusion_common::arrow::datatypes::DataType;
+use datafusion_expr::{
+    ColumnarValue, ScalarFunctionArgs, ScalarUDFImpl, Signature, TypeSignature, Volatility,
+};
+use regex::Regex;
+
+use crate::args::{as_string_array, to_array};
+use crate::dialect::RegexDialect;
+use crate::error::RegexpError;
+use crate::metrics::{BatchMetrics, RegexpMetrics};
+use crate::pattern::{PatternCache, PatternOptions};
+
+const NAME: &str = "redact";
+
+/// The categories one `categories` value names, with their compiled patterns.
+type Selected<'a> = Vec<(&'a Category, Arc<Regex>)>;
+
+/// The capture group that, when present in a category's pattern, limits
+/// redaction to that part of the match, e.g. the token after `Bearer `.
+pub const SECRET_GROUP: &str = "secret";
+
+/// How a redacted value is rewritten.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Masking {
+    /// Replaces the value with a fixed text in which `{category}` expands to
+    /// the category name.
+    Replace(String),
+    /// Format-preserving masking: every letter and digit becomes `mask`
+    /// except the first `keep_first` and the last `keep_last` of them, while
+    /// separators such as spaces, dashes and `@` are kept.
+    Mask {
+        mask: char,
+        keep_first: usize,
+        keep_last: usize,
+    },
+}
+
+impl Default for Masking {
+    fn default() -> Self {
+        Masking::Replace("[REDACTED:{category}]".to_string())
+    }
+}
+
+impl Masking {
+    /// Masks everything with `*` but the last `n` letters and digits.
+    pub fn keep_last(n: usize) -> Self {
+        Masking::Mask {
+            mask: '*',
+            keep_first: 0,
+            keep_last: n,
+        }
+    }
+
+    fn apply(&self, category: &str, value: &str, out: &mut String) {
+        match self {
+            Masking::Replace(template) => out.push_str(&template.replace("{category}", category)),
+            Masking::Mask {
+                mask,
+                keep_first,
+                keep_last,
+            } => {
+                let total = value.chars().filter(|c| c.is_alphanumeric()).count();
+                let mut seen = 0;
+                for c in value.chars() {
+                    if !c.is_alphanumeric() {
+                        out.push(c);
+                        continue;
+                    }
+                    if seen < *keep_first || seen + keep_last >= total {
+                        out.push(c);
+                    } else {
+                        out.push(*mask);
+                    }
+                    seen += 1;
+                }
+            }
+        }
+    }
+}
+
+/// A named kind of sensitive value: a pattern, an optional validator that
+/// every match must pass, and how matches are masked.
+#[derive(Debug, Clone)]
+pub struct Category {
+    name: String,
+    pattern: String,
+    dialect: RegexDialect,
+    validator: Option<fn(&str) -> bool>,
+    masking: Masking,
+}
+
+impl Category {
+    /// A category matching the Java-dialect `pattern`. If the pattern has a
+    /// group named [`SECRET_GROUP`], only that group is redacted.
+    pub fn new(name: impl Into<String>, pattern: impl Into<String>) -> Self {
+        Self {
+            name: name.into().to_ascii_lowercase(),
+            pattern: pattern.into(),
+            dialect: RegexDialect::Java,
+            validator: None,
+            masking: Masking::default(),
+        }
+    }
+
+    /// The dialect the pattern is written in.
+    pub fn with_dialect(mut self, dialect: RegexDialect) -> Self {
+        self.dialect = dialect;
+        self
+    }
+
+    /// A check every match must pass to be redacted, such as a checksum. A
+    /// match that fails is retried on its parts; see [`Self::accepted_span`].
+    pub fn with_validator(mut self, validator: fn(&str) -> bool) -> Self {
+        self.validator = Some(validator);
+        self
+    }
+
+    /// How matches are rewritten.
+    pub fn with_masking(mut self, masking: Masking) -> Self {
+        self.masking = masking;
+        self
+    }
+
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    pub fn pattern(&self) -> &str {
+        &self.pattern
+    }
+
+    pub fn masking(&self) -> &Masking {
+        &self.masking
+    }
+
+    /// Whether `value`, a match of the pattern, passes the validator.
+    pub fn accepts(&self, value: &str) -> bool {
+        self.validator.is_none_or(|validator| validator(value))
+    }
+
+    /// The part of `value`, a match of the pattern, to redact: all of it if
+    /// it passes the validator, else the leftmost, then longest, run of its
+    /// alphanumeric pieces that does. A greedy pattern can run on into the
+    /// text after a value, such as the expiry date after a card number.
+    pub fn accepted_span(&self, value: &str) -> Option<Range<usize>> {
+        if self.accepts(value) {
+            return Some(0..value.len());
+        }
+        self.validator?;
+        let mut pieces: Vec<Range<usize>> = Vec::new();
+        for (offset, c) in value.char_indices() {
+            let end = offset + c.len_utf8();
+            match pieces.last_mut() {
+                Some(piece) if c.is_alphanumeric() && piece.end == offset => piece.end = end,
+                _ if c.is_alphanumeric() => pieces.push(offset..end),
+                _ => {}
+            }
+        }
+        for first in 0..pieces.len() {
+            for last in (first..pieces.len()).rev() {
+                let span = pieces[first].start..pieces[last].end;
+                if span != (0..value.len()) && self.accepts(&value[span.clone()]) {
+                    return Some(span);
+                }
+            }
+        }
+        None
+    }
+}
+
+/// The built-in categories, in the order that decides which one wins when
+/// two of them match overlapping text.
+pub fn builtin_categories() -> Vec<Category> {
+    let builtin =
+        |name: &str, pattern: &str| Category::new(name, pattern).with_dialect(RegexDialect::Rust);
+    vec![
+        builtin(
+            "bearer_token",
+            r"(?i)\bbearer\s+(?P<secret>[a-z0-9._~+/-]+=*)",
+        ),
+        builtin(
+            "email",
+            r"(?i)\b[a-z0-9._%+-]+@[a-z0-9-]+(?:\.[a-z0-9-]+)*\.[a-z]{2,}\b",
+        ),
+        builtin("credit_card", r"\b(?:\d[ -]?){12,18}\d\b")
+            .with_validator(is_card_number)
+            .with_masking(Masking::keep_last(4)),
+        // Candidates are deliberately loose; the parser decides.
+        builtin(
+            "ipv6",
+            r"(?i)[0-9a-f]*(?::[0-9a-f]*){2,}(?:\.[0-9]+){0,3}",
+        )
+        .with_validator(|value| value.parse::<Ipv6Addr>().is_ok()),
+        builtin("ipv4", r"\b(?:\d{1,3}\.){3}\d{1,3}\b")
+            .with_validator(|value| value.parse::<Ipv4Addr>().is_ok()),
+        builtin(
+            "phone",
+            r"(?:\+\d{1,3}[ .-]?)?(?:\(\d{1,4}\)[ .-]?|\b\d{1,4}[ .-])(?:\d{2,4}[ .-]?){0,2}\d{4}\b",
+        )
+        .with_validator(|value| (7..=15).contains(&digits(value).count()))
+        .with_masking(Masking::keep_last(4)),
+    ]
+}
+
+fn digits(value: &str) -> impl Iterator<Item = u32> + '_ {
+    value.chars().filter_map(|c| c.to_digit(10))
+}
+
+/// Whether the digits of `value` form a plausible card number: 13 to 19 of
+/// them, passing the Luhn checksum.
+pub fn is_card_number(value: &str) -> bool {
+    let digits: Vec<u32> = digits(value).collect();
+    (13..=19).contains(&digits.len()) && luhn_valid(&digits)
+}
+
+/// The Luhn (mod 10) checksum used by payment card numbers.
+pub fn luhn_valid(digits: &[u32]) -> bool {
+    let sum: u32 = digits
+        .iter()
+        .rev()
+        .enumerate()
+        .map(|(i, &d)| match (i % 2, d * 2) {
+            (0, _) => d,
+            (_, doubled) if doubled > 9 => doubled - 9,
+            (_, doubled) => doubled,
+        })
+        .sum();
+    sum.is_multiple_of(10)
+}
+
+/// `redact(str, categories[, replacement])`: `str` with every value of the
+/// comma-separated `categories` (or `all`) masked.
+///
+/// Without `replacement` each category uses its own [`Masking`]; with it,
+/// every value is replaced by `replacement`, in which `{category}` expands
+/// to the category name. When values of different categories overlap, the
+/// one starting first wins, then the one registered first. An unknown
+/// category is an error, and a NULL argument yields NULL.
+#[derive(Debug, Clone)]
+pub struct Redact {
+    signature: Signature,
+    categories: Vec<Category>,
+    cache: Arc<PatternCache>,
+    metrics: Arc<RegexpMetrics>,
+}
+
+impl Default for Redact {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Redact {
+    /// `redact` with the [built-in categories](builtin_categories).
+    pub fn new() -> Self {
+        Self {
+            signature: Signature::one_of(
+                vec![
+                    TypeSignature::Exact(vec![DataType::Utf8, DataType::Utf8]),
+                    TypeSignature::Exact(vec![DataType::Utf8, DataType::Utf8, DataType::Utf8]),
+                ],
+                Volatility::Immutable,
+            ),
+            categories: builtin_categories(),
+            cache: Arc::new(PatternCache::default()),
+            metrics: Arc::new(RegexpMetrics::new()),
+        }
+    }
+
+    /// Adds `category`, replacing a registered one of the same name.
+    pub fn with_category(mut self, category: Category) -> Self {
+        match self.categories.iter_mut().find(|c| c.name == category.name) {
+            Some(existing) => *existing = category,
+            None => self.categories.push(category),
+        }
+        self
+    }
+
+    /// Changes how the category `name` is masked; unknown names are ignored.
+    pub fn with_masking(mut self, name: &str, masking: Masking) -> Self {
+        if let Some(category) = self.categories.iter_mut().find(|c| c.name == name) {
+            category.masking = masking;
+        }
+        self
+    }
+
+    /// The registered categories, in priority order.
+    pub fn categories(&self) -> &[Category] {
+        &self.categories
+    }
+
+    /// Execution metrics, shared by every clone of this instance.
+    pub fn metrics(&self) -> &RegexpMetrics {
+        &self.metrics
+    }
+
+    /// Indexes into `self.categories` named by `list`, in priority order.
+    fn select(&self, list: &str) -> Result<Vec<usize>, RegexpError> {
+        let mut selected = Vec::new();
+        for name in list
+            .split(',')
+            .map(str::trim)
+            .filter(|name| !name.is_empty())
+        {
+            let name = name.to_ascii_lowercase();
+            if name == "all" {
+                return Ok((0..self.categories.len()).collect());
+            }
+            match self.categories.iter().position(|c| c.name == name) {
+                Some(index) => selected.push(index),
+                None => {
+                    return Err(RegexpError::UnknownCategory {
+                        function: NAME,
+                        name,
+                        known: self.categories.iter().map(|c| c.name.clone()).collect(),
+                    });
+                }
+            }
+        }
+        selected.sort_unstable();
+        selected.dedup();
+        Ok(selected)
+    }
+}
+
+impl ScalarUDFImpl for Redact {
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn name(&self) -> &str {
+        NAME
+    }
+
+    fn signature(&self) -> &Signature {
+        &self.signature
+    }
+
+    fn return_type(&self, _arg_types: &[DataType]) -> Result<DataType> {
+        Ok(DataType::Utf8)
+    }
+
+    fn invoke_with_args(&self, args: ScalarFunctionArgs) -> Result<ColumnarValue> {
+        let num_rows = args.number_rows;
+        let input_array_ref: ArrayRef = to_array(&args.args[0], num_rows)?;
+        let categories_array_ref: ArrayRef = to_array(&args.args[1], num_rows)?;
+        let replacement_array_ref: Option<ArrayRef> = args
+            .args
+            .get(2)
+            .map(|arg| to_array(arg, num_rows))
+            .transpose()?;
+        let input_array: &StringArray = as_string_array(NAME, "str", &input_array_ref)?;
+        let categories_array: &StringArray =
+            as_string_array(NAME, "categories", &categories_array_ref)?;
+        let replacement_array: Option<&StringArray> = replacement_array_ref
+            .as_ref()
+            .map(|array| as_string_array(NAME, "replacement", array))
+            .transpose()?;
+
+        let mut string_builder = StringBuilder::new();
+        let mut batch_metrics = BatchMetrics::start(NAME, num_rows, &self.metrics);
+        let mut current: Option<(&str, Selected)> = None;
+
+        for i in 0..num_rows {
+            if input_array.is_null(i)
+                || categories_array.is_null(i)
+                || replacement_array.is_some_and(|array| array.is_null(i))
+            {
+                string_builder.append_null();
+                continue;
+            }
+
+            let list = categories_array.value(i);
+            if current.as_ref().is_none_or(|(last, _)| *last != list) {
+                let mut selected = Vec::new();
+                for index in self.select(list)? {
+                    let category = &self.categories[index];
+                    let options = PatternOptions {
+                        dialect: category.dialect,
+                        ..Default::default()
+                    };
+                    let regex = self.cache.get_or_compile(
+                        NAME,
+                        &category.pattern,
+                        options,
+                        i,
+                        &mut batch_metrics,
+                    )?;
+                    selected.push((category, regex));
+                }
+                current = Some((list, selected));
+            }
+            let (_, selected) = current.as_ref().unwrap();
+            batch_metrics.rows_processed += 1;
+
+            let text = input_array.value(i);
+            let mut found: Vec<(usize, usize, usize)> = Vec::new();
+            for (priority, (category, regex)) in selected.iter().enumerate() {
+                let secret = regex
+                    .capture_names()
+                    .position(|name| name == Some(SECRET_GROUP));
+                for caps in regex.captures_iter(text) {
+                    let m = secret
+                        .and_then(|group| caps.get(group))
+                        .unwrap_or_else(|| caps.get(0).unwrap());
+                    if m.is_empty() {
+                        continue;
+                    }
+                    if let Some(span) = category.accepted_span(m.as_str()) {
+                        found.push((m.start() + span.start, priority, m.start() + span.end));
+                    }
+                }
+            }
+            if found.is_empty() {
+                string_builder.append_value(text);
+                continue;
+            }
+            batch_metrics.rows_matched += 1;
+
+            found.sort_unstable();
+            let mut redacted = String::with_capacity(text.len());
+            let mut position = 0;
+            for (start, priority, end) in found {
+                if start < position {
+                    continue;
+                }
+                let category = selected[priority].0;
+                redacted.push_str(&text[position..start]);
+                match replacement_array {
+                    Some(array) => Masking::Replace(array.value(i).to_string()).apply(
+                        &category.name,
+                        &text[start..end],
+                        &mut redacted,
+                    ),
+                    None => {
+                        category
+                            .masking
+                            .apply(&category.name, &text[start..end], &mut redacted)
+                    }
+                }
+                position = end;
+            }
+            redacted.push_str(&text[position..]);
+            string_builder.append_value(redacted);
+        }
+
+        Ok(ColumnarValue::Array(Arc::new(string_builder.finish())))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use arrow_array::cast::AsArray;
+    use datafusion_common::ScalarValue;
+    use datafusion_common::arrow::datatypes::Field;
+
+    fn run_test(
+        udf: &Redact,
+        text: Option<&str>,
+        categories: &str,
+        replacement: Option<&str>,
+    ) -> Result<Option<String>> {
+        let mut args = vec![
+            ColumnarValue::Array(Arc::new(StringArray::from(vec![text]))),
+            ColumnarValue::Scalar(ScalarValue::from(categories)),
+        ];
+        if let Some(replacement) = replacement {
+            args.push(ColumnarValue::Scalar(ScalarValue::from(replacement)));
+        }
+        let args = ScalarFunctionArgs {
+            args,
+            number_rows: 1,
+            arg_fields: vec![],
+            return_field: Arc::new(Field::new("result", DataType::Utf8, true)),
+        };
+        let array = udf.invoke_with_args(args)?.to_array(1)?;
+        Ok(array
+            .as_string::<i32>()
+            .iter()
+            .next()
+            .unwrap()
+            .map(str::to_string))
+    }
+
+    fn redact(text: &str, categories: &str) -> String {
+        run_test(&Redact::new(), Some(text), categories, None)
+            .unwrap()
+            .unwrap()
+    }
+
+    #[test]
+    fn test_email() {
+        assert_eq!(
+            redact("mail John.Doe+ops@mail.example.co.uk now", "email"),
+            "mail [REDACTED:email] now"
+        );
+        assert_eq!(
+            redact("user@localhost and @handle", "email"),
+            "user@localhost and @handle"
+        );
+    }
+
+    #[test]
+    fn test_ipv4() {
+        assert_eq!(
+            redact("from 192.168.1.100 to 10.0.0.1:8080", "ipv4"),
+            "from [REDACTED:ipv4] to [REDACTED:ipv4]:8080"
+        );
+        assert_eq!(redact("999.1.1.1 and 1.2.3", "ipv4"), "999.1.1.1 and 1.2.3");
+    }
+
+    #[test]
+    fn test_ipv6() {
+        assert_eq!(
+            redact("src=2001:db8::ff00:42:8329 dst=::1", "ipv6"),
+            "src=[REDACTED:ipv6] dst=[REDACTED:ipv6]"
+        );
+        assert_eq!(
+            redact("mapped ::ffff:192.0.2.1", "ipv6"),
+            "mapped [REDACTED:ipv6]"
+        );
+        assert_eq!(
+            redact("at 12:30:45 in a:b:c", "ipv6"),
+            "at 12:30:45 in a:b:c"
+        );
+    }
+
+    #[test]
+    fn test_credit_card_requires_luhn() {
+        assert_eq!(
+            redact("card 4111 1111 1111 1111 ok", "credit_card"),
+            "card **** **** **** 1111 ok"
+        );
+        assert_eq!(
+            redact("5500-0000-0000-0004", "credit_card"),
+            "****-****-****-0004"
+        );
+        assert_eq!(
+            redact("4111111111111112", "credit_card"),
+            "4111111111111112"
+        );
+        assert_eq!(
+            redact("order 123456789012", "credit_card"),
+            "order 123456789012"
+        );
+    }
+
+    #[test]
+    fn test_credit_card_followed_by_other_digits() {
+        // The candidate runs on into the expiry date or CVV and fails the
+        // checksum as a whole; the card number inside it is still found.
+        assert_eq!(
+            redact("card 4111111111111111 12/25", "credit_card"),
+            "card ************1111 12/25"
+        );
+        assert_eq!(
+            redact("card 4111 1111 1111 1111 exp 12/25", "credit_card"),
+            "card **** **** **** 1111 exp 12/25"
+        );
+        assert_eq!(
+            redact("card 4111111111111111 123", "credit_card"),
+            "card ************1111 123"
+        );
+        assert_eq!(
+            redact("cvv 123 card 5500-0000-0000-0004", "credit_card"),
+            "cvv 123 card ****-****-****-0004"
+        );
+    }
+
+    #[test]
+    fn test_phone() {
+        assert_eq!(
+            redact("call +1 (555) 123-4567 or 555.987.6543", "phone"),
+            "call +* (***) ***-4567 or ***.***.6543"
+        );
+        assert_eq!(
+            redact("on 2024-01-15 room 12", "phone"),
+            "on 2024-01-15 room 12"
+        );
+    }
+
+    #[test]
+    fn test_bearer_token_keeps_scheme() {
+        assert_eq!(
+            redact("Authorization: Bearer eyJhbGciOi.J9x-_y==", "bearer_token"),
+            "Authorization: Bearer [REDACTED:bearer_token]"
+        );
+    }
+
+    #[test]
+    fn test_luhn() {
+        let digits = |s: &str| -> Vec<u32> { s.chars().map(|c| c.to_digit(10).unwrap()).collect() };
+        assert!(luhn_valid(&digits("79927398713")));
+        assert!(!luhn_valid(&digits("79927398710")));
+        assert!(is_card_number("378282246310005"));
+        assert!(!is_card_number("0000 0000"));
+    }
+
+    #[test]
+    fn test_all_categories_and_overlaps() {
+        assert_eq!(
+            redact("a@b.io paid with 4111111111111111 from 10.1.2.3", "all"),
+            "[REDACTED:email] paid with ************1111 from [REDACTED:ipv4]"
+        );
+        // On the same text the category registered first wins, whatever the
+        // order in the argument.
+        let udf = Redact::new().with_category(Category::new("digits", r"\d+"));
+        let result = run_test(&udf, Some("4111111111111111"), "digits, credit_card", None);
+        assert_eq!(result.unwrap().as_deref(), Some("************1111"));
+    }
+
+    #[test]
+    fn test_replacement_argument() {
+        let result = run_test(
+            &Redact::new(),
+            Some("a@b.io from 10.1.2.3"),
+            "email,ipv4",
+            Some("<{category}>"),
+        )
+        .unwrap();
+        assert_eq!(result.as_deref(), Some("<email> from <ipv4>"));
+    }
+
+    #[test]
+    fn test_custom_category_and_masking() {
+        let udf = Redact::new()
+            .with_category(Category::new("employee_id", r"\bEMP-\d{6}\b"))
+            .with_masking(
+                "email",
+                Masking::Mask {
+                    mask: '#',
+                    keep_first: 1,
+                    keep_last: 0,
+                },
+            );
+        let result = run_test(&udf, Some("EMP-123456 jo@x.io"), "employee_id,email", None).unwrap();
+        assert_eq!(result.as_deref(), Some("[REDACTED:employee_id] j#@#.##"));
+    }
+
+    #[test]
+    fn test_unknown_category_and_nulls() {
+        let err = run_test(&Redact::new(), Some("x"), "email,ssn", None).unwrap_err();
+        let err = RegexpError::find(&err).unwrap();
+        assert_eq!(err.error_class(), "INVALID_PARAMETER_VALUE.CATEGORY");
+        assert!(err.to_string().contains("'ssn'"));
+
+        assert_eq!(run_test(&Redact::new(), None, "email", None).unwrap(), None);
+    }
+}
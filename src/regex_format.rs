@@ -0,0 +1,584 @@
+//! Text files whose lines are split into columns by a regex, like Hive's
+//! `RegexSerDe`.
+//!
+//! ```sql
+//! CREATE EXTERNAL TABLE access
+//! STORED AS REGEX
+//! LOCATION 'logs/access.log'
+//! OPTIONS ('input.regex' '(?<host>\S+) \S+ \S+ \[([^\]]+)\] "([^"]*)" (\d{3}) .*');
+//! ```
+//!
+//! A line must match `input.regex` as a whole, as with Java's
+//! `Matcher.matches`, and each capture group fills one column. Without
+//! declared columns the schema is inferred from the pattern: a named group
+//! gives a Utf8 column of that name, and an unnamed one is called `_cN` after
+//! its position, so the table above has `host`, `_c1`, `_c2` and `_c3`.
+//! A declared column takes the group of the same name, or else the next
+//! group in order that no column takes by name, and the captured text is
+//! cast to its type, giving NULL when the cast fails.
+//!
+//! | option | |
+//! | --- | --- |
+//! | `input.regex` | The pattern, in Java syntax. Required. |
+//! | `input.regex.case.insensitive` | `true` to match regardless of case. |
+//! | `mode` | What to do with a line that does not match; see [`ParseMode`]. |
+//! | `compression` | `gzip`, `zstd`, `bzip2`, `xz` or `uncompressed`. |
+//! | `file_extension` | The extension of the files read from a directory, `log` by default. |
+//! | `start_pattern` | A pattern, in Java syntax, for the lines that begin a record; the lines after one that do not match are joined to it with `\n`. |
+//! | `continuation_pattern` | Instead of `start_pattern`, a pattern for the lines that are joined to the one before. |
+//! | `max_record_size` | The most bytes a multiline record keeps, 10 MiB by default; lines past it are dropped. |
+//!
+//! With a `start_pattern` or `continuation_pattern`, `input.regex` must
+//! match the whole record, so a `.` that should cross lines needs the `(?s)`
+//! flag. The extension only matters when `LOCATION` is a directory and no
+//! columns are declared. `STORED AS REGEX` needs [`RegexFormatFactory`] on
+//! the session, which [`crate::registry::register_all`] installs. A table
+//! can also be set up with
+//! `ListingOptions::new(Arc::new(RegexFormat::new(..)))`.
+
+use std::any::Any;
+use std::collections::HashMap;
+use std::sync::Arc;
+
+use arrow_array::builder::StringBuilder;
+use arrow_array::{ArrayRef, RecordBatch, RecordBatchOptions};
+use async_trait::async_trait;
+use datafusion::arrow::compute::cast;
+use datafusion::arrow::datatypes::{DataType, Field, Schema, SchemaRef};
+use datafusion::catalog::Session;
+use datafusion::datasource::file_format::{FileFormat, FileFormatFactory};
+use datafusion::datasource::physical_plan::{FileScanConfig, FileSource};
+use datafusion::physical_plan::ExecutionPlan;
+use datafusion_common::{GetExt, Result, Statistics, config_datafusion_err, config_err, plan_err};
+use datafusion_datasource::file_compression_type::FileCompressionType;
+use object_store::{ObjectMeta, ObjectStore};
+use regex::{Regex, RegexBuilder};
+
+use crate::error::RegexpError;
+use crate::pattern::PatternOptions;
+use crate::text_file::{
+    LineDecoder, LineFormat, ParseMode, RecordStart, TextOptions, TextSource, scan_plan,
+};
+
+const NAME: &str = "regex";
+
+/// A [`FileFormat`] for text files split into columns by a regex; see the
+/// module documentation.
+#[derive(Debug, Clone)]
+pub struct RegexFormat {
+    input_regex: String,
+    case_insensitive: bool,
+    options: TextOptions,
+}
+
+impl RegexFormat {
+    pub fn new(input_regex: impl Into<String>) -> Self {
+        Self {
+            input_regex: input_regex.into(),
+            case_insensitive: false,
+            options: TextOptions::default(),
+        }
+    }
+
+    /// Builds a format from `CREATE EXTERNAL TABLE` options, with the
+    /// `format.` prefix DataFusion gives keys that have no namespace.
+    pub fn from_options(options: &HashMap<String, String>) -> Result<Self> {
+        let Some(input_regex) = options.get("input.regex") else {
+            return config_err!("STORED AS REGEX requires the 'input.regex' option");
+        };
+        let mut format = Self::new(input_regex.as_str());
+        for (key, value) in options {
+            match key.as_str() {
+                "input.regex" => {}
+                "input.regex.case.insensitive" => {
+                    format.case_insensitive = value.to_ascii_lowercase().parse().map_err(|_| {
+                        config_datafusion_err!(
+                            "Invalid '{key}' value '{value}', expected 'true' or 'false'"
+                        )
+                    })?;
+                }
+                _ if format.options.set(key, value)? => {}
+                _ => return config_err!("Unknown option '{key}' for STORED AS REGEX"),
+            }
+        }
+        // Report a bad pattern when the table is created, not when it is read.
+        format.compile()?;
+        Ok(format)
+    }
+
+    pub fn with_case_insensitive(mut self, case_insensitive: bool) -> Self {
+        self.case_insensitive = case_insensitive;
+        self
+    }
+
+    pub fn with_mode(mut self, mode: ParseMode) -> Self {
+        self.options.mode = mode;
+        self
+    }
+
+    pub fn with_file_compression_type(mut self, compression: FileCompressionType) -> Self {
+        self.options.compression = compression;
+        self
+    }
+
+    pub fn with_file_extension(mut self, file_extension: impl Into<String>) -> Self {
+        self.options.file_extension = file_extension.into();
+        self
+    }
+
+    pub fn with_record_start(mut self, record_start: RecordStart) -> Self {
+        self.options.record_start = Some(record_start);
+        self
+    }
+
+    pub fn with_max_record_size(mut self, max_record_size: usize) -> Self {
+        self.options.max_record_size = max_record_size;
+        self
+    }
+
+    pub fn input_regex(&self) -> &str {
+        &self.input_regex
+    }
+
+    pub fn mode(&self) -> ParseMode {
+        self.options.mode
+    }
+
+    /// `input_regex` anchored at both ends, so that it must match whole lines.
+    fn compile(&self) -> Result<Regex, RegexpError> {
+        let invalid = |message: String| RegexpError::InvalidPattern {
+            function: NAME,
+            pattern: self.input_regex.clone(),
+            row: 0,
+            message,
+        };
+        let translated = PatternOptions::default()
+            .translate(&format!(r"\A(?:{})\z", self.input_regex))
+            .map_err(invalid)?;
+        RegexBuilder::new(&translated.pattern)
+            .case_insensitive(self.case_insensitive)
+            .build()
+            .map_err(|e| invalid(e.to_string()))
+    }
+
+    /// The schema inferred from the pattern's groups.
+    pub fn schema(&self) -> Result<SchemaRef> {
+        let regex = self.compile()?;
+        let fields = regex
+            .capture_names()
+            .skip(1)
+            .enumerate()
+            .map(|(i, name)| {
+                let name = name.map_or_else(|| format!("_c{i}"), str::to_string);
+                Field::new(name, DataType::Utf8, true)
+            })
+            .collect();
+        Ok(self.options.schema(fields))
+    }
+
+    /// Where each column of `file_schema` comes from: the group of the same
+    /// name, or else the next group that no column takes by name.
+    fn columns(&self, regex: &Regex, file_schema: &Schema) -> Result<Vec<Column>> {
+        let named: Vec<Option<usize>> = file_schema
+            .fields()
+            .iter()
+            .map(|field| {
+                regex
+                    .capture_names()
+                    .position(|name| name == Some(field.name()))
+            })
+            .collect();
+        let mut unclaimed =
+            (1..regex.captures_len()).filter(|group| !named.contains(&Some(*group)));
+        file_schema
+            .fields()
+            .iter()
+            .zip(named.iter().copied())
+            .map(|(field, named)| {
+                if self.options.is_corrupt_record(field) {
+                    return Ok(Column::CorruptRecord);
+                }
+                match named.or_else(|| unclaimed.next()) {
+                    Some(group) => Ok(Column::Group(group)),
+                    None => plan_err!(
+                        "Column `{}` has no capture group in input.regex '{}', which has {} \
+                         groups",
+                        field.name(),
+                        self.input_regex,
+                        regex.captures_len() - 1
+                    ),
+                }
+            })
+            .collect()
+    }
+}
+
+#[async_trait]
+impl FileFormat for RegexFormat {
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn get_ext(&self) -> String {
+        self.options.file_extension.clone()
+    }
+
+    fn get_ext_with_compression(&self, compression: &FileCompressionType) -> Result<String> {
+        Ok(self.options.ext_with_compression(compression))
+    }
+
+    fn compression_type(&self) -> Option<FileCompressionType> {
+        Some(self.options.compression)
+    }
+
+    async fn infer_schema(
+        &self,
+        _state: &dyn Session,
+        _store: &Arc<dyn ObjectStore>,
+        _objects: &[ObjectMeta],
+    ) -> Result<SchemaRef> {
+        self.schema()
+    }
+
+    async fn infer_stats(
+        &self,
+        _state: &dyn Session,
+        _store: &Arc<dyn ObjectStore>,
+        table_schema: SchemaRef,
+        _object: &ObjectMeta,
+    ) -> Result<Statistics> {
+        Ok(Statistics::new_unknown(&table_schema))
+    }
+
+    async fn create_physical_plan(
+        &self,
+        _state: &dyn Session,
+        conf: FileScanConfig,
+    ) -> Result<Arc<dyn ExecutionPlan>> {
+        Ok(scan_plan(conf, Arc::new(self.clone())))
+    }
+
+    fn file_source(&self) -> Arc<dyn FileSource> {
+        Arc::new(TextSource::new(Arc::new(self.clone())))
+    }
+}
+
+impl LineFormat for RegexFormat {
+    fn file_type(&self) -> &str {
+        NAME
+    }
+
+    fn options(&self) -> &TextOptions {
+        &self.options
+    }
+
+    fn decoder(
+        &self,
+        file_schema: &SchemaRef,
+        projection: SchemaRef,
+    ) -> Result<Box<dyn LineDecoder>> {
+        let regex = self.compile()?;
+        let columns = self.columns(&regex, file_schema)?;
+        let columns = projection
+            .fields()
+            .iter()
+            .map(|field| Ok(columns[file_schema.index_of(field.name())?]))
+            .collect::<Result<Vec<_>>>()?;
+        Ok(Box::new(RegexDecoder {
+            regex,
+            mode: self.options.mode,
+            schema: projection,
+            columns,
+        }))
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+enum Column {
+    Group(usize),
+    CorruptRecord,
+}
+
+struct RegexDecoder {
+    regex: Regex,
+    mode: ParseMode,
+    schema: SchemaRef,
+    columns: Vec<Column>,
+}
+
+impl LineDecoder for RegexDecoder {
+    fn decode(&mut self, lines: &[String]) -> Result<RecordBatch> {
+        let mut builders: Vec<StringBuilder> =
+            self.columns.iter().map(|_| StringBuilder::new()).collect();
+        let needs_groups = self
+            .columns
+            .iter()
+            .any(|column| matches!(column, Column::Group(_)));
+        let mut locations = self.regex.capture_locations();
+        let mut num_rows = 0;
+
+        for line in lines {
+            // Only read the groups of projected columns, and only match at
+            // all when the outcome shows up in the result.
+            let matched = match (needs_groups, self.mode) {
+                (true, _) => self.regex.captures_read(&mut locations, line).is_some(),
+                (false, ParseMode::Permissive) => true,
+                (false, _) => self.regex.is_match(line),
+            };
+            if !matched && !self.mode.keep_malformed(NAME, line)? {
+                continue;
+            }
+            num_rows += 1;
+            for (column, builder) in self.columns.iter().zip(&mut builders) {
+                match column {
+                    Column::Group(group) => builder.append_option(
+                        matched
+                            .then(|| locations.get(*group))
+                            .flatten()
+                            .map(|(start, end)| &line[start..end]),
+                    ),
+                    Column::CorruptRecord => {
+                        builder.append_option((!matched).then_some(line.as_str()))
+                    }
+                }
+            }
+        }
+
+        let arrays = builders
+            .iter_mut()
+            .zip(self.schema.fields())
+            .map(|(builder, field)| {
+                let array: ArrayRef = Arc::new(builder.finish());
+                match field.data_type() {
+                    DataType::Utf8 => Ok(array),
+                    data_type => Ok(cast(&array, data_type)?),
+                }
+            })
+            .collect::<Result<Vec<_>>>()?;
+        Ok(RecordBatch::try_new_with_options(
+            Arc::clone(&self.schema),
+            arrays,
+            &RecordBatchOptions::new().with_row_count(Some(num_rows)),
+        )?)
+    }
+}
+
+/// Creates [`RegexFormat`]s for `STORED AS REGEX`.
+#[derive(Debug, Default)]
+pub struct RegexFormatFactory;
+
+impl RegexFormatFactory {
+    pub fn new() -> Self {
+        Self
+    }
+}
+
+impl FileFormatFactory for RegexFormatFactory {
+    fn create(
+        &self,
+        _state: &dyn Session,
+        format_options: &HashMap<String, String>,
+    ) -> Result<Arc<dyn FileFormat>> {
+        Ok(Arc::new(RegexFormat::from_options(format_options)?))
+    }
+
+    /// Reads each line into a single column.
+    fn default(&self) -> Arc<dyn FileFormat> {
+        Arc::new(RegexFormat::new("(.*)"))
+    }
+
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+}
+
+impl GetExt for RegexFormatFactory {
+    fn get_ext(&self) -> String {
+        NAME.to_string()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::text_file::CORRUPT_RECORD_COLUMN;
+    use arrow_array::{Array, Int32Array, StringArray};
+
+    fn decode(format: &RegexFormat, schema: Option<Schema>, lines: &[&str]) -> Result<RecordBatch> {
+        let file_schema = match schema {
+            Some(schema) => Arc::new(schema),
+            None => format.schema()?,
+        };
+        let lines: Vec<String> = lines.iter().map(|line| line.to_string()).collect();
+        format
+            .decoder(&file_schema, Arc::clone(&file_schema))?
+            .decode(&lines)
+    }
+
+    fn strings(batch: &RecordBatch, column: usize) -> Vec<Option<String>> {
+        let array = batch
+            .column(column)
+            .as_any()
+            .downcast_ref::<StringArray>()
+            .unwrap();
+        array.iter().map(|v| v.map(str::to_string)).collect()
+    }
+
+    fn options(pairs: &[(&str, &str)]) -> HashMap<String, String> {
+        pairs
+            .iter()
+            .map(|(key, value)| (key.to_string(), value.to_string()))
+            .collect()
+    }
+
+    #[test]
+    fn test_schema_from_named_and_unnamed_groups() {
+        let format = RegexFormat::new(r"(?<host>\S+) (\S+) (?<status>\d+)");
+        let schema = format.schema().unwrap();
+        let names: Vec<_> = schema.fields().iter().map(|f| f.name().as_str()).collect();
+        assert_eq!(names, ["host", "_c1", "status"]);
+
+        let format = format.with_mode(ParseMode::CorruptRecord);
+        let schema = format.schema().unwrap();
+        assert_eq!(schema.field(3).name(), CORRUPT_RECORD_COLUMN);
+    }
+
+    #[test]
+    fn test_lines_must_match_whole() {
+        let format = RegexFormat::new(r"(\w+)=(\d+)");
+        let batch = decode(&format, None, &["a=1", "b=2 trailing", "c=3"]).unwrap();
+        assert_eq!(
+            strings(&batch, 0),
+            [Some("a".to_string()), None, Some("c".to_string())]
+        );
+        assert_eq!(
+            strings(&batch, 1),
+            [Some("1".to_string()), None, Some("3".to_string())]
+        );
+    }
+
+    #[test]
+    fn test_case_insensitive() {
+        let format = RegexFormat::new(r"level=(info|warn)");
+        let batch = decode(&format, None, &["LEVEL=WARN"]).unwrap();
+        assert_eq!(strings(&batch, 0), [None]);
+
+        let format = format.with_case_insensitive(true);
+        let batch = decode(&format, None, &["LEVEL=WARN"]).unwrap();
+        assert_eq!(strings(&batch, 0), [Some("WARN".to_string())]);
+    }
+
+    #[test]
+    fn test_parse_modes() {
+        let lines = ["a=1", "garbage", "b=2"];
+        let format = RegexFormat::new(r"(\w+)=(\d+)");
+
+        let batch = decode(
+            &format.clone().with_mode(ParseMode::CorruptRecord),
+            None,
+            &lines,
+        )
+        .unwrap();
+        assert_eq!(batch.num_rows(), 3);
+        assert_eq!(
+            strings(&batch, 2),
+            [None, Some("garbage".to_string()), None]
+        );
+
+        let batch = decode(
+            &format.clone().with_mode(ParseMode::DropMalformed),
+            None,
+            &lines,
+        )
+        .unwrap();
+        assert_eq!(
+            strings(&batch, 0),
+            [Some("a".to_string()), Some("b".to_string())]
+        );
+
+        let err = decode(&format.with_mode(ParseMode::FailFast), None, &lines).unwrap_err();
+        let err = RegexpError::find(&err).unwrap();
+        assert_eq!(
+            err.error_class(),
+            "MALFORMED_RECORD_IN_PARSING.WITHOUT_SUGGESTION"
+        );
+        assert!(err.to_string().contains("garbage"));
+    }
+
+    #[test]
+    fn test_declared_columns_by_name_and_position() {
+        let format = RegexFormat::new(r"(\w+) (?<code>\d+)");
+        let schema = Schema::new(vec![
+            Field::new("code", DataType::Int32, true),
+            Field::new("word", DataType::Utf8, true),
+        ]);
+        let batch = decode(&format, Some(schema), &["ok 200", "bad x"]).unwrap();
+        let codes = batch
+            .column(0)
+            .as_any()
+            .downcast_ref::<Int32Array>()
+            .unwrap();
+        assert_eq!(codes.value(0), 200);
+        assert!(codes.is_null(1));
+        // `code` takes group 2 by name, so `word` reads group 1.
+        assert_eq!(strings(&batch, 1), [Some("ok".to_string()), None]);
+
+        let schema = Schema::new(vec![
+            Field::new("a", DataType::Utf8, true),
+            Field::new("b", DataType::Utf8, true),
+            Field::new("c", DataType::Utf8, true),
+        ]);
+        let err = decode(&format, Some(schema), &[]).unwrap_err();
+        assert!(err.to_string().contains("Column `c` has no capture group"));
+    }
+
+    #[test]
+    fn test_projection_without_groups_keeps_row_count() {
+        let format = RegexFormat::new(r"(\w+)").with_mode(ParseMode::DropMalformed);
+        let file_schema = format.schema().unwrap();
+        let lines = vec!["a".to_string(), "b c".to_string(), "d".to_string()];
+        let batch = format
+            .decoder(&file_schema, Arc::new(Schema::empty()))
+            .unwrap()
+            .decode(&lines)
+            .unwrap();
+        assert_eq!(batch.num_rows(), 2);
+    }
+
+    #[test]
+    fn test_from_options() {
+        let format = RegexFormat::from_options(&options(&[
+            ("input.regex", r"(\S+)"),
+            ("input.regex.case.insensitive", "TRUE"),
+            ("format.mode", "failfast"),
+            ("format.compression", "gzip"),
+        ]))
+        .unwrap();
+        assert!(format.case_insensitive);
+        assert_eq!(format.mode(), ParseMode::FailFast);
+        assert_eq!(format.options.compression, FileCompressionType::GZIP);
+
+        let err = RegexFormat::from_options(&options(&[])).unwrap_err();
+        assert!(
+            err.to_string()
+                .contains("requires the 'input.regex' option")
+        );
+        let err = RegexFormat::from_options(&options(&[("input.regex", "(")])).unwrap_err();
+        assert!(RegexpError::find(&err).is_some());
+        let err = RegexFormat::from_options(&options(&[
+            ("input.regex", "(a)"),
+            ("format.mode", "lenient"),
+        ]))
+        .unwrap_err();
+        assert!(err.to_string().contains("Unknown mode 'lenient'"));
+        let err = RegexFormat::from_options(&options(&[
+            ("input.regex", "(a)"),
+            ("format.delimiter", ","),
+        ]))
+        .unwrap_err();
+        assert!(
+            err.to_string()
+                .contains("Unknown option 'format.delimiter'")
+        );
+    }
+}
@@ -0,0 +1,407 @@
+use std::any::Any;
+use std::sync::Arc;
+
+use arrow_array::builder::{ListBuilder, StringBuilder};
+use arrow_array::cast::AsArray;
+use arrow_array::{Array, ArrayRef, StringArray};
+use datafusion_common::Result;
+use datafusion_common::arrow::compute::cast;
+use datafusion_common::arrow::datatypes::{DataType, Field};
+use datafusion_expr::{ColumnarValue, ScalarFunctionArgs, ScalarUDFImpl, Signature, Volatility};
+use regex::RegexSet;
+
+use crate::args::{as_string_array, to_array};
+use crate::config::{Configurable, RegexpConfig};
+use crate::error::RegexpError;
+use crate::metrics::{BatchMetrics, RegexpMetrics};
+use crate::pattern::{PatternCache, PatternOptions};
+
+/// `(label, pattern)` pairs, in rule order.
+type Rules = Vec<(String, String)>;
+
+/// Spark-style rule classification: `regexp_classify(str, rules)` returns
+/// the label of the first rule whose pattern matches `str`, or NULL, and
+/// `regexp_classify_all(str, rules)` every matching label as a `List<Utf8>`.
+///
+/// `rules` is an ordered list of `(label, pattern)` pairs, given either as a
+/// map (`map(['TIMEOUT', 'OOM'], ['timeout', 'OutOfMemory'])`) or as a list
+/// of two-field structs, so it can come from a lookup table:
+///
+/// ```sql
+/// SELECT regexp_classify(line, (SELECT array_agg(named_struct('label', label, 'pattern', pattern)) FROM rules))
+/// FROM logs
+/// ```
+///
+/// All patterns are compiled into one [`RegexSet`] that is cached with the
+/// function instance, so each row is scanned once and the set is reused
+/// across batches. Rules with a NULL label or pattern are ignored.
+#[derive(Debug, Clone)]
+pub struct RegexpClassify {
+    signature: Signature,
+    name: &'static str,
+    all: bool,
+    options: PatternOptions,
+    cache: Arc<PatternCache>,
+    metrics: Arc<RegexpMetrics>,
+}
+
+impl Default for RegexpClassify {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl RegexpClassify {
+    /// `regexp_classify`, returning the first matching label.
+    pub fn new() -> Self {
+        Self {
+            signature: Signature::user_defined(Volatility::Immutable),
+            name: "regexp_classify",
+            all: false,
+            // Only which rules match is read, never where.
+            options: PatternOptions {
+                match_only: true,
+                ..Default::default()
+            },
+            cache: Arc::new(PatternCache::default()),
+            metrics: Arc::new(RegexpMetrics::new()),
+        }
+    }
+
+    /// `regexp_classify_all`, returning every matching label.
+    pub fn all() -> Self {
+        Self {
+            name: "regexp_classify_all",
+            all: true,
+            ..Self::new()
+        }
+    }
+
+    /// Execution metrics, shared by every clone of this instance.
+    pub fn metrics(&self) -> &RegexpMetrics {
+        &self.metrics
+    }
+
+    fn unexpected_rules(&self, actual: &DataType) -> RegexpError {
+        RegexpError::UnexpectedInputType {
+            function: self.name,
+            argument: "rules",
+            expected: "a MAP<STRING, STRING> or an ARRAY<STRUCT<label, pattern>>",
+            actual: actual.to_string(),
+        }
+    }
+
+    /// The `(label, pattern)` pairs of row `row` of the rules argument.
+    fn rules_at(&self, rules: &ArrayRef, row: usize) -> Result<Rules> {
+        let entries = match rules.data_type() {
+            DataType::Map(_, _) => rules.as_map().value(row),
+            DataType::List(_) => rules.as_list::<i32>().value(row).as_struct().clone(),
+            DataType::LargeList(_) => rules.as_list::<i64>().value(row).as_struct().clone(),
+            other => return Err(self.unexpected_rules(other).into()),
+        };
+        let labels = cast(entries.column(0), &DataType::Utf8)?;
+        let patterns = cast(entries.column(1), &DataType::Utf8)?;
+        let labels = labels.as_string::<i32>();
+        let patterns = patterns.as_string::<i32>();
+        Ok(labels
+            .iter()
+            .zip(patterns.iter())
+            .filter_map(|(label, pattern)| Some((label?.to_string(), pattern?.to_string())))
+            .collect())
+    }
+
+    /// Resolves row `rules_row` of the rules argument and compiles its
+    /// patterns into a set, reporting compile errors against input row `row`.
+    fn compile_rules(
+        &self,
+        rules_array: &ArrayRef,
+        rules_row: usize,
+        row: usize,
+        batch_metrics: &mut BatchMetrics,
+    ) -> Result<(Rules, Arc<RegexSet>)> {
+        let rules = self.rules_at(rules_array, rules_row)?;
+        let patterns: Vec<String> = rules.iter().map(|(_, p)| p.clone()).collect();
+        let set = self
+            .cache
+            .get_or_compile_set(self.name, &patterns, self.options, batch_metrics)
+            .map_err(|e| match e {
+                RegexpError::InvalidPattern {
+                    function,
+                    pattern,
+                    message,
+                    ..
+                } => RegexpError::InvalidPattern {
+                    function,
+                    pattern,
+                    row,
+                    message,
+                },
+                other => other,
+            })?;
+        Ok((rules, set))
+    }
+}
+
+fn is_rules_type(data_type: &DataType) -> bool {
+    let entries = match data_type {
+        DataType::Map(entries, _) => entries.data_type(),
+        DataType::List(item) | DataType::LargeList(item) => item.data_type(),
+        DataType::Null => return true,
+        _ => return false,
+    };
+    matches!(entries, DataType::Struct(fields) if fields.len() == 2)
+}
+
+impl Configurable for RegexpClassify {
+    fn with_config(&self, config: &RegexpConfig) -> Option<Self> {
+        let options = PatternOptions {
+            // Rules are matched without a collation.
+            collation: self.options.collation,
+            ..self.options.with_config(config)
+        };
+        (options != self.options).then(|| Self {
+            options,
+            ..self.clone()
+        })
+    }
+}
+
+impl ScalarUDFImpl for RegexpClassify {
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn name(&self) -> &str {
+        self.name
+    }
+
+    fn signature(&self) -> &Signature {
+        &self.signature
+    }
+
+    fn coerce_types(&self, arg_types: &[DataType]) -> Result<Vec<DataType>> {
+        match arg_types {
+            [_, rules] if is_rules_type(rules) => Ok(vec![DataType::Utf8, rules.clone()]),
+            [_, rules] => Err(self.unexpected_rules(rules).into()),
+            _ => Err(RegexpError::Internal {
+                function: self.name,
+                message: format!("expected 2 arguments, got {}", arg_types.len()),
+            }
+            .into()),
+        }
+    }
+
+    fn return_type(&self, _arg_types: &[DataType]) -> Result<DataType> {
+        if self.all {
+            Ok(DataType::List(Arc::new(Field::new_list_field(
+                DataType::Utf8,
+                true,
+            ))))
+        } else {
+            Ok(DataType::Utf8)
+        }
+    }
+
+    fn invoke_with_args(&self, args: ScalarFunctionArgs) -> Result<ColumnarValue> {
+        let num_rows = args.number_rows;
+        let input_array_ref: ArrayRef = to_array(&args.args[0], num_rows)?;
+        let input_array: &StringArray = as_string_array(self.name, "str", &input_array_ref)?;
+        // A literal rule set is one row shared by the whole batch; rules from
+        // a lookup table arrive as a column, usually with identical rows.
+        let (rules_array, rules_are_scalar): (ArrayRef, bool) = match &args.args[1] {
+            ColumnarValue::Scalar(scalar) => (scalar.to_array()?, true),
+            ColumnarValue::Array(array) => (Arc::clone(array), false),
+        };
+
+        let mut first_builder = StringBuilder::new();
+        let mut all_builder = ListBuilder::new(StringBuilder::new());
+        let mut batch_metrics = BatchMetrics::start(self.name, num_rows, &self.metrics);
+        // The rules row the current set was compiled from. Scalar rules are
+        // resolved once up front; a rules column is only re-resolved when a
+        // row's value differs from the one compiled last.
+        let mut current: Option<(usize, Rules, Arc<RegexSet>)> =
+            if rules_are_scalar && !rules_array.is_null(0) {
+                let (rules, set) = self.compile_rules(&rules_array, 0, 0, &mut batch_metrics)?;
+                Some((0, rules, set))
+            } else {
+                None
+            };
+
+        for i in 0..num_rows {
+            let rules_row = if rules_are_scalar { 0 } else { i };
+            if input_array.is_null(i) || rules_array.is_null(rules_row) {
+                first_builder.append_null();
+                all_builder.append_null();
+                continue;
+            }
+
+            let unchanged = current.as_ref().is_some_and(|(last_row, _, _)| {
+                *last_row == rules_row
+                    || rules_array.slice(*last_row, 1).to_data()
+                        == rules_array.slice(rules_row, 1).to_data()
+            });
+            if !unchanged {
+                let (rules, set) =
+                    self.compile_rules(&rules_array, rules_row, i, &mut batch_metrics)?;
+                current = Some((rules_row, rules, set));
+            }
+            let (_, rules, set) = current.as_ref().unwrap();
+            batch_metrics.rows_processed += 1;
+
+            // `SetMatches` yields indexes in ascending order, which is rule
+            // order.
+            let matches = set.matches(input_array.value(i));
+            if matches.matched_any() {
+                batch_metrics.rows_matched += 1;
+            }
+            if self.all {
+                for index in matches.iter() {
+                    all_builder.values().append_value(&rules[index].0);
+                }
+                all_builder.append(true);
+            } else {
+                first_builder
+                    .append_option(matches.iter().next().map(|index| rules[index].0.as_str()));
+            }
+        }
+
+        if self.all {
+            Ok(ColumnarValue::Array(Arc::new(all_builder.finish())))
+        } else {
+            Ok(ColumnarValue::Array(Arc::new(first_builder.finish())))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use arrow_array::builder::{MapBuilder, StringBuilder};
+    use arrow_array::{ListArray, MapArray};
+    use datafusion_common::ScalarValue;
+
+    fn rules(pairs: &[(&str, &str)]) -> ColumnarValue {
+        let mut builder = MapBuilder::new(None, StringBuilder::new(), StringBuilder::new());
+        for (label, pattern) in pairs {
+            builder.keys().append_value(label);
+            builder.values().append_value(pattern);
+        }
+        builder.append(true).unwrap();
+        let map: MapArray = builder.finish();
+        ColumnarValue::Scalar(ScalarValue::Map(Arc::new(map)))
+    }
+
+    fn invoke(udf: &RegexpClassify, input: Vec<Option<&str>>, rules: ColumnarValue) -> ArrayRef {
+        let num_rows = input.len();
+        let args = ScalarFunctionArgs {
+            args: vec![
+                ColumnarValue::Array(Arc::new(StringArray::from(input))),
+                rules,
+            ],
+            number_rows: num_rows,
+            arg_fields: vec![],
+            return_field: Arc::new(Field::new("result", udf.return_type(&[]).unwrap(), true)),
+        };
+        udf.invoke_with_args(args)
+            .unwrap()
+            .to_array(num_rows)
+            .unwrap()
+    }
+
+    const RULES: &[(&str, &str)] = &[
+        ("TIMEOUT", "(?i)timed? ?out"),
+        ("OOM", "OutOfMemory"),
+        ("JAVA", r"java\.\w+"),
+    ];
+
+    #[test]
+    fn test_classify_first_label_in_rule_order() {
+        let result = invoke(
+            &RegexpClassify::new(),
+            vec![
+                Some("java.lang.OutOfMemoryError"),
+                Some("request Timed out"),
+                Some("ok"),
+                None,
+            ],
+            rules(RULES),
+        );
+        let labels = result.as_string::<i32>();
+        assert_eq!(
+            labels.iter().collect::<Vec<_>>(),
+            vec![Some("OOM"), Some("TIMEOUT"), None, None]
+        );
+    }
+
+    #[test]
+    fn test_classify_all_labels() {
+        let result = invoke(
+            &RegexpClassify::all(),
+            vec![Some("java.lang.OutOfMemoryError"), Some("ok")],
+            rules(RULES),
+        );
+        let lists = result.as_any().downcast_ref::<ListArray>().unwrap();
+        let labels = |i: usize| -> Vec<String> {
+            lists
+                .value(i)
+                .as_string::<i32>()
+                .iter()
+                .map(|v| v.unwrap().to_string())
+                .collect()
+        };
+        assert_eq!(labels(0), vec!["OOM", "JAVA"]);
+        assert!(labels(1).is_empty());
+    }
+
+    #[test]
+    fn test_classify_reuses_set_across_batches() {
+        let udf = RegexpClassify::new();
+        invoke(&udf, vec![Some("a")], rules(RULES));
+        invoke(&udf, vec![Some("b")], rules(RULES));
+        let snapshot = udf.metrics().snapshot();
+        assert_eq!((snapshot.compilations, snapshot.cache_hits), (1, 1));
+    }
+
+    #[test]
+    fn test_classify_resolves_rules_only_when_they_change() {
+        let udf = RegexpClassify::new();
+        invoke(&udf, vec![Some("a"), Some("b"), Some("c")], rules(RULES));
+        let snapshot = udf.metrics().snapshot();
+        assert_eq!((snapshot.compilations, snapshot.cache_hits), (1, 0));
+
+        let mut builder = MapBuilder::new(None, StringBuilder::new(), StringBuilder::new());
+        for pattern in ["a", "a", "b", "a"] {
+            builder.keys().append_value(pattern.to_uppercase());
+            builder.values().append_value(pattern);
+            builder.append(true).unwrap();
+        }
+        let column = ColumnarValue::Array(Arc::new(builder.finish()));
+        let result = invoke(
+            &udf,
+            vec![Some("a"), Some("b"), Some("b"), Some("a")],
+            column,
+        );
+        assert_eq!(
+            result.as_string::<i32>().iter().collect::<Vec<_>>(),
+            vec![Some("A"), None, Some("B"), Some("A")]
+        );
+        let snapshot = udf.metrics().snapshot();
+        assert_eq!((snapshot.compilations, snapshot.cache_hits), (3, 1));
+    }
+
+    #[test]
+    fn test_classify_invalid_rule_pattern() {
+        let udf = RegexpClassify::new();
+        let args = ScalarFunctionArgs {
+            args: vec![
+                ColumnarValue::Array(Arc::new(StringArray::from(vec!["x"]))),
+                rules(&[("OK", "x"), ("BAD", "(")]),
+            ],
+            number_rows: 1,
+            arg_fields: vec![],
+            return_field: Arc::new(Field::new("result", DataType::Utf8, true)),
+        };
+        let err = udf.invoke_with_args(args).unwrap_err();
+        assert_eq!(RegexpError::find(&err).unwrap().pattern(), Some("("));
+    }
+}
@@ -0,0 +1,174 @@
+use std::any::Any;
+use std::sync::Arc;
+
+use arrow_array::builder::Int32Builder;
+use arrow_array::{Array, ArrayRef, StringArray};
+use datafusion_common::Result;
+use datafusion_common::arrow::datatypes::DataType;
+use datafusion_expr::{
+    ColumnarValue, ScalarFunctionArgs, ScalarUDFImpl, Signature, TypeSignature, Volatility,
+};
+use regex::Regex;
+
+use crate::args::{as_string_array, extract_input_and_pattern};
+use crate::collation::Collation;
+use crate::config::{Configurable, RegexpConfig};
+use crate::metrics::{BatchMetrics, RegexpMetrics};
+use crate::pattern::{BatchPatterns, PatternCache, PatternOptions, java_find_iter};
+
+const NAME: &str = "regexp_count";
+
+/// Spark's `regexp_count(str, regexp)`: the number of non-overlapping matches
+/// of `regexp` in `str`, as `Int32`.
+///
+/// Matches are counted the way Java's `Matcher.find` reports them, so
+/// `regexp_count('aab', 'a*')` is 3: `"aa"`, the empty match before `b` and
+/// the empty match at the end. A NULL `str` or `regexp` yields NULL.
+#[derive(Debug, Clone)]
+pub struct RegexpCount {
+    signature: Signature,
+    options: PatternOptions,
+    cache: Arc<PatternCache>,
+    metrics: Arc<RegexpMetrics>,
+}
+
+impl Default for RegexpCount {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl RegexpCount {
+    pub fn new() -> Self {
+        Self {
+            signature: Signature::one_of(
+                vec![TypeSignature::Exact(vec![DataType::Utf8, DataType::Utf8])],
+                Volatility::Immutable,
+            ),
+            options: PatternOptions::default(),
+            cache: Arc::new(PatternCache::default()),
+            metrics: Arc::new(RegexpMetrics::new()),
+        }
+    }
+
+    /// The collation patterns are matched under.
+    pub fn with_collation(mut self, collation: Collation) -> Self {
+        self.options.collation = collation;
+        self
+    }
+
+    /// Execution metrics, shared by every clone of this instance.
+    pub fn metrics(&self) -> &RegexpMetrics {
+        &self.metrics
+    }
+}
+
+impl Configurable for RegexpCount {
+    fn with_config(&self, config: &RegexpConfig) -> Option<Self> {
+        let options = self.options.with_config(config);
+        (options != self.options).then(|| Self {
+            options,
+            ..self.clone()
+        })
+    }
+}
+
+impl ScalarUDFImpl for RegexpCount {
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn name(&self) -> &str {
+        NAME
+    }
+
+    fn signature(&self) -> &Signature {
+        &self.signature
+    }
+
+    fn return_type(&self, _arg_types: &[DataType]) -> Result<DataType> {
+        Ok(DataType::Int32)
+    }
+
+    fn invoke_with_args(&self, args: ScalarFunctionArgs) -> Result<ColumnarValue> {
+        let num_rows = args.number_rows;
+        let (input_array_ref, pattern_array_ref): (ArrayRef, ArrayRef) =
+            extract_input_and_pattern(&args.args[0], &args.args[1], num_rows)?;
+        let input_array: &StringArray = as_string_array(NAME, "str", &input_array_ref)?;
+        let pattern_array: &StringArray = as_string_array(NAME, "regexp", &pattern_array_ref)?;
+
+        let mut builder = Int32Builder::with_capacity(num_rows);
+        let mut batch_metrics = BatchMetrics::start(NAME, num_rows, &self.metrics);
+        let mut patterns = BatchPatterns::new(NAME, &self.cache, self.options);
+
+        for i in 0..num_rows {
+            if input_array.is_null(i) || pattern_array.is_null(i) {
+                builder.append_null();
+                continue;
+            }
+
+            let compiled_regex: Arc<Regex> =
+                patterns.get(pattern_array.value(i), i, &mut batch_metrics)?;
+            batch_metrics.rows_processed += 1;
+
+            let text = self.options.collation.prepare_text(input_array.value(i));
+            let count = java_find_iter(&compiled_regex, text.as_str()).count();
+            if count > 0 {
+                batch_metrics.rows_matched += 1;
+            }
+            builder.append_value(count as i32);
+        }
+
+        Ok(ColumnarValue::Array(Arc::new(builder.finish())))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use arrow_array::Int32Array;
+    use datafusion_common::ScalarValue;
+    use datafusion_common::arrow::datatypes::Field;
+
+    fn run_test(input: Vec<Option<&str>>, pattern: &str) -> Vec<Option<i32>> {
+        let num_rows = input.len();
+        let args = ScalarFunctionArgs {
+            args: vec![
+                ColumnarValue::Array(Arc::new(StringArray::from(input))),
+                ColumnarValue::Scalar(ScalarValue::from(pattern)),
+            ],
+            number_rows: num_rows,
+            arg_fields: vec![],
+            return_field: Arc::new(Field::new("result", DataType::Int32, true)),
+        };
+        let result = RegexpCount::new().invoke_with_args(args).unwrap();
+        let array = result.to_array(num_rows).unwrap();
+        let counts = array.as_any().downcast_ref::<Int32Array>().unwrap();
+        counts.iter().collect()
+    }
+
+    #[test]
+    fn test_count_non_overlapping_matches() {
+        let result = run_test(
+            vec![Some("E01 ok E02 E03"), Some("ok"), Some("aaaa"), None],
+            r"E\d\d|aa",
+        );
+        assert_eq!(result, vec![Some(3), Some(0), Some(2), None]);
+    }
+
+    #[test]
+    fn test_count_empty_matches_advance_like_java() {
+        assert_eq!(run_test(vec![Some("aab")], "a*"), vec![Some(3)]);
+        assert_eq!(run_test(vec![Some("abc")], ""), vec![Some(4)]);
+        assert_eq!(run_test(vec![Some("")], ""), vec![Some(1)]);
+        assert_eq!(run_test(vec![Some("")], "a"), vec![Some(0)]);
+        assert_eq!(run_test(vec![Some("a,b,,c")], ",?"), vec![Some(7)]);
+    }
+
+    #[test]
+    fn test_count_unicode() {
+        assert_eq!(run_test(vec![Some("你好-世界")], ""), vec![Some(6)]);
+        assert_eq!(run_test(vec![Some("你好-世界-你")], "你"), vec![Some(2)]);
+        assert_eq!(run_test(vec![Some("Gödel ünd")], r"\w+"), vec![Some(3)]);
+    }
+}
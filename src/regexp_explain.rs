@@ -0,0 +1,455 @@
+use std::any::Any;
+use std::sync::Arc;
+
+use arrow_array::builder::{
+    BooleanBuilder, Int32Builder, ListBuilder, NullBufferBuilder, StringBuilder,
+};
+use arrow_array::{Array, ArrayRef, StringArray, StructArray};
+use datafusion_common::Result;
+use datafusion_common::arrow::datatypes::{DataType, Field, Fields};
+use datafusion_expr::{ColumnarValue, ScalarFunctionArgs, ScalarUDFImpl, Signature, Volatility};
+use regex_syntax::ParserBuilder;
+use regex_syntax::hir::Hir;
+use regex_syntax::hir::literal::{ExtractKind, Extractor};
+
+use crate::args::{as_string_array, to_array};
+use crate::collation::Collation;
+use crate::config::{Configurable, RegexpConfig};
+use crate::dialect::RegexDialect;
+use crate::error::RegexpError;
+use crate::metrics::{BatchMetrics, RegexpMetrics};
+use crate::pattern::{PatternCache, PatternOptions};
+
+const NAME: &str = "regexp_explain";
+
+/// `regexp_explain(regexp)`: how `regexp` is compiled, as a struct with
+///
+/// - `valid`: whether it compiles, and `error` the reason when it does not;
+/// - `dialect`: the dialect it was read in;
+/// - `engine`: `literal` when every match is one fixed string, `literal_set`
+///   when it is one of a fixed set, found without running an automaton, and
+///   `automaton` otherwise;
+/// - `group_count` and `group_names`, with NULL for unnamed groups;
+/// - `literal_prefix` and `literal_suffix`: text every match starts or ends
+///   with, or NULL when there is none;
+/// - `runs_as_java`: whether, read as a Java pattern, it can be run here.
+///   This is not Java validity: lookaround and backreferences are valid
+///   Java but cannot be translated for the `regex` crate, so they are
+///   `false`;
+/// - `translations`: the constructs rewritten to run on the `regex` crate.
+///
+/// The pattern goes through the same translation and [`PatternCache`] as
+/// [`RegexpExtract`](crate::regexp_extract::RegexpExtract), so what is
+/// reported is what the extraction functions run. The components of a
+/// pattern that does not compile are NULL. A NULL pattern yields NULL.
+#[derive(Debug, Clone)]
+pub struct RegexpExplain {
+    signature: Signature,
+    options: PatternOptions,
+    cache: Arc<PatternCache>,
+    metrics: Arc<RegexpMetrics>,
+}
+
+impl Default for RegexpExplain {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl RegexpExplain {
+    pub fn new() -> Self {
+        Self {
+            signature: Signature::exact(vec![DataType::Utf8], Volatility::Immutable),
+            options: PatternOptions::default(),
+            cache: Arc::new(PatternCache::default()),
+            metrics: Arc::new(RegexpMetrics::new()),
+        }
+    }
+
+    /// The dialect patterns are read in.
+    pub fn with_dialect(mut self, dialect: RegexDialect) -> Self {
+        self.options.dialect = dialect;
+        self
+    }
+
+    /// The collation patterns are compiled under.
+    pub fn with_collation(mut self, collation: Collation) -> Self {
+        self.options.collation = collation;
+        self
+    }
+
+    /// Execution metrics, shared by every clone of this instance.
+    pub fn metrics(&self) -> &RegexpMetrics {
+        &self.metrics
+    }
+
+    fn fields() -> Fields {
+        let list = |name: &str, nullable_items: bool, nullable: bool| {
+            Field::new(
+                name,
+                DataType::List(Arc::new(Field::new_list_field(
+                    DataType::Utf8,
+                    nullable_items,
+                ))),
+                nullable,
+            )
+        };
+        Fields::from(vec![
+            Field::new("valid", DataType::Boolean, false),
+            Field::new("error", DataType::Utf8, true),
+            Field::new("dialect", DataType::Utf8, false),
+            Field::new("engine", DataType::Utf8, true),
+            Field::new("group_count", DataType::Int32, true),
+            list("group_names", true, true),
+            Field::new("literal_prefix", DataType::Utf8, true),
+            Field::new("literal_suffix", DataType::Utf8, true),
+            Field::new("runs_as_java", DataType::Boolean, false),
+            list("translations", false, false),
+        ])
+    }
+
+    /// The syntax tree of the compiled pattern, parsed with the same flags
+    /// as [`PatternCache`] compiles it.
+    fn hir(&self, translated: &str) -> Option<Hir> {
+        ParserBuilder::new()
+            .case_insensitive(self.options.collation.is_case_insensitive())
+            .build()
+            .parse(translated)
+            .ok()
+    }
+}
+
+fn engine(hir: &Hir) -> &'static str {
+    if hir.properties().is_literal() {
+        "literal"
+    } else if hir.properties().is_alternation_literal() {
+        "literal_set"
+    } else {
+        "automaton"
+    }
+}
+
+/// The literal every match starts (or, for [`ExtractKind::Suffix`], ends)
+/// with, cut back to whole characters.
+fn required_literal(hir: &Hir, kind: ExtractKind) -> Option<String> {
+    let mut extractor = Extractor::new();
+    let seq = extractor.kind(kind.clone()).extract(hir);
+    let bytes = match kind {
+        ExtractKind::Suffix => {
+            let suffix = seq.longest_common_suffix()?;
+            let start = (0..=suffix.len())
+                .find(|&i| std::str::from_utf8(&suffix[i..]).is_ok())
+                .unwrap_or(suffix.len());
+            &suffix[start..]
+        }
+        _ => {
+            let prefix = seq.longest_common_prefix()?;
+            match std::str::from_utf8(prefix) {
+                Ok(_) => prefix,
+                Err(e) => &prefix[..e.valid_up_to()],
+            }
+        }
+    };
+    let literal = std::str::from_utf8(bytes).ok()?;
+    (!literal.is_empty()).then(|| literal.to_string())
+}
+
+impl Configurable for RegexpExplain {
+    fn with_config(&self, config: &RegexpConfig) -> Option<Self> {
+        let options = self.options.with_config(config);
+        (options != self.options).then(|| Self {
+            options,
+            ..self.clone()
+        })
+    }
+}
+
+impl ScalarUDFImpl for RegexpExplain {
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn name(&self) -> &str {
+        NAME
+    }
+
+    fn signature(&self) -> &Signature {
+        &self.signature
+    }
+
+    fn return_type(&self, _arg_types: &[DataType]) -> Result<DataType> {
+        Ok(DataType::Struct(Self::fields()))
+    }
+
+    fn invoke_with_args(&self, args: ScalarFunctionArgs) -> Result<ColumnarValue> {
+        let num_rows = args.number_rows;
+        let pattern_array_ref: ArrayRef = to_array(&args.args[0], num_rows)?;
+        let pattern_array: &StringArray = as_string_array(NAME, "regexp", &pattern_array_ref)?;
+
+        let mut valid = BooleanBuilder::with_capacity(num_rows);
+        let mut error = StringBuilder::new();
+        let mut dialect = StringBuilder::new();
+        let mut engines = StringBuilder::new();
+        let mut group_count = Int32Builder::with_capacity(num_rows);
+        let mut group_names = ListBuilder::new(StringBuilder::new());
+        let mut prefix = StringBuilder::new();
+        let mut suffix = StringBuilder::new();
+        let mut runs_as_java = BooleanBuilder::with_capacity(num_rows);
+        let mut translations = ListBuilder::new(StringBuilder::new())
+            .with_field(Field::new_list_field(DataType::Utf8, false));
+        let mut nulls = NullBufferBuilder::new(num_rows);
+        let mut batch_metrics = BatchMetrics::start(NAME, num_rows, &self.metrics);
+
+        for i in 0..num_rows {
+            if pattern_array.is_null(i) {
+                // The children of a null struct slot still need a value.
+                valid.append_value(false);
+                error.append_null();
+                dialect.append_value("");
+                engines.append_null();
+                group_count.append_null();
+                group_names.append_null();
+                prefix.append_null();
+                suffix.append_null();
+                runs_as_java.append_value(false);
+                translations.append(true);
+                nulls.append_null();
+                continue;
+            }
+            batch_metrics.rows_processed += 1;
+
+            let pattern = pattern_array.value(i);
+            let java_options = PatternOptions {
+                dialect: RegexDialect::Java,
+                ..self.options
+            };
+            runs_as_java.append_value(
+                self.cache
+                    .get_or_compile(NAME, pattern, java_options, i, &mut batch_metrics)
+                    .is_ok(),
+            );
+            dialect.append_value(self.options.dialect.name());
+            let notes = self
+                .options
+                .translate(pattern)
+                .map(|translation| translation.notes)
+                .unwrap_or_default();
+            for note in notes {
+                translations.values().append_value(note);
+            }
+            translations.append(true);
+
+            let compiled =
+                self.cache
+                    .get_or_compile(NAME, pattern, self.options, i, &mut batch_metrics);
+            let regex = match compiled {
+                Ok(regex) => regex,
+                Err(RegexpError::InvalidPattern { message, .. }) => {
+                    valid.append_value(false);
+                    error.append_value(message);
+                    engines.append_null();
+                    group_count.append_null();
+                    group_names.append_null();
+                    prefix.append_null();
+                    suffix.append_null();
+                    nulls.append_non_null();
+                    continue;
+                }
+                Err(other) => return Err(other.into()),
+            };
+            batch_metrics.rows_matched += 1;
+            valid.append_value(true);
+            error.append_null();
+            group_count.append_value(regex.captures_len() as i32 - 1);
+            for name in regex.capture_names().skip(1) {
+                group_names.values().append_option(name);
+            }
+            group_names.append(true);
+
+            let hir = self
+                .options
+                .translate(pattern)
+                .ok()
+                .and_then(|translation| self.hir(&translation.pattern));
+            engines.append_option(hir.as_ref().map(engine));
+            prefix.append_option(
+                hir.as_ref()
+                    .and_then(|hir| required_literal(hir, ExtractKind::Prefix)),
+            );
+            suffix.append_option(
+                hir.as_ref()
+                    .and_then(|hir| required_literal(hir, ExtractKind::Suffix)),
+            );
+            nulls.append_non_null();
+        }
+
+        let array = StructArray::try_new(
+            Self::fields(),
+            vec![
+                Arc::new(valid.finish()),
+                Arc::new(error.finish()),
+                Arc::new(dialect.finish()),
+                Arc::new(engines.finish()),
+                Arc::new(group_count.finish()),
+                Arc::new(group_names.finish()),
+                Arc::new(prefix.finish()),
+                Arc::new(suffix.finish()),
+                Arc::new(runs_as_java.finish()),
+                Arc::new(translations.finish()),
+            ],
+            nulls.finish(),
+        )?;
+        Ok(ColumnarValue::Array(Arc::new(array)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use arrow_array::cast::AsArray;
+    use arrow_array::types::Int32Type;
+
+    #[derive(Debug, PartialEq)]
+    struct Explained {
+        valid: bool,
+        error: Option<String>,
+        engine: Option<String>,
+        group_count: Option<i32>,
+        group_names: Option<Vec<Option<String>>>,
+        literal_prefix: Option<String>,
+        literal_suffix: Option<String>,
+        runs_as_java: bool,
+        translations: Vec<String>,
+    }
+
+    fn explain(udf: &RegexpExplain, patterns: Vec<Option<&str>>) -> Vec<Option<Explained>> {
+        let num_rows = patterns.len();
+        let args = ScalarFunctionArgs {
+            args: vec![ColumnarValue::Array(Arc::new(StringArray::from(patterns)))],
+            number_rows: num_rows,
+            arg_fields: vec![],
+            return_field: Arc::new(Field::new("result", udf.return_type(&[]).unwrap(), true)),
+        };
+        let array = udf
+            .invoke_with_args(args)
+            .unwrap()
+            .to_array(num_rows)
+            .unwrap();
+        let structs = array.as_struct();
+        let string = |name: &str, i: usize| {
+            let column = structs.column_by_name(name).unwrap().as_string::<i32>();
+            column.is_valid(i).then(|| column.value(i).to_string())
+        };
+        let boolean =
+            |name: &str, i: usize| structs.column_by_name(name).unwrap().as_boolean().value(i);
+        let list = |name: &str, i: usize| {
+            let column = structs.column_by_name(name).unwrap().as_list::<i32>();
+            column.is_valid(i).then(|| {
+                column
+                    .value(i)
+                    .as_string::<i32>()
+                    .iter()
+                    .map(|v| v.map(str::to_string))
+                    .collect::<Vec<_>>()
+            })
+        };
+        (0..num_rows)
+            .map(|i| {
+                let group_count = structs
+                    .column_by_name("group_count")
+                    .unwrap()
+                    .as_primitive::<Int32Type>();
+                structs.is_valid(i).then(|| Explained {
+                    valid: boolean("valid", i),
+                    error: string("error", i),
+                    engine: string("engine", i),
+                    group_count: group_count.is_valid(i).then(|| group_count.value(i)),
+                    group_names: list("group_names", i),
+                    literal_prefix: string("literal_prefix", i),
+                    literal_suffix: string("literal_suffix", i),
+                    runs_as_java: boolean("runs_as_java", i),
+                    translations: list("translations", i)
+                        .unwrap()
+                        .into_iter()
+                        .flatten()
+                        .collect(),
+                })
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_groups_literals_and_translations() {
+        let result = explain(
+            &RegexpExplain::new(),
+            vec![Some(r"ERROR (?<code>\d+): (.*) at line"), None],
+        );
+        assert_eq!(
+            result,
+            vec![
+                Some(Explained {
+                    valid: true,
+                    error: None,
+                    engine: Some("automaton".to_string()),
+                    group_count: Some(2),
+                    group_names: Some(vec![Some("code".to_string()), None]),
+                    literal_prefix: Some("ERROR ".to_string()),
+                    literal_suffix: Some(" at line".to_string()),
+                    runs_as_java: true,
+                    translations: vec![
+                        r"\d, \w and \s are ASCII-only in Java".to_string(),
+                        "`.` excludes all Java line terminators".to_string(),
+                    ],
+                }),
+                None
+            ]
+        );
+    }
+
+    #[test]
+    fn test_engines() {
+        let engines: Vec<Option<String>> = explain(
+            &RegexpExplain::new(),
+            vec![Some("GET"), Some("GET|POST"), Some("[A-Z]+")],
+        )
+        .into_iter()
+        .map(|explained| explained.unwrap().engine)
+        .collect();
+        assert_eq!(
+            engines,
+            vec![
+                Some("literal".to_string()),
+                Some("literal_set".to_string()),
+                Some("automaton".to_string())
+            ]
+        );
+    }
+
+    #[test]
+    fn test_invalid_and_dialect_specific_patterns() {
+        let rust = RegexpExplain::new().with_dialect(RegexDialect::Rust);
+        let result = explain(&rust, vec![Some(r"a(?=b)"), Some(r"\Qa.b\E")]);
+
+        let lookahead = result[0].as_ref().unwrap();
+        assert!(!lookahead.valid);
+        assert!(lookahead.error.is_some());
+        assert_eq!(lookahead.group_count, None);
+        // Valid Java, but lookaround cannot be translated.
+        assert!(!lookahead.runs_as_java);
+
+        // `\Q...\E` quoting is Java-only syntax.
+        let java_only = result[1].as_ref().unwrap();
+        assert!(!java_only.valid);
+        assert!(java_only.runs_as_java);
+        assert!(java_only.translations.is_empty());
+    }
+
+    #[test]
+    fn test_case_insensitive_collation_has_no_literals() {
+        let udf = RegexpExplain::new().with_collation(Collation::Utf8Lcase);
+        let result = explain(&udf, vec![Some("abc")]);
+        let explained = result[0].as_ref().unwrap();
+        assert_eq!(explained.literal_prefix, None);
+        assert_eq!(explained.engine.as_deref(), Some("automaton"));
+    }
+}
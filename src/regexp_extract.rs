@@ -3,34 +3,129 @@ use std::sync::Arc;
 
 use arrow_array::builder::StringBuilder;
 use arrow_array::{Array, ArrayRef, StringArray};
+use datafusion_common::Result;
 use datafusion_common::arrow::datatypes::DataType;
-use datafusion_common::{Result, ScalarValue};
-use datafusion_expr::{ColumnarValue, ScalarFunctionArgs, ScalarUDFImpl, Signature, Volatility};
+use datafusion_expr::{
+    ColumnarValue, ScalarFunctionArgs, ScalarUDFImpl, Signature, TypeSignature, Volatility,
+};
 use regex::Regex;
 
-fn extract_input_and_pattern(
-    arg1: &ColumnarValue,
-    arg2: &ColumnarValue,
-    num_rows: usize,
-) -> Result<(ArrayRef, ArrayRef)> {
-    // looks like we need to check the first argument to check if
-    // it is a scalar because
-    // of the case SELECT regexp_extract('2023-12-25', '(\\d{4})-(\\d{2})-(\\d{2})', 1) FROM my_table;
-    // in this case optimizer will convert the first option to a scalar
-    let input_array: ArrayRef = match arg1 {
-        ColumnarValue::Array(array) => array.clone(),
-        ColumnarValue::Scalar(scalar) => scalar.to_array_of_size(num_rows)?,
-    };
-    let pattern_array: ArrayRef = match arg2 {
-        ColumnarValue::Array(array) => array.clone(),
-        ColumnarValue::Scalar(scalar) => scalar.to_array_of_size(num_rows)?,
-    };
-    Ok((input_array, pattern_array))
-}
+use crate::args::{as_string_array, extract_input_and_pattern, scalar_group_index};
+use crate::collation::Collation;
+use crate::config::{Configurable, ErrorMode, RegexpConfig};
+use crate::dialect::RegexDialect;
+use crate::metrics::{BatchMetrics, RegexpMetrics};
+use crate::pattern::{
+    BatchPatterns, DEFAULT_CACHE_CAPACITY, PatternCache, PatternOptions, check_group_index,
+};
+
+const NAME: &str = "regexp_extract";
 
 #[derive(Debug, Clone)]
 pub struct RegexpExtract {
     signature: Signature,
+    settings: Settings,
+    cache: Arc<PatternCache>,
+    metrics: Arc<RegexpMetrics>,
+}
+
+/// Everything a [`RegexpExtractBuilder`] or the session's [`RegexpConfig`]
+/// can change.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+struct Settings {
+    collation: Collation,
+    dialect: RegexDialect,
+    legacy_group_index: bool,
+    error_mode: ErrorMode,
+    size_limit: Option<usize>,
+}
+
+/// Builds a [`RegexpExtract`] with per-deployment defaults.
+///
+/// ```ignore
+/// let udf = RegexpExtract::builder()
+///     .dialect(RegexDialect::Rust)
+///     .error_mode(ErrorMode::Null)
+///     .cache_capacity(1024)
+///     .build();
+/// ```
+///
+/// Everything but the cache capacity can be overridden per session through
+/// [`RegexpConfig`].
+#[derive(Debug, Clone)]
+pub struct RegexpExtractBuilder {
+    settings: Settings,
+    cache_capacity: usize,
+}
+
+impl Default for RegexpExtractBuilder {
+    fn default() -> Self {
+        Self {
+            settings: Settings::default(),
+            cache_capacity: DEFAULT_CACHE_CAPACITY,
+        }
+    }
+}
+
+impl RegexpExtractBuilder {
+    /// The collation used when the call does not pass one.
+    pub fn collation(mut self, collation: Collation) -> Self {
+        self.settings.collation = collation;
+        self
+    }
+
+    /// The dialect patterns are written in; Java by default.
+    pub fn dialect(mut self, dialect: RegexDialect) -> Self {
+        self.settings.dialect = dialect;
+        self
+    }
+
+    /// Whether a negative group index yields an empty string, like one past
+    /// the last group, instead of the `REGEX_GROUP_INDEX` error.
+    pub fn legacy_group_index(mut self, legacy_group_index: bool) -> Self {
+        self.settings.legacy_group_index = legacy_group_index;
+        self
+    }
+
+    /// What to do with a row whose pattern or group index is invalid.
+    pub fn error_mode(mut self, error_mode: ErrorMode) -> Self {
+        self.settings.error_mode = error_mode;
+        self
+    }
+
+    /// The compiled size limit of each pattern, in bytes. Larger patterns
+    /// fail to compile, which bounds the memory a query can spend on them.
+    pub fn size_limit(mut self, size_limit: usize) -> Self {
+        self.settings.size_limit = Some(size_limit);
+        self
+    }
+
+    /// How many compiled patterns to keep; 0 disables the cache.
+    pub fn cache_capacity(mut self, cache_capacity: usize) -> Self {
+        self.cache_capacity = cache_capacity;
+        self
+    }
+
+    pub fn build(self) -> RegexpExtract {
+        RegexpExtract {
+            signature: Signature::one_of(
+                vec![
+                    TypeSignature::Exact(vec![DataType::Utf8, DataType::Utf8, DataType::Int64]),
+                    // regexp_extract(str, regexp, idx, collation)
+                    TypeSignature::Exact(vec![
+                        DataType::Utf8,
+                        DataType::Utf8,
+                        DataType::Int64,
+                        DataType::Utf8,
+                    ]),
+                ],
+                Volatility::Immutable,
+            ),
+            settings: self.settings,
+            cache: Arc::new(PatternCache::new(self.cache_capacity)),
+            metrics: Arc::new(RegexpMetrics::new()),
+        }
+    }
 }
 
 impl Default for RegexpExtract {
@@ -41,12 +136,44 @@ impl Default for RegexpExtract {
 
 impl RegexpExtract {
     pub fn new() -> Self {
-        Self {
-            signature: Signature::exact(
-                vec![DataType::Utf8, DataType::Utf8, DataType::Int64],
-                Volatility::Immutable,
-            ),
-        }
+        Self::builder().build()
+    }
+
+    pub fn builder() -> RegexpExtractBuilder {
+        RegexpExtractBuilder::default()
+    }
+
+    /// The collation used when the call does not pass one.
+    pub fn with_collation(mut self, collation: Collation) -> Self {
+        self.settings.collation = collation;
+        self
+    }
+
+    /// Execution metrics, shared by every clone of this instance.
+    ///
+    /// From a registered `ScalarUDF`, reach them through
+    /// `udf.inner().as_any().downcast_ref::<RegexpExtract>()`.
+    pub fn metrics(&self) -> &RegexpMetrics {
+        &self.metrics
+    }
+}
+
+impl Configurable for RegexpExtract {
+    fn with_config(&self, config: &RegexpConfig) -> Option<Self> {
+        let current = self.settings;
+        let settings = Settings {
+            collation: config.collation.unwrap_or(current.collation),
+            dialect: config.dialect.unwrap_or(current.dialect),
+            legacy_group_index: config
+                .legacy_group_index
+                .unwrap_or(current.legacy_group_index),
+            error_mode: config.error_mode.unwrap_or(current.error_mode),
+            size_limit: config.size_limit.or(current.size_limit),
+        };
+        (settings != current).then(|| Self {
+            settings,
+            ..self.clone()
+        })
     }
 }
 
@@ -56,7 +183,7 @@ impl ScalarUDFImpl for RegexpExtract {
     }
 
     fn name(&self) -> &str {
-        "regexp_extract"
+        NAME
     }
 
     fn signature(&self) -> &Signature {
@@ -78,9 +205,11 @@ impl ScalarUDFImpl for RegexpExtract {
         // input_col:    ColumnarValue::Array(["Event on 2023-12-25 was successful"])
         // pattern_col:  ColumnarValue::Scalar("(\\d{4})-(\\d{2})-(\\d{2})")
         // idx_col:      ColumnarValue::Scalar(1)
+        // An optional fourth argument names the collation to match under.
         let input_col = &args.args[0];
         let pattern_col = &args.args[1];
         let idx_col = &args.args[2];
+        let collation = Collation::from_argument(NAME, args.args.get(3), self.settings.collation)?;
 
         // --- Step 3: Normalize Inputs to Arrays ---
         // Our helper function ensures everything is an array of `num_rows`.
@@ -92,48 +221,33 @@ impl ScalarUDFImpl for RegexpExtract {
 
         // --- Step 4: Downcast to Specific Array Types ---
         // We convert the generic `ArrayRef` to the concrete `StringArray` we need.
-        let input_array: &StringArray = input_array_ref
-            .as_any()
-            .downcast_ref::<StringArray>()
-            .ok_or_else(|| {
-                datafusion_common::DataFusionError::Internal("Expected a StringArray".to_string())
-            })?;
-        let pattern_array: &StringArray = pattern_array_ref
-            .as_any()
-            .downcast_ref::<StringArray>()
-            .ok_or_else(|| {
-                datafusion_common::DataFusionError::Internal(
-                    "Expected a StringArray for pattern".to_string(),
-                )
-            })?;
+        let input_array: &StringArray = as_string_array(NAME, "str", &input_array_ref)?;
+        let pattern_array: &StringArray = as_string_array(NAME, "regexp", &pattern_array_ref)?;
 
         // --- Step 5: Extract Scalar Index ---
         // We get the single integer value for the group index.
         // idx -> 1
-        let idx: i64 = match idx_col {
-            ColumnarValue::Scalar(ScalarValue::Int64(Some(idx))) => *idx,
-            _ => {
-                return Err(datafusion_common::DataFusionError::Internal(
-                    "Expected a single Int64 for the index".to_string(),
-                ));
-            }
-        };
-
-        // --- Step 6: Validate Group Index ---
-        // Spark's regexp_extract requires a non-negative group index.
-        if idx < 0 {
-            return Err(datafusion_common::DataFusionError::Execution(
-                "Group index must be a non-negative integer.".to_string(),
-            ));
-        }
+        let idx: i64 = scalar_group_index(NAME, Some(idx_col), 0)?;
 
-        // --- Step 7: Prepare Output Builder ---
-        // An Arrow builder for efficiently creating the output `StringArray`.
+        // --- Step 6: Prepare Output Builder ---
+        // An Arrow builder for efficiently creating the output `StringArray`,
+        // plus the per-batch metrics and pattern lookup.
         let mut string_builder: StringBuilder = StringBuilder::new();
+        let mut batch_metrics = BatchMetrics::start(NAME, num_rows, &self.metrics);
+        let mut patterns = BatchPatterns::new(
+            NAME,
+            &self.cache,
+            PatternOptions {
+                collation,
+                dialect: self.settings.dialect,
+                size_limit: self.settings.size_limit,
+                ..Default::default()
+            },
+        );
 
-        // --- Step 8: Iterate and Process Each Row ---
+        // --- Step 7: Iterate and Process Each Row ---
         for i in 0..num_rows {
-            if input_array.is_null(i) {
+            if input_array.is_null(i) || pattern_array.is_null(i) {
                 string_builder.append_null();
                 continue;
             }
@@ -144,33 +258,58 @@ impl ScalarUDFImpl for RegexpExtract {
             let input_val: &str = input_array.value(i);
             let pattern: &str = pattern_array.value(i);
 
-            let compiled_regex: Regex = match Regex::new(pattern) {
-                Ok(re) => re,
-                Err(e) => {
-                    return Err(datafusion_common::DataFusionError::Execution(format!(
-                        "Error compiling regex: {e}"
-                    )));
-                }
+            // Under `ErrorMode::Null` a row that cannot be evaluated is NULL.
+            let error_mode = self.settings.error_mode;
+            let Some(compiled_regex): Option<Arc<Regex>> =
+                error_mode.recover(patterns.get(pattern, i, &mut batch_metrics))?
+            else {
+                string_builder.append_null();
+                continue;
             };
+            batch_metrics.rows_processed += 1;
+
+            // Spark's regexp_extract requires a non-negative group index.
+            // Like Spark, the check runs per row so it can report the pattern.
+            if !self.settings.legacy_group_index
+                && error_mode
+                    .recover(check_group_index(NAME, pattern, &compiled_regex, idx, i))?
+                    .is_none()
+            {
+                string_builder.append_null();
+                continue;
+            }
 
-            match compiled_regex.captures(input_val) {
-                Some(captures) if idx < captures.len() as i64 => {
+            // Under a normalizing collation the regex runs on the NFC form of
+            // the input; matched ranges are mapped back to `input_val`.
+            let text = collation.prepare_text(input_val);
+            let captures = compiled_regex.captures(text.as_str());
+            if captures.is_some() {
+                batch_metrics.rows_matched += 1;
+            }
+            match captures {
+                Some(captures) if (0..captures.len() as i64).contains(&idx) => {
                     // Example: pattern "(\d{4})-(\d{2})-(\d{2})" matches "2023-12-25"
                     // captures[0] -> "2023-12-25" (the full match)
                     // captures[1] -> "2023" (year - first group)
                     // captures[2] -> "12" (month - second group)
                     // captures[3] -> "25" (day - third group)
                     // Depending on idx: 1=year, 2=month, 3=day, or 0=full match
-                    string_builder.append_value(captures.get(idx as usize).unwrap().as_str());
+                    // A group that did not take part in the match (e.g. the
+                    // unused side of an alternation) yields "", as in Spark.
+                    let value = captures
+                        .get(idx as usize)
+                        .map_or("", |group| text.original(group.range()));
+                    string_builder.append_value(value);
                 }
                 _ => {
                     // Handle both: no regex match OR index out of bounds
+                    // (negative, too, under the legacy group index rules)
                     string_builder.append_value("");
                 }
             }
         }
 
-        // --- Step 9: Finalize and Return Result Array ---
+        // --- Step 8: Finalize and Return Result Array ---
         // The builder is finalized into a new Arrow Array.
         // For our example, this will be a StringArray containing ["2023"] (the year).
         Ok(ColumnarValue::Array(Arc::new(string_builder.finish())))
@@ -180,6 +319,7 @@ impl ScalarUDFImpl for RegexpExtract {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::error::RegexpError;
     use arrow_array::StringArray;
     use datafusion_common::ScalarValue;
     use datafusion_common::arrow::datatypes::Field;
@@ -358,4 +498,249 @@ mod tests {
             "Group index must be a non-negative integer.",
         );
     }
+
+    #[test]
+    fn test_error_carries_spark_class_and_row() {
+        let args = ScalarFunctionArgs {
+            args: vec![
+                ColumnarValue::Array(Arc::new(StringArray::from(vec!["a", "b"]))),
+                ColumnarValue::Array(Arc::new(StringArray::from(vec!["(a)", "[bad"]))),
+                ColumnarValue::Scalar(ScalarValue::Int64(Some(1))),
+            ],
+            number_rows: 2,
+            arg_fields: vec![],
+            return_field: Arc::new(Field::new("result", DataType::Utf8, true)),
+        };
+
+        let err = RegexpExtract::new().invoke_with_args(args).unwrap_err();
+        let regexp_error = RegexpError::find(&err).expect("Expected a RegexpError");
+        assert_eq!(
+            regexp_error.error_class(),
+            "INVALID_PARAMETER_VALUE.PATTERN"
+        );
+        assert_eq!(regexp_error.pattern(), Some("[bad"));
+        assert_eq!(regexp_error.row(), Some(1));
+    }
+
+    #[test]
+    fn test_failed_batch_is_still_recorded() {
+        let args = ScalarFunctionArgs {
+            args: vec![
+                ColumnarValue::Array(Arc::new(StringArray::from(vec!["a", "b"]))),
+                ColumnarValue::Array(Arc::new(StringArray::from(vec!["(a)", "[bad"]))),
+                ColumnarValue::Scalar(ScalarValue::Int64(Some(1))),
+            ],
+            number_rows: 2,
+            arg_fields: vec![],
+            return_field: Arc::new(Field::new("result", DataType::Utf8, true)),
+        };
+
+        let udf = RegexpExtract::new();
+        udf.invoke_with_args(args).unwrap_err();
+        let snapshot = udf.metrics().snapshot();
+        assert_eq!((snapshot.rows_processed, snapshot.rows_matched), (1, 1));
+        assert_eq!((snapshot.compilations, snapshot.cache_misses), (2, 2));
+    }
+
+    #[test]
+    fn test_unmatched_optional_group_returns_empty_string() {
+        run_test(
+            ColumnarValue::Array(Arc::new(StringArray::from(vec!["b"]))),
+            ColumnarValue::Scalar(ScalarValue::from(r"(a)|(b)")),
+            ColumnarValue::Scalar(ScalarValue::Int64(Some(1))),
+            vec![Some("")],
+            1,
+        );
+    }
+
+    fn run_collation_test(
+        input: Vec<&str>,
+        pattern: &str,
+        collation: &str,
+        expected_values: Vec<Option<&str>>,
+    ) {
+        let num_rows = input.len();
+        let args = ScalarFunctionArgs {
+            args: vec![
+                ColumnarValue::Array(Arc::new(StringArray::from(input))),
+                ColumnarValue::Scalar(ScalarValue::from(pattern)),
+                ColumnarValue::Scalar(ScalarValue::Int64(Some(1))),
+                ColumnarValue::Scalar(ScalarValue::from(collation)),
+            ],
+            number_rows: num_rows,
+            arg_fields: vec![],
+            return_field: Arc::new(Field::new("result", DataType::Utf8, true)),
+        };
+
+        let result = RegexpExtract::new().invoke_with_args(args).unwrap();
+        let array = result.to_array(num_rows).unwrap();
+        let string_array = array.as_any().downcast_ref::<StringArray>().unwrap();
+        assert_eq!(string_array, &StringArray::from(expected_values));
+    }
+
+    #[test]
+    fn test_utf8_lcase_collation_keeps_original_case() {
+        run_collation_test(
+            vec!["ERROR: Disk Full", "error: disk full"],
+            r"error: (disk \w+)",
+            "UTF8_LCASE",
+            vec![Some("Disk Full"), Some("disk full")],
+        );
+    }
+
+    #[test]
+    fn test_unicode_collation_matches_nfd_input() {
+        // The first row spells "é" precomposed, the second decomposed.
+        run_collation_test(
+            vec!["caf\u{e9} cr\u{e8}me", "cafe\u{301} cre\u{300}me"],
+            "(caf\u{e9}) ",
+            "UNICODE",
+            vec![Some("caf\u{e9}"), Some("cafe\u{301}")],
+        );
+    }
+
+    #[test]
+    fn test_unicode_ci_collation() {
+        run_collation_test(
+            vec!["CAFE\u{301}"],
+            "(caf\u{e9})",
+            "UNICODE_CI",
+            vec![Some("CAFE\u{301}")],
+        );
+    }
+
+    #[test]
+    fn test_invalid_collation_name() {
+        let args = ScalarFunctionArgs {
+            args: vec![
+                ColumnarValue::Scalar(ScalarValue::from("a")),
+                ColumnarValue::Scalar(ScalarValue::from("(a)")),
+                ColumnarValue::Scalar(ScalarValue::Int64(Some(1))),
+                ColumnarValue::Scalar(ScalarValue::from("NOT_A_COLLATION")),
+            ],
+            number_rows: 1,
+            arg_fields: vec![],
+            return_field: Arc::new(Field::new("result", DataType::Utf8, true)),
+        };
+
+        let err = RegexpExtract::new().invoke_with_args(args).unwrap_err();
+        let regexp_error = RegexpError::find(&err).expect("Expected a RegexpError");
+        assert_eq!(regexp_error.error_class(), "COLLATION_INVALID_NAME");
+    }
+
+    fn invoke(udf: RegexpExtract, input: &str, pattern: &str, idx: i64) -> Result<Option<String>> {
+        let args = ScalarFunctionArgs {
+            args: vec![
+                ColumnarValue::Scalar(ScalarValue::from(input)),
+                ColumnarValue::Scalar(ScalarValue::from(pattern)),
+                ColumnarValue::Scalar(ScalarValue::Int64(Some(idx))),
+            ],
+            number_rows: 1,
+            arg_fields: vec![],
+            return_field: Arc::new(Field::new("result", DataType::Utf8, true)),
+        };
+        let array = udf.invoke_with_args(args)?.to_array(1)?;
+        let strings = array.as_any().downcast_ref::<StringArray>().unwrap();
+        Ok(strings.iter().next().unwrap().map(str::to_string))
+    }
+
+    #[test]
+    fn test_builder_legacy_group_index_allows_negative_index() {
+        let udf = RegexpExtract::builder().legacy_group_index(true).build();
+        assert_eq!(
+            invoke(udf, "a-b", "(a)-(b)", -1).unwrap(),
+            Some(String::new())
+        );
+    }
+
+    #[test]
+    fn test_builder_null_error_mode() {
+        let udf = RegexpExtract::builder().error_mode(ErrorMode::Null).build();
+        assert_eq!(invoke(udf.clone(), "a", "[bad", 1).unwrap(), None);
+        assert_eq!(invoke(udf, "a", "(a)", -1).unwrap(), None);
+    }
+
+    #[test]
+    fn test_builder_dialect_and_size_limit() {
+        // `\h` is Java syntax; the Rust dialect takes it as is and rejects it.
+        let java = RegexpExtract::new();
+        assert_eq!(
+            invoke(java, "a b", r"a(\h)b", 1).unwrap(),
+            Some(" ".to_string())
+        );
+        let rust = RegexpExtract::builder().dialect(RegexDialect::Rust).build();
+        assert!(invoke(rust, "a b", r"a(\h)b", 1).is_err());
+
+        let limited = RegexpExtract::builder().size_limit(64).build();
+        let err = invoke(limited, "a", r"(\w{100})", 1).unwrap_err();
+        let regexp_error = RegexpError::find(&err).expect("Expected a RegexpError");
+        assert_eq!(
+            regexp_error.error_class(),
+            "INVALID_PARAMETER_VALUE.PATTERN"
+        );
+    }
+
+    #[test]
+    fn test_java_dialect_is_the_default() {
+        // ASCII-only shorthand classes, as in Spark.
+        let udf = RegexpExtract::new;
+        assert_eq!(
+            invoke(udf(), "٣4", r"(\d+)", 1).unwrap(),
+            Some("4".to_string())
+        );
+        // `.` stops at every Java line terminator, not only `\n`.
+        assert_eq!(
+            invoke(udf(), "ab\rc", r"(a.*)", 1).unwrap(),
+            Some("ab".to_string())
+        );
+        // `(?i)` folds ASCII letters only.
+        assert_eq!(
+            invoke(udf(), "É", r"(?i)(é)", 1).unwrap(),
+            Some(String::new())
+        );
+
+        let rust = || RegexpExtract::builder().dialect(RegexDialect::Rust).build();
+        assert_eq!(
+            invoke(rust(), "٣4", r"(\d+)", 1).unwrap(),
+            Some("٣4".to_string())
+        );
+        assert_eq!(
+            invoke(rust(), "É", r"(?i)(é)", 1).unwrap(),
+            Some("É".to_string())
+        );
+    }
+
+    #[test]
+    fn test_end_anchor_on_input_ending_in_a_newline() {
+        // Java's `c$` matches "c" in "abc\n"; without lookahead the match
+        // would include the newline, so the pattern is rejected instead.
+        let err = invoke(RegexpExtract::new(), "abc\n", "c$", 0).unwrap_err();
+        let err = RegexpError::find(&err).unwrap();
+        assert_eq!(err.error_class(), "INVALID_PARAMETER_VALUE.PATTERN");
+        assert!(err.to_string().contains(r"use \z"));
+
+        assert_eq!(
+            invoke(RegexpExtract::new(), "abc\n", r"c\z", 0).unwrap(),
+            Some(String::new())
+        );
+        assert_eq!(
+            invoke(RegexpExtract::new(), "abc\n", "(?m)c$", 0).unwrap(),
+            Some("c".to_string())
+        );
+    }
+
+    #[test]
+    fn test_with_config_overrides_only_set_keys() {
+        let udf = RegexpExtract::builder().error_mode(ErrorMode::Null).build();
+        assert!(udf.with_config(&RegexpConfig::default()).is_none());
+
+        let config = RegexpConfig {
+            legacy_group_index: Some(true),
+            ..Default::default()
+        };
+        let configured = udf.with_config(&config).unwrap();
+        assert!(configured.settings.legacy_group_index);
+        assert_eq!(configured.settings.error_mode, ErrorMode::Null);
+        assert!(Arc::ptr_eq(&configured.cache, &udf.cache));
+    }
 }
@@ -0,0 +1,281 @@
+use std::any::Any;
+use std::sync::Arc;
+
+use arrow_array::builder::{ListBuilder, StringBuilder};
+use arrow_array::{Array, ArrayRef, StringArray};
+use datafusion_common::Result;
+use datafusion_common::arrow::datatypes::{DataType, Field};
+use datafusion_expr::{
+    ColumnarValue, ScalarFunctionArgs, ScalarUDFImpl, Signature, TypeSignature, Volatility,
+};
+use regex::Regex;
+
+use crate::args::{as_string_array, extract_input_and_pattern, scalar_group_index};
+use crate::collation::Collation;
+use crate::config::{Configurable, RegexpConfig};
+use crate::dialect::RegexDialect;
+use crate::metrics::{BatchMetrics, RegexpMetrics};
+use crate::pattern::{
+    BatchPatterns, PatternCache, PatternOptions, check_group_index, java_captures_iter,
+};
+
+const NAME: &str = "regexp_extract_all";
+
+/// Spark's `regexp_extract_all(str, regexp[, idx])`: every match of group
+/// `idx` (default 1) as a `List<Utf8>`.
+///
+/// Pattern compilation, NULL handling and group index errors are shared with
+/// [`RegexpExtract`](crate::regexp_extract::RegexpExtract). A row without any
+/// match yields an empty list.
+#[derive(Debug, Clone)]
+pub struct RegexpExtractAll {
+    signature: Signature,
+    options: PatternOptions,
+    cache: Arc<PatternCache>,
+    metrics: Arc<RegexpMetrics>,
+}
+
+impl Default for RegexpExtractAll {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl RegexpExtractAll {
+    pub fn new() -> Self {
+        Self {
+            signature: Signature::one_of(
+                vec![
+                    TypeSignature::Exact(vec![DataType::Utf8, DataType::Utf8]),
+                    TypeSignature::Exact(vec![DataType::Utf8, DataType::Utf8, DataType::Int64]),
+                    // regexp_extract_all(str, regexp, idx, collation)
+                    TypeSignature::Exact(vec![
+                        DataType::Utf8,
+                        DataType::Utf8,
+                        DataType::Int64,
+                        DataType::Utf8,
+                    ]),
+                ],
+                Volatility::Immutable,
+            ),
+            options: PatternOptions::default(),
+            cache: Arc::new(PatternCache::default()),
+            metrics: Arc::new(RegexpMetrics::new()),
+        }
+    }
+
+    /// The collation used when the call does not pass one.
+    pub fn with_collation(mut self, collation: Collation) -> Self {
+        self.options.collation = collation;
+        self
+    }
+
+    /// The dialect patterns are written in; Java by default.
+    pub fn with_dialect(mut self, dialect: RegexDialect) -> Self {
+        self.options.dialect = dialect;
+        self
+    }
+
+    /// Execution metrics, shared by every clone of this instance.
+    pub fn metrics(&self) -> &RegexpMetrics {
+        &self.metrics
+    }
+}
+
+impl Configurable for RegexpExtractAll {
+    fn with_config(&self, config: &RegexpConfig) -> Option<Self> {
+        let options = self.options.with_config(config);
+        (options != self.options).then(|| Self {
+            options,
+            ..self.clone()
+        })
+    }
+}
+
+impl ScalarUDFImpl for RegexpExtractAll {
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn name(&self) -> &str {
+        NAME
+    }
+
+    fn signature(&self) -> &Signature {
+        &self.signature
+    }
+
+    fn return_type(&self, _arg_types: &[DataType]) -> Result<DataType> {
+        Ok(DataType::List(Arc::new(Field::new_list_field(
+            DataType::Utf8,
+            true,
+        ))))
+    }
+
+    fn invoke_with_args(&self, args: ScalarFunctionArgs) -> Result<ColumnarValue> {
+        let num_rows = args.number_rows;
+        let idx: i64 = scalar_group_index(NAME, args.args.get(2), 1)?;
+        let collation = Collation::from_argument(NAME, args.args.get(3), self.options.collation)?;
+
+        let (input_array_ref, pattern_array_ref): (ArrayRef, ArrayRef) =
+            extract_input_and_pattern(&args.args[0], &args.args[1], num_rows)?;
+        let input_array: &StringArray = as_string_array(NAME, "str", &input_array_ref)?;
+        let pattern_array: &StringArray = as_string_array(NAME, "regexp", &pattern_array_ref)?;
+
+        let mut list_builder = ListBuilder::new(StringBuilder::new());
+        let mut batch_metrics = BatchMetrics::start(NAME, num_rows, &self.metrics);
+        let mut patterns = BatchPatterns::new(
+            NAME,
+            &self.cache,
+            PatternOptions {
+                collation,
+                ..self.options
+            },
+        );
+
+        for i in 0..num_rows {
+            if input_array.is_null(i) || pattern_array.is_null(i) {
+                list_builder.append_null();
+                continue;
+            }
+
+            let input_val: &str = input_array.value(i);
+            let pattern: &str = pattern_array.value(i);
+
+            let compiled_regex: Arc<Regex> = patterns.get(pattern, i, &mut batch_metrics)?;
+            batch_metrics.rows_processed += 1;
+            check_group_index(NAME, pattern, &compiled_regex, idx, i)?;
+
+            // Each match contributes its group `idx`; like regexp_extract, a
+            // missing or non-participating group contributes "".
+            let text = collation.prepare_text(input_val);
+            let mut matched = false;
+            for captures in java_captures_iter(&compiled_regex, text.as_str()) {
+                matched = true;
+                let value = captures
+                    .get(idx as usize)
+                    .map_or("", |group| text.original(group.range()));
+                list_builder.values().append_value(value);
+            }
+            if matched {
+                batch_metrics.rows_matched += 1;
+            }
+            list_builder.append(true);
+        }
+
+        Ok(ColumnarValue::Array(Arc::new(list_builder.finish())))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use arrow_array::ListArray;
+    use datafusion_common::ScalarValue;
+
+    fn run_test(
+        input: ColumnarValue,
+        pattern: ColumnarValue,
+        index: Option<i64>,
+        num_rows: usize,
+    ) -> Vec<Option<Vec<String>>> {
+        run_test_with(RegexpExtractAll::new(), input, pattern, index, num_rows)
+    }
+
+    fn run_test_with(
+        udf: RegexpExtractAll,
+        input: ColumnarValue,
+        pattern: ColumnarValue,
+        index: Option<i64>,
+        num_rows: usize,
+    ) -> Vec<Option<Vec<String>>> {
+        let mut args = vec![input, pattern];
+        if let Some(index) = index {
+            args.push(ColumnarValue::Scalar(ScalarValue::Int64(Some(index))));
+        }
+        let args = ScalarFunctionArgs {
+            args,
+            number_rows: num_rows,
+            arg_fields: vec![],
+            return_field: Arc::new(Field::new(
+                "result",
+                RegexpExtractAll::new().return_type(&[]).unwrap(),
+                true,
+            )),
+        };
+
+        let result = udf.invoke_with_args(args).unwrap();
+        let array = result.to_array(num_rows).unwrap();
+        let list_array = array.as_any().downcast_ref::<ListArray>().unwrap();
+        (0..num_rows)
+            .map(|i| {
+                if list_array.is_null(i) {
+                    return None;
+                }
+                let values = list_array.value(i);
+                let strings = values.as_any().downcast_ref::<StringArray>().unwrap();
+                Some(strings.iter().map(|v| v.unwrap().to_string()).collect())
+            })
+            .collect()
+    }
+
+    fn strings(values: &[&str]) -> Option<Vec<String>> {
+        Some(values.iter().map(|v| v.to_string()).collect())
+    }
+
+    #[test]
+    fn test_extract_all_default_group() {
+        let result = run_test(
+            ColumnarValue::Array(Arc::new(StringArray::from(vec!["a=1;b=2;c=3", "none"]))),
+            ColumnarValue::Scalar(ScalarValue::from(r"(\w)=(\d)")),
+            None,
+            2,
+        );
+        assert_eq!(result, vec![strings(&["a", "b", "c"]), strings(&[])]);
+    }
+
+    #[test]
+    fn test_extract_all_explicit_group() {
+        let result = run_test(
+            ColumnarValue::Array(Arc::new(StringArray::from(vec!["a=1;b=2"]))),
+            ColumnarValue::Scalar(ScalarValue::from(r"(\w)=(\d)")),
+            Some(2),
+            1,
+        );
+        assert_eq!(result, vec![strings(&["1", "2"])]);
+    }
+
+    #[test]
+    fn test_extract_all_null_input_and_pattern() {
+        let result = run_test(
+            ColumnarValue::Array(Arc::new(StringArray::from(vec![None, Some("x1")]))),
+            ColumnarValue::Array(Arc::new(StringArray::from(vec![Some(r"(\d)"), None]))),
+            None,
+            2,
+        );
+        assert_eq!(result, vec![None, None]);
+    }
+
+    #[test]
+    fn test_extract_all_unicode() {
+        let result = run_test(
+            ColumnarValue::Array(Arc::new(StringArray::from(vec!["你好-世界-Gödel"]))),
+            ColumnarValue::Scalar(ScalarValue::from(r"([^-]+)")),
+            Some(0),
+            1,
+        );
+        assert_eq!(result, vec![strings(&["你好", "世界", "Gödel"])]);
+    }
+
+    #[test]
+    fn test_extract_all_java_shorthand_classes() {
+        let input = || ColumnarValue::Array(Arc::new(StringArray::from(vec!["a1 é٣ b2"])));
+        let pattern = || ColumnarValue::Scalar(ScalarValue::from(r"(\w\d)"));
+        let result = run_test(input(), pattern(), None, 1);
+        assert_eq!(result, vec![strings(&["a1", "b2"])]);
+
+        let rust = RegexpExtractAll::new().with_dialect(RegexDialect::Rust);
+        let result = run_test_with(rust, input(), pattern(), None, 1);
+        assert_eq!(result, vec![strings(&["a1", "é٣", "b2"])]);
+    }
+}
@@ -0,0 +1,273 @@
+use std::any::Any;
+use std::sync::Arc;
+
+use arrow_array::builder::StringBuilder;
+use arrow_array::{Array, ArrayRef, StringArray};
+use datafusion_common::arrow::datatypes::DataType;
+use datafusion_common::{Result, ScalarValue};
+use datafusion_expr::{
+    ColumnarValue, ScalarFunctionArgs, ScalarUDFImpl, Signature, TypeSignature, Volatility,
+};
+use regex::Regex;
+
+use crate::args::{as_string_array, extract_input_and_pattern, scalar_positive_int64};
+use crate::dialect::RegexDialect;
+use crate::error::RegexpError;
+use crate::metrics::{BatchMetrics, RegexpMetrics};
+use crate::pattern::{BatchPatterns, PatternCache, PatternOptions, search_start};
+
+const NAME: &str = "bigquery_regexp_extract";
+
+/// BigQuery's `REGEXP_EXTRACT(value, regexp[, position[, occurrence]])`,
+/// registered as `bigquery_regexp_extract` next to Spark's
+/// [`RegexpExtract`](crate::regexp_extract::RegexpExtract).
+///
+/// Patterns are RE2 syntax, compiled as-is. A pattern may have at most one
+/// capture group: with one, the group is returned, otherwise the whole match.
+/// The search starts at the 1-based character `position` and returns the
+/// `occurrence`-th non-overlapping match, both defaulting to 1. The result is
+/// NULL when there is no such match, when the group did not take part in it,
+/// or when `position` is past the end of `value`.
+#[derive(Debug, Clone)]
+pub struct BigQueryRegexpExtract {
+    signature: Signature,
+    cache: Arc<PatternCache>,
+    metrics: Arc<RegexpMetrics>,
+}
+
+impl Default for BigQueryRegexpExtract {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl BigQueryRegexpExtract {
+    pub fn new() -> Self {
+        Self {
+            signature: Signature::one_of(
+                vec![
+                    TypeSignature::Exact(vec![DataType::Utf8, DataType::Utf8]),
+                    TypeSignature::Exact(vec![DataType::Utf8, DataType::Utf8, DataType::Int64]),
+                    TypeSignature::Exact(vec![
+                        DataType::Utf8,
+                        DataType::Utf8,
+                        DataType::Int64,
+                        DataType::Int64,
+                    ]),
+                ],
+                Volatility::Immutable,
+            ),
+            cache: Arc::new(PatternCache::default()),
+            metrics: Arc::new(RegexpMetrics::new()),
+        }
+    }
+
+    /// Execution metrics, shared by every clone of this instance.
+    pub fn metrics(&self) -> &RegexpMetrics {
+        &self.metrics
+    }
+}
+
+impl ScalarUDFImpl for BigQueryRegexpExtract {
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn name(&self) -> &str {
+        NAME
+    }
+
+    fn signature(&self) -> &Signature {
+        &self.signature
+    }
+
+    fn return_type(&self, _arg_types: &[DataType]) -> Result<DataType> {
+        Ok(DataType::Utf8)
+    }
+
+    fn invoke_with_args(&self, args: ScalarFunctionArgs) -> Result<ColumnarValue> {
+        let num_rows = args.number_rows;
+
+        // BigQuery checks both before evaluating any row.
+        let position = scalar_positive_int64(NAME, "position", args.args.get(2))?;
+        let occurrence = scalar_positive_int64(NAME, "occurrence", args.args.get(3))?;
+        let (Some(position), Some(occurrence)) = (position, occurrence) else {
+            return Ok(ColumnarValue::Scalar(ScalarValue::Utf8(None)));
+        };
+
+        let (input_array_ref, pattern_array_ref): (ArrayRef, ArrayRef) =
+            extract_input_and_pattern(&args.args[0], &args.args[1], num_rows)?;
+        let input_array: &StringArray = as_string_array(NAME, "value", &input_array_ref)?;
+        let pattern_array: &StringArray = as_string_array(NAME, "regexp", &pattern_array_ref)?;
+
+        let mut string_builder = StringBuilder::new();
+        let mut batch_metrics = BatchMetrics::start(NAME, num_rows, &self.metrics);
+        let mut patterns = BatchPatterns::new(
+            NAME,
+            &self.cache,
+            PatternOptions {
+                dialect: RegexDialect::Rust,
+                ..Default::default()
+            },
+        );
+
+        for i in 0..num_rows {
+            if input_array.is_null(i) || pattern_array.is_null(i) {
+                string_builder.append_null();
+                continue;
+            }
+
+            let input_val: &str = input_array.value(i);
+            let pattern: &str = pattern_array.value(i);
+
+            let compiled_regex: Arc<Regex> = patterns.get(pattern, i, &mut batch_metrics)?;
+            batch_metrics.rows_processed += 1;
+            let group_count = compiled_regex.captures_len() - 1;
+            if group_count > 1 {
+                return Err(RegexpError::InvalidPattern {
+                    function: NAME,
+                    pattern: pattern.to_string(),
+                    row: i,
+                    message: format!(
+                        "Regular expressions passed into extraction functions must not \
+                         have more than 1 capturing group, but got {group_count}"
+                    ),
+                }
+                .into());
+            }
+
+            let Some(start) = search_start(input_val, position) else {
+                string_builder.append_null();
+                continue;
+            };
+
+            let captures = compiled_regex
+                .captures_iter(&input_val[start..])
+                .nth((occurrence - 1) as usize);
+            if captures.is_some() {
+                batch_metrics.rows_matched += 1;
+            }
+            string_builder.append_option(
+                captures
+                    .and_then(|captures| captures.get(group_count))
+                    .map(|group| group.as_str()),
+            );
+        }
+
+        Ok(ColumnarValue::Array(Arc::new(string_builder.finish())))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use datafusion_common::arrow::datatypes::Field;
+
+    fn run_test(
+        input: Vec<Option<&str>>,
+        pattern: &str,
+        position_and_occurrence: &[Option<i64>],
+    ) -> Result<Vec<Option<String>>> {
+        let num_rows = input.len();
+        let mut args = vec![
+            ColumnarValue::Array(Arc::new(StringArray::from(input))),
+            ColumnarValue::Scalar(ScalarValue::from(pattern)),
+        ];
+        args.extend(
+            position_and_occurrence
+                .iter()
+                .map(|value| ColumnarValue::Scalar(ScalarValue::Int64(*value))),
+        );
+        let args = ScalarFunctionArgs {
+            args,
+            number_rows: num_rows,
+            arg_fields: vec![],
+            return_field: Arc::new(Field::new("result", DataType::Utf8, true)),
+        };
+        let array = BigQueryRegexpExtract::new()
+            .invoke_with_args(args)?
+            .to_array(num_rows)?;
+        let strings = array.as_any().downcast_ref::<StringArray>().unwrap();
+        Ok(strings.iter().map(|v| v.map(str::to_string)).collect())
+    }
+
+    fn strings(values: &[Option<&str>]) -> Vec<Option<String>> {
+        values.iter().map(|v| v.map(str::to_string)).collect()
+    }
+
+    #[test]
+    fn test_documentation_examples() {
+        // From the BigQuery reference for REGEXP_EXTRACT.
+        let emails = vec![
+            Some("foo@example.com"),
+            Some("bar@example.org"),
+            Some("baz@example.net"),
+        ];
+        let result = run_test(emails.clone(), "^[a-zA-Z0-9_.+-]+", &[]).unwrap();
+        assert_eq!(result, strings(&[Some("foo"), Some("bar"), Some("baz")]));
+
+        let result = run_test(emails, r"@([a-zA-Z0-9-]+)\.", &[]).unwrap();
+        assert_eq!(
+            result,
+            strings(&[Some("example"), Some("example"), Some("example")])
+        );
+    }
+
+    #[test]
+    fn test_position_and_occurrence() {
+        let input = vec![Some("Hello Helloo and Hellooo")];
+        let cases = [
+            (&[Some(1), Some(1)][..], Some("Hello")),
+            (&[Some(1), Some(2)][..], Some("Helloo")),
+            (&[Some(1), Some(3)][..], Some("Hellooo")),
+            (&[Some(1), Some(4)][..], None),
+            (&[Some(2), Some(1)][..], Some("ello")),
+            (&[Some(7), Some(2)][..], Some("Hellooo")),
+            (&[Some(20), Some(1)][..], None),
+            (&[Some(30), Some(1)][..], None),
+        ];
+        for (args, expected) in cases {
+            let result = run_test(input.clone(), "H?ello+", args).unwrap();
+            assert_eq!(result, strings(&[expected]), "args {args:?}");
+        }
+    }
+
+    #[test]
+    fn test_null_on_no_match_and_unmatched_group() {
+        let result = run_test(vec![Some("abc"), Some("b"), None], "(a)|b", &[]).unwrap();
+        assert_eq!(result, strings(&[Some("a"), None, None]));
+    }
+
+    #[test]
+    fn test_null_position_or_occurrence_is_null() {
+        let result = run_test(vec![Some("abc")], "b", &[None]).unwrap();
+        assert_eq!(result, strings(&[None]));
+        let result = run_test(vec![Some("abc")], "b", &[Some(1), None]).unwrap();
+        assert_eq!(result, strings(&[None]));
+    }
+
+    #[test]
+    fn test_more_than_one_group_is_an_error() {
+        let err = run_test(vec![Some("a-b")], "(a)-(b)", &[]).unwrap_err();
+        let err = RegexpError::find(&err).unwrap();
+        assert_eq!(err.error_class(), "INVALID_PARAMETER_VALUE.PATTERN");
+        assert!(
+            err.to_string()
+                .contains("must not have more than 1 capturing group")
+        );
+    }
+
+    #[test]
+    fn test_non_positive_position_and_occurrence_are_errors() {
+        for (args, argument) in [
+            (&[Some(0)][..], "position"),
+            (&[Some(-1)][..], "position"),
+            (&[Some(1), Some(0)][..], "occurrence"),
+        ] {
+            let err = run_test(vec![Some("abc")], "b", args).unwrap_err();
+            let err = RegexpError::find(&err).unwrap();
+            assert_eq!(err.error_class(), "DATATYPE_MISMATCH.VALUE_OUT_OF_RANGE");
+            assert!(err.to_string().contains(&format!("`{argument}`")));
+        }
+    }
+}
@@ -0,0 +1,196 @@
+use std::any::Any;
+use std::sync::Arc;
+
+use arrow_array::builder::StringBuilder;
+use arrow_array::{Array, ArrayRef, StringArray};
+use datafusion_common::Result;
+use datafusion_common::arrow::datatypes::DataType;
+use datafusion_expr::{
+    ColumnarValue, ScalarFunctionArgs, ScalarUDFImpl, Signature, TypeSignature, Volatility,
+};
+use regex::Regex;
+
+use crate::args::{as_string_array, extract_input_and_pattern, scalar_group_index};
+use crate::error::RegexpError;
+use crate::metrics::{BatchMetrics, RegexpMetrics};
+use crate::pattern::{BatchPatterns, PatternCache, PatternOptions, check_group_index};
+
+const NAME: &str = "trino_regexp_extract";
+
+/// Trino's `regexp_extract(string, pattern[, group])`, registered as
+/// `trino_regexp_extract` so it can live next to Spark's
+/// [`RegexpExtract`](crate::regexp_extract::RegexpExtract).
+///
+/// Where Spark returns `""`, Trino returns NULL: when nothing matches and
+/// when `group` took no part in the match. `group` defaults to 0, the whole
+/// match. A negative `group`, or one past the last group of the pattern, is
+/// an error even for rows that do not match, as Trino validates it before
+/// searching.
+#[derive(Debug, Clone)]
+pub struct TrinoRegexpExtract {
+    signature: Signature,
+    cache: Arc<PatternCache>,
+    metrics: Arc<RegexpMetrics>,
+}
+
+impl Default for TrinoRegexpExtract {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl TrinoRegexpExtract {
+    pub fn new() -> Self {
+        Self {
+            signature: Signature::one_of(
+                vec![
+                    TypeSignature::Exact(vec![DataType::Utf8, DataType::Utf8]),
+                    TypeSignature::Exact(vec![DataType::Utf8, DataType::Utf8, DataType::Int64]),
+                ],
+                Volatility::Immutable,
+            ),
+            cache: Arc::new(PatternCache::default()),
+            metrics: Arc::new(RegexpMetrics::new()),
+        }
+    }
+
+    /// Execution metrics, shared by every clone of this instance.
+    pub fn metrics(&self) -> &RegexpMetrics {
+        &self.metrics
+    }
+}
+
+/// Rejects a group index past the last group of `regex`, the check Trino
+/// adds to Spark's.
+fn check_group_exists(
+    function: &'static str,
+    pattern: &str,
+    regex: &Regex,
+    idx: i64,
+    row: usize,
+) -> Result<(), RegexpError> {
+    let group_count = regex.captures_len() - 1;
+    if idx > group_count as i64 {
+        return Err(RegexpError::InvalidGroupIndex {
+            function,
+            pattern: pattern.to_string(),
+            index: idx,
+            group_count,
+            row,
+        });
+    }
+    Ok(())
+}
+
+impl ScalarUDFImpl for TrinoRegexpExtract {
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn name(&self) -> &str {
+        NAME
+    }
+
+    fn signature(&self) -> &Signature {
+        &self.signature
+    }
+
+    fn return_type(&self, _arg_types: &[DataType]) -> Result<DataType> {
+        Ok(DataType::Utf8)
+    }
+
+    fn invoke_with_args(&self, args: ScalarFunctionArgs) -> Result<ColumnarValue> {
+        let num_rows = args.number_rows;
+        let idx: i64 = scalar_group_index(NAME, args.args.get(2), 0)?;
+
+        let (input_array_ref, pattern_array_ref): (ArrayRef, ArrayRef) =
+            extract_input_and_pattern(&args.args[0], &args.args[1], num_rows)?;
+        let input_array: &StringArray = as_string_array(NAME, "string", &input_array_ref)?;
+        let pattern_array: &StringArray = as_string_array(NAME, "pattern", &pattern_array_ref)?;
+
+        let mut string_builder = StringBuilder::new();
+        let mut batch_metrics = BatchMetrics::start(NAME, num_rows, &self.metrics);
+        let mut patterns = BatchPatterns::new(NAME, &self.cache, PatternOptions::default());
+
+        for i in 0..num_rows {
+            if input_array.is_null(i) || pattern_array.is_null(i) {
+                string_builder.append_null();
+                continue;
+            }
+
+            let pattern: &str = pattern_array.value(i);
+            let compiled_regex: Arc<Regex> = patterns.get(pattern, i, &mut batch_metrics)?;
+            batch_metrics.rows_processed += 1;
+            check_group_index(NAME, pattern, &compiled_regex, idx, i)?;
+            check_group_exists(NAME, pattern, &compiled_regex, idx, i)?;
+
+            let captures = compiled_regex.captures(input_array.value(i));
+            if captures.is_some() {
+                batch_metrics.rows_matched += 1;
+            }
+            string_builder.append_option(
+                captures
+                    .and_then(|captures| captures.get(idx as usize))
+                    .map(|group| group.as_str()),
+            );
+        }
+
+        Ok(ColumnarValue::Array(Arc::new(string_builder.finish())))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use datafusion_common::ScalarValue;
+    use datafusion_common::arrow::datatypes::Field;
+
+    fn run_test(
+        input: Vec<Option<&str>>,
+        pattern: &str,
+        index: Option<i64>,
+    ) -> Result<Vec<Option<String>>> {
+        let num_rows = input.len();
+        let mut args = vec![
+            ColumnarValue::Array(Arc::new(StringArray::from(input))),
+            ColumnarValue::Scalar(ScalarValue::from(pattern)),
+        ];
+        if let Some(index) = index {
+            args.push(ColumnarValue::Scalar(ScalarValue::Int64(Some(index))));
+        }
+        let args = ScalarFunctionArgs {
+            args,
+            number_rows: num_rows,
+            arg_fields: vec![],
+            return_field: Arc::new(Field::new("result", DataType::Utf8, true)),
+        };
+        let array = TrinoRegexpExtract::new()
+            .invoke_with_args(args)?
+            .to_array(num_rows)?;
+        let strings = array.as_any().downcast_ref::<StringArray>().unwrap();
+        Ok(strings.iter().map(|v| v.map(str::to_string)).collect())
+    }
+
+    #[test]
+    fn test_defaults_to_whole_match_and_null_without_match() {
+        let result = run_test(vec![Some("1a 2b 14m"), Some("none"), None], r"\d+", None);
+        assert_eq!(result.unwrap(), vec![Some("1".to_string()), None, None]);
+    }
+
+    #[test]
+    fn test_group_that_did_not_participate_is_null() {
+        let result = run_test(vec![Some("b")], "(a)|(b)", Some(1));
+        assert_eq!(result.unwrap(), vec![None]);
+    }
+
+    #[test]
+    fn test_group_past_the_last_is_an_error_even_without_match() {
+        let err = run_test(vec![Some("no digits")], r"(\d+)", Some(2)).unwrap_err();
+        let err = RegexpError::find(&err).unwrap();
+        assert_eq!(
+            err.error_class(),
+            "INVALID_PARAMETER_VALUE.REGEX_GROUP_INDEX"
+        );
+        assert!(err.to_string().contains("between 0 and 1, but got 2"));
+    }
+}
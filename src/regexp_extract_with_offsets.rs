@@ -0,0 +1,246 @@
+use std::any::Any;
+use std::sync::Arc;
+
+use arrow_array::builder::{Int32Builder, NullBufferBuilder, StringBuilder};
+use arrow_array::{Array, ArrayRef, StringArray, StructArray};
+use datafusion_common::Result;
+use datafusion_common::arrow::datatypes::{DataType, Field, Fields};
+use datafusion_expr::{
+    ColumnarValue, ScalarFunctionArgs, ScalarUDFImpl, Signature, TypeSignature, Volatility,
+};
+use regex::Regex;
+
+use crate::args::{as_string_array, extract_input_and_pattern, scalar_group_index};
+use crate::collation::Collation;
+use crate::config::{Configurable, RegexpConfig};
+use crate::metrics::{BatchMetrics, RegexpMetrics};
+use crate::pattern::{
+    BatchPatterns, PatternCache, PatternOptions, char_offset_of_byte, check_group_index,
+};
+
+const NAME: &str = "regexp_extract_with_offsets";
+
+/// `regexp_extract_with_offsets(str, regexp[, idx])`: like
+/// [`RegexpExtract`](crate::regexp_extract::RegexpExtract), returning a
+/// `{value, start, end}` struct that also says where the value was found.
+///
+/// `start` is the 1-based character position of the value, as returned by
+/// [`RegexpInstr`](crate::regexp_instr::RegexpInstr), and `end` the position
+/// just past it, so `end - start` is its length in characters and
+/// `substring(str, end)` is the rest of the line. Without a match, or when
+/// group `idx` did not take part in it, the result is `{"", 0, 0}`.
+#[derive(Debug, Clone)]
+pub struct RegexpExtractWithOffsets {
+    signature: Signature,
+    options: PatternOptions,
+    cache: Arc<PatternCache>,
+    metrics: Arc<RegexpMetrics>,
+}
+
+impl Default for RegexpExtractWithOffsets {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl RegexpExtractWithOffsets {
+    pub fn new() -> Self {
+        Self {
+            signature: Signature::one_of(
+                vec![
+                    TypeSignature::Exact(vec![DataType::Utf8, DataType::Utf8]),
+                    TypeSignature::Exact(vec![DataType::Utf8, DataType::Utf8, DataType::Int64]),
+                ],
+                Volatility::Immutable,
+            ),
+            options: PatternOptions::default(),
+            cache: Arc::new(PatternCache::default()),
+            metrics: Arc::new(RegexpMetrics::new()),
+        }
+    }
+
+    /// The collation patterns are matched under.
+    pub fn with_collation(mut self, collation: Collation) -> Self {
+        self.options.collation = collation;
+        self
+    }
+
+    /// Execution metrics, shared by every clone of this instance.
+    pub fn metrics(&self) -> &RegexpMetrics {
+        &self.metrics
+    }
+
+    fn fields() -> Fields {
+        Fields::from(vec![
+            Field::new("value", DataType::Utf8, false),
+            Field::new("start", DataType::Int32, false),
+            Field::new("end", DataType::Int32, false),
+        ])
+    }
+}
+
+impl Configurable for RegexpExtractWithOffsets {
+    fn with_config(&self, config: &RegexpConfig) -> Option<Self> {
+        let options = self.options.with_config(config);
+        (options != self.options).then(|| Self {
+            options,
+            ..self.clone()
+        })
+    }
+}
+
+impl ScalarUDFImpl for RegexpExtractWithOffsets {
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn name(&self) -> &str {
+        NAME
+    }
+
+    fn signature(&self) -> &Signature {
+        &self.signature
+    }
+
+    fn return_type(&self, _arg_types: &[DataType]) -> Result<DataType> {
+        Ok(DataType::Struct(Self::fields()))
+    }
+
+    fn invoke_with_args(&self, args: ScalarFunctionArgs) -> Result<ColumnarValue> {
+        let num_rows = args.number_rows;
+        let idx: i64 = scalar_group_index(NAME, args.args.get(2), 0)?;
+
+        let (input_array_ref, pattern_array_ref): (ArrayRef, ArrayRef) =
+            extract_input_and_pattern(&args.args[0], &args.args[1], num_rows)?;
+        let input_array: &StringArray = as_string_array(NAME, "str", &input_array_ref)?;
+        let pattern_array: &StringArray = as_string_array(NAME, "regexp", &pattern_array_ref)?;
+
+        let mut values = StringBuilder::new();
+        let mut starts = Int32Builder::with_capacity(num_rows);
+        let mut ends = Int32Builder::with_capacity(num_rows);
+        let mut nulls = NullBufferBuilder::new(num_rows);
+        let mut batch_metrics = BatchMetrics::start(NAME, num_rows, &self.metrics);
+        let mut patterns = BatchPatterns::new(NAME, &self.cache, self.options);
+
+        for i in 0..num_rows {
+            if input_array.is_null(i) || pattern_array.is_null(i) {
+                // The children of a null struct slot still need a value.
+                values.append_value("");
+                starts.append_value(0);
+                ends.append_value(0);
+                nulls.append_null();
+                continue;
+            }
+
+            let input_val: &str = input_array.value(i);
+            let pattern: &str = pattern_array.value(i);
+
+            let compiled_regex: Arc<Regex> = patterns.get(pattern, i, &mut batch_metrics)?;
+            batch_metrics.rows_processed += 1;
+            check_group_index(NAME, pattern, &compiled_regex, idx, i)?;
+
+            let text = self.options.collation.prepare_text(input_val);
+            let captures = compiled_regex.captures(text.as_str());
+            if captures.is_some() {
+                batch_metrics.rows_matched += 1;
+            }
+            match captures.and_then(|captures| captures.get(idx as usize)) {
+                Some(group) => {
+                    let range = text.original_range(group.range());
+                    let start = char_offset_of_byte(input_val, range.start);
+                    let len = input_val[range.clone()].chars().count();
+                    values.append_value(&input_val[range]);
+                    starts.append_value(start as i32 + 1);
+                    ends.append_value((start + len) as i32 + 1);
+                }
+                None => {
+                    values.append_value("");
+                    starts.append_value(0);
+                    ends.append_value(0);
+                }
+            }
+            nulls.append_non_null();
+        }
+
+        let array = StructArray::try_new(
+            Self::fields(),
+            vec![
+                Arc::new(values.finish()),
+                Arc::new(starts.finish()),
+                Arc::new(ends.finish()),
+            ],
+            nulls.finish(),
+        )?;
+        Ok(ColumnarValue::Array(Arc::new(array)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use arrow_array::Int32Array;
+    use arrow_array::cast::AsArray;
+    use datafusion_common::ScalarValue;
+
+    fn run_test(
+        input: Vec<Option<&str>>,
+        pattern: &str,
+        index: Option<i64>,
+    ) -> Vec<Option<(String, i32, i32)>> {
+        let num_rows = input.len();
+        let mut args = vec![
+            ColumnarValue::Array(Arc::new(StringArray::from(input))),
+            ColumnarValue::Scalar(ScalarValue::from(pattern)),
+        ];
+        if let Some(index) = index {
+            args.push(ColumnarValue::Scalar(ScalarValue::Int64(Some(index))));
+        }
+        let udf = RegexpExtractWithOffsets::new();
+        let args = ScalarFunctionArgs {
+            args,
+            number_rows: num_rows,
+            arg_fields: vec![],
+            return_field: Arc::new(Field::new("result", udf.return_type(&[]).unwrap(), true)),
+        };
+        let result = udf.invoke_with_args(args).unwrap();
+        let array = result.to_array(num_rows).unwrap();
+        let structs = array.as_struct();
+        let values = structs.column(0).as_string::<i32>();
+        let starts = structs
+            .column(1)
+            .as_any()
+            .downcast_ref::<Int32Array>()
+            .unwrap();
+        let ends = structs
+            .column(2)
+            .as_any()
+            .downcast_ref::<Int32Array>()
+            .unwrap();
+        (0..num_rows)
+            .map(|i| {
+                structs
+                    .is_valid(i)
+                    .then(|| (values.value(i).to_string(), starts.value(i), ends.value(i)))
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_offsets_of_whole_match() {
+        let result = run_test(vec![Some("id=42;"), Some("none"), None], r"\d+", None);
+        assert_eq!(
+            result,
+            vec![
+                Some(("42".to_string(), 4, 6)),
+                Some((String::new(), 0, 0)),
+                None
+            ]
+        );
+    }
+
+    #[test]
+    fn test_offsets_are_in_characters() {
+        let result = run_test(vec![Some("你好-世界")], r"([^-]+)-([^-]+)", Some(2));
+        assert_eq!(result, vec![Some(("世界".to_string(), 4, 6))]);
+    }
+}
@@ -0,0 +1,198 @@
+use std::any::Any;
+use std::sync::Arc;
+
+use arrow_array::builder::Int32Builder;
+use arrow_array::{Array, ArrayRef, StringArray};
+use datafusion_common::Result;
+use datafusion_common::arrow::datatypes::DataType;
+use datafusion_expr::{
+    ColumnarValue, ScalarFunctionArgs, ScalarUDFImpl, Signature, TypeSignature, Volatility,
+};
+use regex::Regex;
+
+use crate::args::{as_string_array, extract_input_and_pattern, scalar_group_index};
+use crate::collation::Collation;
+use crate::config::{Configurable, RegexpConfig};
+use crate::metrics::{BatchMetrics, RegexpMetrics};
+use crate::pattern::{
+    BatchPatterns, PatternCache, PatternOptions, char_offset_of_byte, check_group_index,
+};
+
+const NAME: &str = "regexp_instr";
+
+/// Spark's `regexp_instr(str, regexp[, idx])`: the 1-based character
+/// position where the first match of `regexp` (or its group `idx`, default 0)
+/// starts, or 0 when there is no match.
+///
+/// Positions count characters, not bytes, so `regexp_instr('你好-世界', '世')`
+/// is 4. A group that did not take part in the match, or an `idx` past the
+/// last group, also yields 0.
+#[derive(Debug, Clone)]
+pub struct RegexpInstr {
+    signature: Signature,
+    options: PatternOptions,
+    cache: Arc<PatternCache>,
+    metrics: Arc<RegexpMetrics>,
+}
+
+impl Default for RegexpInstr {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl RegexpInstr {
+    pub fn new() -> Self {
+        Self {
+            signature: Signature::one_of(
+                vec![
+                    TypeSignature::Exact(vec![DataType::Utf8, DataType::Utf8]),
+                    TypeSignature::Exact(vec![DataType::Utf8, DataType::Utf8, DataType::Int64]),
+                ],
+                Volatility::Immutable,
+            ),
+            options: PatternOptions::default(),
+            cache: Arc::new(PatternCache::default()),
+            metrics: Arc::new(RegexpMetrics::new()),
+        }
+    }
+
+    /// The collation patterns are matched under.
+    pub fn with_collation(mut self, collation: Collation) -> Self {
+        self.options.collation = collation;
+        self
+    }
+
+    /// Execution metrics, shared by every clone of this instance.
+    pub fn metrics(&self) -> &RegexpMetrics {
+        &self.metrics
+    }
+}
+
+impl Configurable for RegexpInstr {
+    fn with_config(&self, config: &RegexpConfig) -> Option<Self> {
+        let options = self.options.with_config(config);
+        (options != self.options).then(|| Self {
+            options,
+            ..self.clone()
+        })
+    }
+}
+
+impl ScalarUDFImpl for RegexpInstr {
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn name(&self) -> &str {
+        NAME
+    }
+
+    fn signature(&self) -> &Signature {
+        &self.signature
+    }
+
+    fn return_type(&self, _arg_types: &[DataType]) -> Result<DataType> {
+        Ok(DataType::Int32)
+    }
+
+    fn invoke_with_args(&self, args: ScalarFunctionArgs) -> Result<ColumnarValue> {
+        let num_rows = args.number_rows;
+        let idx: i64 = scalar_group_index(NAME, args.args.get(2), 0)?;
+
+        let (input_array_ref, pattern_array_ref): (ArrayRef, ArrayRef) =
+            extract_input_and_pattern(&args.args[0], &args.args[1], num_rows)?;
+        let input_array: &StringArray = as_string_array(NAME, "str", &input_array_ref)?;
+        let pattern_array: &StringArray = as_string_array(NAME, "regexp", &pattern_array_ref)?;
+
+        let mut builder = Int32Builder::with_capacity(num_rows);
+        let mut batch_metrics = BatchMetrics::start(NAME, num_rows, &self.metrics);
+        let mut patterns = BatchPatterns::new(NAME, &self.cache, self.options);
+
+        for i in 0..num_rows {
+            if input_array.is_null(i) || pattern_array.is_null(i) {
+                builder.append_null();
+                continue;
+            }
+
+            let input_val: &str = input_array.value(i);
+            let pattern: &str = pattern_array.value(i);
+
+            let compiled_regex: Arc<Regex> = patterns.get(pattern, i, &mut batch_metrics)?;
+            batch_metrics.rows_processed += 1;
+            check_group_index(NAME, pattern, &compiled_regex, idx, i)?;
+
+            let text = self.options.collation.prepare_text(input_val);
+            let captures = compiled_regex.captures(text.as_str());
+            if captures.is_some() {
+                batch_metrics.rows_matched += 1;
+            }
+            let position = captures
+                .and_then(|captures| captures.get(idx as usize))
+                .map_or(0, |group| {
+                    let start = text.original_range(group.range()).start;
+                    char_offset_of_byte(input_val, start) as i32 + 1
+                });
+            builder.append_value(position);
+        }
+
+        Ok(ColumnarValue::Array(Arc::new(builder.finish())))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use arrow_array::Int32Array;
+    use datafusion_common::ScalarValue;
+    use datafusion_common::arrow::datatypes::Field;
+
+    fn run_test(input: Vec<Option<&str>>, pattern: &str, index: Option<i64>) -> Vec<Option<i32>> {
+        let num_rows = input.len();
+        let mut args = vec![
+            ColumnarValue::Array(Arc::new(StringArray::from(input))),
+            ColumnarValue::Scalar(ScalarValue::from(pattern)),
+        ];
+        if let Some(index) = index {
+            args.push(ColumnarValue::Scalar(ScalarValue::Int64(Some(index))));
+        }
+        let args = ScalarFunctionArgs {
+            args,
+            number_rows: num_rows,
+            arg_fields: vec![],
+            return_field: Arc::new(Field::new("result", DataType::Int32, true)),
+        };
+        let result = RegexpInstr::new().invoke_with_args(args).unwrap();
+        let array = result.to_array(num_rows).unwrap();
+        let positions = array.as_any().downcast_ref::<Int32Array>().unwrap();
+        positions.iter().collect()
+    }
+
+    #[test]
+    fn test_instr_match_and_no_match() {
+        let result = run_test(
+            vec![Some("user=alice id=7"), Some("nothing"), None],
+            r"id=(\d+)",
+            None,
+        );
+        assert_eq!(result, vec![Some(12), Some(0), None]);
+    }
+
+    #[test]
+    fn test_instr_group_position() {
+        let result = run_test(vec![Some("user=alice id=7")], r"id=(\d+)", Some(1));
+        assert_eq!(result, vec![Some(15)]);
+    }
+
+    #[test]
+    fn test_instr_counts_characters_not_bytes() {
+        let result = run_test(vec![Some("你好-世界")], r"-(世界)", Some(1));
+        assert_eq!(result, vec![Some(4)]);
+    }
+
+    #[test]
+    fn test_instr_unmatched_group_is_zero() {
+        let result = run_test(vec![Some("b")], r"(a)|b", Some(1));
+        assert_eq!(result, vec![Some(0)]);
+    }
+}
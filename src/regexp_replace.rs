@@ -0,0 +1,473 @@
+use std::any::Any;
+use std::sync::Arc;
+
+use arrow_array::builder::StringBuilder;
+use arrow_array::{Array, ArrayRef, StringArray};
+use datafusion_common::arrow::datatypes::DataType;
+use datafusion_common::{Result, ScalarValue};
+use datafusion_expr::{
+    ColumnarValue, ScalarFunctionArgs, ScalarUDFImpl, Signature, TypeSignature, Volatility,
+};
+use regex::{Captures, Regex};
+
+use crate::args::{as_string_array, extract_input_and_pattern, scalar_int64, to_array};
+use crate::config::{Configurable, RegexpConfig};
+use crate::dialect::RegexDialect;
+use crate::error::RegexpError;
+use crate::metrics::{BatchMetrics, RegexpMetrics};
+use crate::pattern::{
+    BatchPatterns, PatternCache, PatternOptions, java_captures_iter, search_start,
+};
+
+const NAME: &str = "regexp_replace";
+
+/// One piece of a parsed Java replacement string.
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Piece {
+    Literal(String),
+    Group(usize),
+}
+
+/// A Java `Matcher.appendReplacement` replacement string, resolved against
+/// the groups of one pattern.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct JavaReplacement {
+    pieces: Vec<Piece>,
+}
+
+impl JavaReplacement {
+    /// Parses `replacement` exactly as `java.util.regex.Matcher` does:
+    /// `$n` takes the longest group number that exists, `${name}` refers to a
+    /// named group and `\` escapes the next character. Errors carry Java's
+    /// messages.
+    pub fn parse(replacement: &str, regex: &Regex) -> Result<Self, String> {
+        let group_count = regex.captures_len() - 1;
+        let mut pieces = Vec::new();
+        let mut literal = String::new();
+        let mut chars = replacement.chars().peekable();
+
+        while let Some(c) = chars.next() {
+            match c {
+                '\\' => match chars.next() {
+                    Some(escaped) => literal.push(escaped),
+                    None => return Err("character to be escaped is missing".to_string()),
+                },
+                '$' => {
+                    let group = match chars.next() {
+                        None => {
+                            return Err(
+                                "Illegal group reference: group index is missing".to_string()
+                            );
+                        }
+                        Some('{') => {
+                            let mut name = String::new();
+                            while let Some(&c) = chars.peek() {
+                                if !c.is_ascii_alphanumeric() {
+                                    break;
+                                }
+                                name.push(c);
+                                chars.next();
+                            }
+                            if name.is_empty() {
+                                return Err("named capturing group has 0 length name".to_string());
+                            }
+                            if chars.next() != Some('}') {
+                                return Err(
+                                    "named capturing group is missing trailing '}'".to_string()
+                                );
+                            }
+                            if name.starts_with(|c: char| c.is_ascii_digit()) {
+                                return Err(format!(
+                                    "capturing group name {{{name}}} starts with digit character"
+                                ));
+                            }
+                            regex
+                                .capture_names()
+                                .position(|n| n == Some(name.as_str()))
+                                .ok_or_else(|| format!("No group with name {{{name}}}"))?
+                        }
+                        Some(first) => {
+                            let mut group = first
+                                .to_digit(10)
+                                .ok_or_else(|| "Illegal group reference".to_string())?
+                                as usize;
+                            // The first digit is always part of the reference;
+                            // later digits only while the group exists.
+                            while let Some(digit) = chars.peek().and_then(|c| c.to_digit(10)) {
+                                let extended = group * 10 + digit as usize;
+                                if extended > group_count {
+                                    break;
+                                }
+                                group = extended;
+                                chars.next();
+                            }
+                            if group > group_count {
+                                return Err(format!("No group {group}"));
+                            }
+                            group
+                        }
+                    };
+                    if !literal.is_empty() {
+                        pieces.push(Piece::Literal(std::mem::take(&mut literal)));
+                    }
+                    pieces.push(Piece::Group(group));
+                }
+                _ => literal.push(c),
+            }
+        }
+        if !literal.is_empty() {
+            pieces.push(Piece::Literal(literal));
+        }
+        Ok(Self { pieces })
+    }
+
+    /// Appends the replacement for one match to `out`.
+    pub fn expand(&self, captures: &Captures<'_>, out: &mut String) {
+        for piece in &self.pieces {
+            match piece {
+                Piece::Literal(literal) => out.push_str(literal),
+                Piece::Group(group) => {
+                    if let Some(m) = captures.get(*group) {
+                        out.push_str(m.as_str());
+                    }
+                }
+            }
+        }
+    }
+}
+
+/// Spark's `regexp_replace(str, regexp, rep[, position])`.
+///
+/// `rep` uses Java replacement syntax and matching starts at the 1-based
+/// character `position`; text before it is copied unchanged. Like Spark, an
+/// invalid replacement only fails rows where the pattern matches.
+#[derive(Debug, Clone)]
+pub struct RegexpReplace {
+    signature: Signature,
+    options: PatternOptions,
+    cache: Arc<PatternCache>,
+    metrics: Arc<RegexpMetrics>,
+}
+
+impl Default for RegexpReplace {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl RegexpReplace {
+    pub fn new() -> Self {
+        Self {
+            signature: Signature::one_of(
+                vec![
+                    TypeSignature::Exact(vec![DataType::Utf8, DataType::Utf8, DataType::Utf8]),
+                    TypeSignature::Exact(vec![
+                        DataType::Utf8,
+                        DataType::Utf8,
+                        DataType::Utf8,
+                        DataType::Int64,
+                    ]),
+                ],
+                Volatility::Immutable,
+            ),
+            options: PatternOptions::default(),
+            cache: Arc::new(PatternCache::default()),
+            metrics: Arc::new(RegexpMetrics::new()),
+        }
+    }
+
+    /// The dialect patterns are written in; Java by default.
+    pub fn with_dialect(mut self, dialect: RegexDialect) -> Self {
+        self.options.dialect = dialect;
+        self
+    }
+
+    /// Execution metrics, shared by every clone of this instance.
+    pub fn metrics(&self) -> &RegexpMetrics {
+        &self.metrics
+    }
+}
+
+impl Configurable for RegexpReplace {
+    fn with_config(&self, config: &RegexpConfig) -> Option<Self> {
+        let options = PatternOptions {
+            // regexp_replace takes no collation.
+            collation: self.options.collation,
+            ..self.options.with_config(config)
+        };
+        (options != self.options).then(|| Self {
+            options,
+            ..self.clone()
+        })
+    }
+}
+
+impl ScalarUDFImpl for RegexpReplace {
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn name(&self) -> &str {
+        NAME
+    }
+
+    fn signature(&self) -> &Signature {
+        &self.signature
+    }
+
+    fn return_type(&self, _arg_types: &[DataType]) -> Result<DataType> {
+        Ok(DataType::Utf8)
+    }
+
+    fn invoke_with_args(&self, args: ScalarFunctionArgs) -> Result<ColumnarValue> {
+        let num_rows = args.number_rows;
+
+        // Spark rejects a non-positive position before evaluating any row.
+        let Some(position) = scalar_int64(NAME, "position", args.args.get(3), 1)? else {
+            return Ok(ColumnarValue::Scalar(ScalarValue::Utf8(None)));
+        };
+        if position <= 0 {
+            return Err(RegexpError::ValueOutOfRange {
+                function: NAME,
+                argument: "position",
+                value: position,
+                range: "(0, 2147483647]",
+            }
+            .into());
+        }
+
+        let (input_array_ref, pattern_array_ref): (ArrayRef, ArrayRef) =
+            extract_input_and_pattern(&args.args[0], &args.args[1], num_rows)?;
+        let replacement_array_ref: ArrayRef = to_array(&args.args[2], num_rows)?;
+        let input_array: &StringArray = as_string_array(NAME, "str", &input_array_ref)?;
+        let pattern_array: &StringArray = as_string_array(NAME, "regexp", &pattern_array_ref)?;
+        let replacement_array: &StringArray = as_string_array(NAME, "rep", &replacement_array_ref)?;
+
+        let mut string_builder: StringBuilder = StringBuilder::new();
+        let mut batch_metrics = BatchMetrics::start(NAME, num_rows, &self.metrics);
+        let mut patterns = BatchPatterns::new(NAME, &self.cache, self.options);
+        // The parsed replacement depends on the pattern's groups, so it is
+        // reused only while both the replacement and the regex are unchanged.
+        let mut last_replacement: Option<(&str, Arc<Regex>, Result<JavaReplacement, String>)> =
+            None;
+        let mut output = String::new();
+
+        for i in 0..num_rows {
+            if input_array.is_null(i) || pattern_array.is_null(i) || replacement_array.is_null(i) {
+                string_builder.append_null();
+                continue;
+            }
+
+            let input_val: &str = input_array.value(i);
+            let pattern: &str = pattern_array.value(i);
+            let replacement: &str = replacement_array.value(i);
+
+            let compiled_regex: Arc<Regex> = patterns.get(pattern, i, &mut batch_metrics)?;
+            batch_metrics.rows_processed += 1;
+
+            // Past the end of the string: nothing to replace.
+            let Some(start) = search_start(input_val, position) else {
+                string_builder.append_value(input_val);
+                continue;
+            };
+
+            let reuse = matches!(
+                &last_replacement,
+                Some((last, regex, _)) if *last == replacement && Arc::ptr_eq(regex, &compiled_regex)
+            );
+            if !reuse {
+                let parsed = JavaReplacement::parse(replacement, &compiled_regex);
+                last_replacement = Some((replacement, Arc::clone(&compiled_regex), parsed));
+            }
+            let parsed = &last_replacement.as_ref().unwrap().2;
+
+            // Matching runs on the suffix alone, as Java's Matcher.region with
+            // anchoring bounds does: `^` matches at `position`.
+            let region = &input_val[start..];
+            output.clear();
+            output.push_str(&input_val[..start]);
+            let mut copied = 0;
+            let mut matched = false;
+            for captures in java_captures_iter(&compiled_regex, region) {
+                matched = true;
+                let template =
+                    parsed
+                        .as_ref()
+                        .map_err(|message| RegexpError::InvalidReplacement {
+                            function: NAME,
+                            replacement: replacement.to_string(),
+                            row: i,
+                            message: message.clone(),
+                        })?;
+                let m = captures.get(0).unwrap();
+                output.push_str(&region[copied..m.start()]);
+                template.expand(&captures, &mut output);
+                copied = m.end();
+            }
+            if matched {
+                batch_metrics.rows_matched += 1;
+            }
+            output.push_str(&region[copied..]);
+            string_builder.append_value(&output);
+        }
+
+        Ok(ColumnarValue::Array(Arc::new(string_builder.finish())))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use datafusion_common::arrow::datatypes::Field;
+
+    fn invoke(args: Vec<ColumnarValue>, num_rows: usize) -> Result<ColumnarValue> {
+        let args = ScalarFunctionArgs {
+            args,
+            number_rows: num_rows,
+            arg_fields: vec![],
+            return_field: Arc::new(Field::new("result", DataType::Utf8, true)),
+        };
+        RegexpReplace::new().invoke_with_args(args)
+    }
+
+    fn run_test(
+        input: Vec<Option<&str>>,
+        pattern: &str,
+        replacement: &str,
+        position: Option<i64>,
+        expected_values: Vec<Option<&str>>,
+    ) {
+        let num_rows = input.len();
+        let mut args = vec![
+            ColumnarValue::Array(Arc::new(StringArray::from(input))),
+            ColumnarValue::Scalar(ScalarValue::from(pattern)),
+            ColumnarValue::Scalar(ScalarValue::from(replacement)),
+        ];
+        if let Some(position) = position {
+            args.push(ColumnarValue::Scalar(ScalarValue::Int64(Some(position))));
+        }
+        let result = invoke(args, num_rows).unwrap();
+        let array = result.to_array(num_rows).unwrap();
+        let string_array = array.as_any().downcast_ref::<StringArray>().unwrap();
+        assert_eq!(string_array, &StringArray::from(expected_values));
+    }
+
+    fn run_test_error(input: &str, pattern: &str, replacement: &str, expected_error_msg: &str) {
+        let args = vec![
+            ColumnarValue::Array(Arc::new(StringArray::from(vec![input]))),
+            ColumnarValue::Scalar(ScalarValue::from(pattern)),
+            ColumnarValue::Scalar(ScalarValue::from(replacement)),
+        ];
+        match invoke(args, 1) {
+            Ok(_) => panic!("Expected an error but got Ok"),
+            Err(e) => assert!(
+                e.to_string().contains(expected_error_msg),
+                "Error message '{e}' did not contain expected substring '{expected_error_msg}'"
+            ),
+        }
+    }
+
+    #[test]
+    fn test_numbered_and_named_groups() {
+        run_test(
+            vec![Some("100-200"), Some("no digits")],
+            r"(\d+)-(?<second>\d+)",
+            "${second}:$1",
+            None,
+            vec![Some("200:100"), Some("no digits")],
+        );
+    }
+
+    #[test]
+    fn test_escaped_dollar_and_backslash() {
+        run_test(
+            vec![Some("price 10")],
+            r"(\d+)",
+            r"\$$1\\",
+            None,
+            vec![Some(r"price $10\")],
+        );
+    }
+
+    #[test]
+    fn test_longest_existing_group_number() {
+        // With one group, "$10" is group 1 followed by a literal "0".
+        run_test(vec![Some("ab")], r"(a)", "$10", None, vec![Some("a0b")]);
+    }
+
+    #[test]
+    fn test_position_is_one_based_characters() {
+        run_test(
+            vec![Some("aaa"), Some("你好你好"), Some("abc")],
+            r"^.",
+            "X",
+            Some(2),
+            vec![Some("aXa"), Some("你X你好"), Some("aXc")],
+        );
+    }
+
+    #[test]
+    fn test_position_past_end_returns_input() {
+        run_test(vec![Some("abc")], r"c", "X", Some(4), vec![Some("abc")]);
+    }
+
+    #[test]
+    fn test_empty_input_is_searched_at_position_one() {
+        run_test(vec![Some("")], r"^\z", "X", None, vec![Some("X")]);
+        run_test(vec![Some("")], r"^\z", "X", Some(2), vec![Some("")]);
+    }
+
+    #[test]
+    fn test_end_anchor_on_input_ending_in_a_newline() {
+        // Java keeps the newline: "abX\n". Rather than replace it too, `$`
+        // is rejected where the match is used.
+        run_test_error("abc\n", "c$", "X", r"use \z");
+        run_test(vec![Some("abc\n")], r"c\z", "X", None, vec![Some("abc\n")]);
+        run_test(
+            vec![Some("abc\n")],
+            "(?m)c$",
+            "X",
+            None,
+            vec![Some("abX\n")],
+        );
+    }
+
+    #[test]
+    fn test_empty_matches_follow_java() {
+        run_test(vec![Some("aab")], r"a*", "-", None, vec![Some("--b-")]);
+    }
+
+    #[test]
+    fn test_null_propagation() {
+        run_test(vec![None], r"a", "b", None, vec![None]);
+    }
+
+    #[test]
+    fn test_illegal_group_references() {
+        run_test_error("a", "(a)", "$2", "No group 2");
+        run_test_error("a", "(a)", "$x", "Illegal group reference");
+        run_test_error("a", "(a)", "${missing}", "No group with name {missing}");
+        run_test_error("a", "(a)", "tail\\", "character to be escaped is missing");
+    }
+
+    #[test]
+    fn test_illegal_replacement_without_match_is_not_an_error() {
+        run_test(vec![Some("b")], r"a", "$9", None, vec![Some("b")]);
+    }
+
+    #[test]
+    fn test_non_positive_position() {
+        let args = vec![
+            ColumnarValue::Scalar(ScalarValue::from("a")),
+            ColumnarValue::Scalar(ScalarValue::from("a")),
+            ColumnarValue::Scalar(ScalarValue::from("b")),
+            ColumnarValue::Scalar(ScalarValue::Int64(Some(0))),
+        ];
+        let err = invoke(args, 1).unwrap_err();
+        let regexp_error = RegexpError::find(&err).expect("Expected a RegexpError");
+        assert_eq!(
+            regexp_error.error_class(),
+            "DATATYPE_MISMATCH.VALUE_OUT_OF_RANGE"
+        );
+    }
+}
@@ -0,0 +1,173 @@
+use std::any::Any;
+use std::sync::Arc;
+
+use arrow_array::builder::StringBuilder;
+use arrow_array::{Array, ArrayRef, StringArray};
+use datafusion_common::Result;
+use datafusion_common::arrow::datatypes::DataType;
+use datafusion_expr::{
+    ColumnarValue, ScalarFunctionArgs, ScalarUDFImpl, Signature, TypeSignature, Volatility,
+};
+use regex::Regex;
+
+use crate::args::{as_string_array, extract_input_and_pattern};
+use crate::collation::Collation;
+use crate::config::{Configurable, RegexpConfig};
+use crate::metrics::{BatchMetrics, RegexpMetrics};
+use crate::pattern::{BatchPatterns, PatternCache, PatternOptions};
+
+const NAME: &str = "regexp_substr";
+
+/// Spark's `regexp_substr(str, regexp)`: the first match of `regexp` in
+/// `str`, or NULL when there is none.
+///
+/// Spark defines it as `nullif(regexp_extract(str, regexp, 0), '')`, so an
+/// empty match is NULL as well. This is what sets it apart from
+/// [`RegexpExtract`](crate::regexp_extract::RegexpExtract), which returns
+/// `""` for a row without a match and therefore counts in `COUNT(col)`.
+#[derive(Debug, Clone)]
+pub struct RegexpSubstr {
+    signature: Signature,
+    options: PatternOptions,
+    cache: Arc<PatternCache>,
+    metrics: Arc<RegexpMetrics>,
+}
+
+impl Default for RegexpSubstr {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl RegexpSubstr {
+    pub fn new() -> Self {
+        Self {
+            signature: Signature::one_of(
+                vec![TypeSignature::Exact(vec![DataType::Utf8, DataType::Utf8])],
+                Volatility::Immutable,
+            ),
+            options: PatternOptions::default(),
+            cache: Arc::new(PatternCache::default()),
+            metrics: Arc::new(RegexpMetrics::new()),
+        }
+    }
+
+    /// The collation patterns are matched under.
+    pub fn with_collation(mut self, collation: Collation) -> Self {
+        self.options.collation = collation;
+        self
+    }
+
+    /// Execution metrics, shared by every clone of this instance.
+    pub fn metrics(&self) -> &RegexpMetrics {
+        &self.metrics
+    }
+}
+
+impl Configurable for RegexpSubstr {
+    fn with_config(&self, config: &RegexpConfig) -> Option<Self> {
+        let options = self.options.with_config(config);
+        (options != self.options).then(|| Self {
+            options,
+            ..self.clone()
+        })
+    }
+}
+
+impl ScalarUDFImpl for RegexpSubstr {
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn name(&self) -> &str {
+        NAME
+    }
+
+    fn signature(&self) -> &Signature {
+        &self.signature
+    }
+
+    fn return_type(&self, _arg_types: &[DataType]) -> Result<DataType> {
+        Ok(DataType::Utf8)
+    }
+
+    fn invoke_with_args(&self, args: ScalarFunctionArgs) -> Result<ColumnarValue> {
+        let num_rows = args.number_rows;
+        let (input_array_ref, pattern_array_ref): (ArrayRef, ArrayRef) =
+            extract_input_and_pattern(&args.args[0], &args.args[1], num_rows)?;
+        let input_array: &StringArray = as_string_array(NAME, "str", &input_array_ref)?;
+        let pattern_array: &StringArray = as_string_array(NAME, "regexp", &pattern_array_ref)?;
+
+        let mut string_builder = StringBuilder::new();
+        let mut batch_metrics = BatchMetrics::start(NAME, num_rows, &self.metrics);
+        let mut patterns = BatchPatterns::new(NAME, &self.cache, self.options);
+
+        for i in 0..num_rows {
+            if input_array.is_null(i) || pattern_array.is_null(i) {
+                string_builder.append_null();
+                continue;
+            }
+
+            let compiled_regex: Arc<Regex> =
+                patterns.get(pattern_array.value(i), i, &mut batch_metrics)?;
+            batch_metrics.rows_processed += 1;
+
+            let text = self.options.collation.prepare_text(input_array.value(i));
+            match compiled_regex.find(text.as_str()) {
+                Some(m) => {
+                    batch_metrics.rows_matched += 1;
+                    if m.is_empty() {
+                        string_builder.append_null();
+                    } else {
+                        string_builder.append_value(text.original(m.range()));
+                    }
+                }
+                None => string_builder.append_null(),
+            }
+        }
+
+        Ok(ColumnarValue::Array(Arc::new(string_builder.finish())))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use datafusion_common::ScalarValue;
+    use datafusion_common::arrow::datatypes::Field;
+
+    fn run_test(input: Vec<Option<&str>>, pattern: &str) -> Vec<Option<String>> {
+        let num_rows = input.len();
+        let args = ScalarFunctionArgs {
+            args: vec![
+                ColumnarValue::Array(Arc::new(StringArray::from(input))),
+                ColumnarValue::Scalar(ScalarValue::from(pattern)),
+            ],
+            number_rows: num_rows,
+            arg_fields: vec![],
+            return_field: Arc::new(Field::new("result", DataType::Utf8, true)),
+        };
+        let result = RegexpSubstr::new().invoke_with_args(args).unwrap();
+        let array = result.to_array(num_rows).unwrap();
+        let strings = array.as_any().downcast_ref::<StringArray>().unwrap();
+        strings.iter().map(|v| v.map(str::to_string)).collect()
+    }
+
+    #[test]
+    fn test_substr_first_match_or_null() {
+        let result = run_test(vec![Some("a1b22"), Some("none"), None], r"\d+");
+        assert_eq!(result, vec![Some("1".to_string()), None, None]);
+    }
+
+    #[test]
+    fn test_substr_empty_match_is_null() {
+        let result = run_test(vec![Some("abc")], r"\d*");
+        assert_eq!(result, vec![None]);
+    }
+
+    #[test]
+    fn test_substr_unicode() {
+        let result = run_test(vec![Some("你好-世界")], r"[^-]+\z");
+        assert_eq!(result, vec![Some("世界".to_string())]);
+    }
+}
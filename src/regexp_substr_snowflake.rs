@@ -0,0 +1,465 @@
+use std::any::Any;
+use std::sync::Arc;
+
+use arrow_array::builder::StringBuilder;
+use arrow_array::{Array, ArrayRef, StringArray};
+use datafusion_common::arrow::datatypes::DataType;
+use datafusion_common::{Result, ScalarValue};
+use datafusion_expr::{
+    ColumnarValue, ScalarFunctionArgs, ScalarUDFImpl, Signature, TypeSignature, Volatility,
+};
+use regex::Regex;
+
+use crate::args::{
+    as_string_array, extract_input_and_pattern, scalar_int64, scalar_positive_int64,
+};
+use crate::collation::Collation;
+use crate::dialect::RegexDialect;
+use crate::error::RegexpError;
+use crate::metrics::{BatchMetrics, RegexpMetrics};
+use crate::pattern::{
+    BatchPatterns, PatternCache, PatternOptions, check_group_index, search_start,
+};
+
+const NAME: &str = "snowflake_regexp_substr";
+
+/// Snowflake's `REGEXP_SUBSTR(subject, pattern[, position[, occurrence[,
+/// regex_parameters[, group_num]]]])`, registered as
+/// `snowflake_regexp_substr`.
+///
+/// The search starts at the 1-based character `position` and returns the
+/// `occurrence`-th match, both defaulting to 1, or NULL when there is none.
+/// `regex_parameters` defaults to `c`; see [`RegexParameters`]. Like
+/// [`RegexpExtract`](crate::regexp_extract::RegexpExtract) it rejects a
+/// negative group, and it also rejects one past the last group of the pattern.
+#[derive(Debug, Clone)]
+pub struct SnowflakeRegexpSubstr {
+    signature: Signature,
+    cache: Arc<PatternCache>,
+    metrics: Arc<RegexpMetrics>,
+}
+
+impl Default for SnowflakeRegexpSubstr {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl SnowflakeRegexpSubstr {
+    pub fn new() -> Self {
+        let mut arguments = vec![DataType::Utf8, DataType::Utf8];
+        let mut signatures = vec![TypeSignature::Exact(arguments.clone())];
+        for argument in [
+            DataType::Int64,
+            DataType::Int64,
+            DataType::Utf8,
+            DataType::Int64,
+        ] {
+            arguments.push(argument);
+            signatures.push(TypeSignature::Exact(arguments.clone()));
+        }
+        Self {
+            signature: Signature::one_of(signatures, Volatility::Immutable),
+            cache: Arc::new(PatternCache::default()),
+            metrics: Arc::new(RegexpMetrics::new()),
+        }
+    }
+
+    /// Execution metrics, shared by every clone of this instance.
+    pub fn metrics(&self) -> &RegexpMetrics {
+        &self.metrics
+    }
+}
+
+/// The letters of Snowflake's `regex_parameters` string.
+///
+/// `c` and `i` select case-sensitive and case-insensitive matching, the last
+/// one given winning. `m` makes `^` and `$` match at line breaks and `s` lets
+/// `.` match a newline. `e` extracts a group instead of the whole match: group
+/// 1 unless `group_num` says otherwise, and the whole match anyway when the
+/// pattern has no groups. Passing `group_num` implies `e`.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct RegexParameters {
+    pub case_insensitive: bool,
+    pub multi_line: bool,
+    pub dot_all: bool,
+    pub extract: bool,
+}
+
+impl RegexParameters {
+    pub fn parse(function: &'static str, parameters: &str) -> Result<Self, RegexpError> {
+        let mut parsed = Self::default();
+        for letter in parameters.chars() {
+            match letter {
+                'c' => parsed.case_insensitive = false,
+                'i' => parsed.case_insensitive = true,
+                'm' => parsed.multi_line = true,
+                's' => parsed.dot_all = true,
+                'e' => parsed.extract = true,
+                _ => {
+                    return Err(RegexpError::InvalidRegexParameters {
+                        function,
+                        parameters: parameters.to_string(),
+                        letter,
+                    });
+                }
+            }
+        }
+        Ok(parsed)
+    }
+
+    /// The inline flags that apply `m` and `s` to a pattern.
+    fn flags(&self) -> &'static str {
+        match (self.multi_line, self.dot_all) {
+            (false, false) => "",
+            (true, false) => "(?m)",
+            (false, true) => "(?s)",
+            (true, true) => "(?ms)",
+        }
+    }
+
+    fn options(&self) -> PatternOptions {
+        PatternOptions {
+            collation: if self.case_insensitive {
+                Collation::Utf8Lcase
+            } else {
+                Collation::Utf8Binary
+            },
+            dialect: RegexDialect::Rust,
+            ..Default::default()
+        }
+    }
+}
+
+impl ScalarUDFImpl for SnowflakeRegexpSubstr {
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn name(&self) -> &str {
+        NAME
+    }
+
+    fn signature(&self) -> &Signature {
+        &self.signature
+    }
+
+    fn return_type(&self, _arg_types: &[DataType]) -> Result<DataType> {
+        Ok(DataType::Utf8)
+    }
+
+    fn invoke_with_args(&self, args: ScalarFunctionArgs) -> Result<ColumnarValue> {
+        let num_rows = args.number_rows;
+        let null = Ok(ColumnarValue::Scalar(ScalarValue::Utf8(None)));
+
+        let Some(position) = scalar_positive_int64(NAME, "position", args.args.get(2))? else {
+            return null;
+        };
+        let Some(occurrence) = scalar_positive_int64(NAME, "occurrence", args.args.get(3))? else {
+            return null;
+        };
+        let parameters = match args.args.get(4) {
+            None => RegexParameters::default(),
+            Some(ColumnarValue::Scalar(ScalarValue::Utf8(None))) => return null,
+            Some(ColumnarValue::Scalar(ScalarValue::Utf8(Some(parameters)))) => {
+                RegexParameters::parse(NAME, parameters)?
+            }
+            Some(other) => {
+                return Err(RegexpError::UnexpectedInputType {
+                    function: NAME,
+                    argument: "regex_parameters",
+                    expected: "a STRING literal",
+                    actual: other.data_type().to_string(),
+                }
+                .into());
+            }
+        };
+        // `None` extracts the whole match.
+        let group_num: Option<i64> = match args.args.get(5) {
+            None if parameters.extract => Some(1),
+            None => None,
+            Some(_) => match scalar_int64(NAME, "group_num", args.args.get(5), 0)? {
+                Some(group_num) => Some(group_num),
+                None => return null,
+            },
+        };
+        let explicit_group = args.args.len() > 5;
+
+        let (input_array_ref, pattern_array_ref): (ArrayRef, ArrayRef) =
+            extract_input_and_pattern(&args.args[0], &args.args[1], num_rows)?;
+        let input_array: &StringArray = as_string_array(NAME, "subject", &input_array_ref)?;
+        let pattern_array: &StringArray = as_string_array(NAME, "pattern", &pattern_array_ref)?;
+
+        let mut string_builder = StringBuilder::new();
+        let mut batch_metrics = BatchMetrics::start(NAME, num_rows, &self.metrics);
+        let mut patterns = BatchPatterns::new(NAME, &self.cache, parameters.options())
+            .with_flags(parameters.flags());
+
+        for i in 0..num_rows {
+            if input_array.is_null(i) || pattern_array.is_null(i) {
+                string_builder.append_null();
+                continue;
+            }
+
+            let input_val: &str = input_array.value(i);
+            let pattern: &str = pattern_array.value(i);
+
+            let compiled_regex: Arc<Regex> = patterns.get(pattern, i, &mut batch_metrics)?;
+            batch_metrics.rows_processed += 1;
+
+            let group_count = compiled_regex.captures_len() - 1;
+            let idx = match group_num {
+                Some(idx) if explicit_group => {
+                    check_group_index(NAME, pattern, &compiled_regex, idx, i)?;
+                    if idx > group_count as i64 {
+                        return Err(RegexpError::InvalidGroupIndex {
+                            function: NAME,
+                            pattern: pattern.to_string(),
+                            index: idx,
+                            group_count,
+                            row: i,
+                        }
+                        .into());
+                    }
+                    idx as usize
+                }
+                // `e` without `group_num` falls back to the whole match.
+                Some(_) if group_count > 0 => 1,
+                _ => 0,
+            };
+
+            let Some(start) = search_start(input_val, position) else {
+                string_builder.append_null();
+                continue;
+            };
+
+            let captures = compiled_regex
+                .captures_iter(&input_val[start..])
+                .nth((occurrence - 1) as usize);
+            if captures.is_some() {
+                batch_metrics.rows_matched += 1;
+            }
+            string_builder.append_option(
+                captures
+                    .and_then(|captures| captures.get(idx))
+                    .map(|group| group.as_str()),
+            );
+        }
+
+        Ok(ColumnarValue::Array(Arc::new(string_builder.finish())))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use datafusion_common::arrow::datatypes::Field;
+
+    /// Runs the function over `input`; the trailing arguments are given as
+    /// scalars in signature order.
+    fn run_test(
+        input: Vec<Option<&str>>,
+        pattern: &str,
+        trailing: Vec<ScalarValue>,
+    ) -> Result<Vec<Option<String>>> {
+        let num_rows = input.len();
+        let mut args = vec![
+            ColumnarValue::Array(Arc::new(StringArray::from(input))),
+            ColumnarValue::Scalar(ScalarValue::from(pattern)),
+        ];
+        args.extend(trailing.into_iter().map(ColumnarValue::Scalar));
+        let args = ScalarFunctionArgs {
+            args,
+            number_rows: num_rows,
+            arg_fields: vec![],
+            return_field: Arc::new(Field::new("result", DataType::Utf8, true)),
+        };
+        let array = SnowflakeRegexpSubstr::new()
+            .invoke_with_args(args)?
+            .to_array(num_rows)?;
+        let strings = array.as_any().downcast_ref::<StringArray>().unwrap();
+        Ok(strings.iter().map(|v| v.map(str::to_string)).collect())
+    }
+
+    fn int(value: i64) -> ScalarValue {
+        ScalarValue::Int64(Some(value))
+    }
+
+    fn text(value: &str) -> ScalarValue {
+        ScalarValue::from(value)
+    }
+
+    fn strings(values: &[Option<&str>]) -> Vec<Option<String>> {
+        values.iter().map(|v| v.map(str::to_string)).collect()
+    }
+
+    const SUBJECT: &str = "It was the best of times, it was the worst of times.";
+
+    #[test]
+    fn test_defaults_to_first_whole_match() {
+        let result = run_test(
+            vec![Some(SUBJECT), Some("none"), None],
+            r"the\W+\w+",
+            vec![],
+        );
+        assert_eq!(result.unwrap(), strings(&[Some("the best"), None, None]));
+    }
+
+    #[test]
+    fn test_position_and_occurrence() {
+        let result = run_test(vec![Some(SUBJECT)], r"the\W+\w+", vec![int(1), int(2)]);
+        assert_eq!(result.unwrap(), strings(&[Some("the worst")]));
+
+        let result = run_test(vec![Some(SUBJECT)], r"the\W+\w+", vec![int(12), int(1)]);
+        assert_eq!(result.unwrap(), strings(&[Some("the worst")]));
+
+        let result = run_test(vec![Some(SUBJECT)], r"the\W+\w+", vec![int(1), int(3)]);
+        assert_eq!(result.unwrap(), strings(&[None]));
+
+        let result = run_test(vec![Some("abc")], "c", vec![int(4)]);
+        assert_eq!(result.unwrap(), strings(&[None]));
+    }
+
+    #[test]
+    fn test_parameter_c_is_case_sensitive() {
+        let result = run_test(
+            vec![Some("Hello")],
+            "hello",
+            vec![int(1), int(1), text("c")],
+        );
+        assert_eq!(result.unwrap(), strings(&[None]));
+    }
+
+    #[test]
+    fn test_parameter_i_is_case_insensitive_and_last_of_c_i_wins() {
+        let result = run_test(
+            vec![Some("Hello")],
+            "hello",
+            vec![int(1), int(1), text("i")],
+        );
+        assert_eq!(result.unwrap(), strings(&[Some("Hello")]));
+
+        let result = run_test(
+            vec![Some("Hello")],
+            "hello",
+            vec![int(1), int(1), text("ic")],
+        );
+        assert_eq!(result.unwrap(), strings(&[None]));
+
+        let result = run_test(
+            vec![Some("Hello")],
+            "hello",
+            vec![int(1), int(1), text("ci")],
+        );
+        assert_eq!(result.unwrap(), strings(&[Some("Hello")]));
+    }
+
+    #[test]
+    fn test_parameter_m_anchors_at_line_breaks() {
+        let input = vec![Some("first\nsecond")];
+        let result = run_test(input.clone(), "^s\\w+", vec![int(1), int(1), text("")]);
+        assert_eq!(result.unwrap(), strings(&[None]));
+
+        let result = run_test(input, "^s\\w+", vec![int(1), int(1), text("m")]);
+        assert_eq!(result.unwrap(), strings(&[Some("second")]));
+    }
+
+    #[test]
+    fn test_parameter_s_lets_dot_match_newline() {
+        let input = vec![Some("a\nb")];
+        let result = run_test(input.clone(), "a.b", vec![int(1), int(1), text("c")]);
+        assert_eq!(result.unwrap(), strings(&[None]));
+
+        let result = run_test(input, "a.b", vec![int(1), int(1), text("s")]);
+        assert_eq!(result.unwrap(), strings(&[Some("a\nb")]));
+    }
+
+    #[test]
+    fn test_parameter_e_extracts_first_group() {
+        let pattern = r"the\W+(\w+)";
+        let result = run_test(
+            vec![Some(SUBJECT)],
+            pattern,
+            vec![int(1), int(2), text("e")],
+        );
+        assert_eq!(result.unwrap(), strings(&[Some("worst")]));
+
+        // Without groups, `e` returns the whole match.
+        let result = run_test(
+            vec![Some(SUBJECT)],
+            r"the\W+\w+",
+            vec![int(1), int(1), text("e")],
+        );
+        assert_eq!(result.unwrap(), strings(&[Some("the best")]));
+    }
+
+    #[test]
+    fn test_group_num_implies_e() {
+        let pattern = r"(\w+) of (\w+)";
+        let trailing = vec![int(1), int(1), text("c"), int(2)];
+        let result = run_test(vec![Some(SUBJECT)], pattern, trailing);
+        assert_eq!(result.unwrap(), strings(&[Some("times")]));
+
+        let trailing = vec![int(1), int(1), text("e"), int(0)];
+        let result = run_test(vec![Some(SUBJECT)], pattern, trailing);
+        assert_eq!(result.unwrap(), strings(&[Some("best of times")]));
+    }
+
+    #[test]
+    fn test_unmatched_group_is_null() {
+        let trailing = vec![int(1), int(1), text("e"), int(1)];
+        let result = run_test(vec![Some("b")], "(a)|b", trailing);
+        assert_eq!(result.unwrap(), strings(&[None]));
+    }
+
+    #[test]
+    fn test_null_arguments_are_null() {
+        for trailing in [
+            vec![ScalarValue::Int64(None)],
+            vec![int(1), ScalarValue::Int64(None)],
+            vec![int(1), int(1), ScalarValue::Utf8(None)],
+            vec![int(1), int(1), text("e"), ScalarValue::Int64(None)],
+        ] {
+            let result = run_test(vec![Some("abc")], "(b)", trailing.clone());
+            assert_eq!(result.unwrap(), strings(&[None]), "{trailing:?}");
+        }
+    }
+
+    #[test]
+    fn test_unknown_parameter_is_an_error() {
+        let err = run_test(vec![Some("abc")], "b", vec![int(1), int(1), text("ix")]).unwrap_err();
+        let err = RegexpError::find(&err).unwrap();
+        assert_eq!(
+            err.error_class(),
+            "INVALID_PARAMETER_VALUE.REGEX_PARAMETERS"
+        );
+        assert!(err.to_string().contains("unknown parameter 'x' in 'ix'"));
+    }
+
+    #[test]
+    fn test_group_num_past_the_last_group_is_an_error() {
+        let trailing = vec![int(1), int(1), text("e"), int(2)];
+        let err = run_test(vec![Some("abc")], "(b)", trailing).unwrap_err();
+        let err = RegexpError::find(&err).unwrap();
+        assert_eq!(
+            err.error_class(),
+            "INVALID_PARAMETER_VALUE.REGEX_GROUP_INDEX"
+        );
+    }
+
+    #[test]
+    fn test_non_positive_position_and_occurrence_are_errors() {
+        for trailing in [vec![int(0)], vec![int(1), int(-1)]] {
+            let err = run_test(vec![Some("abc")], "b", trailing).unwrap_err();
+            let err = RegexpError::find(&err).unwrap();
+            assert_eq!(err.error_class(), "DATATYPE_MISMATCH.VALUE_OUT_OF_RANGE");
+        }
+    }
+
+    #[test]
+    fn test_invalid_pattern_reports_pattern_without_flags() {
+        let err = run_test(vec![Some("abc")], "(", vec![int(1), int(1), text("ms")]).unwrap_err();
+        let err = RegexpError::find(&err).unwrap();
+        assert_eq!(err.pattern(), Some("("));
+    }
+}
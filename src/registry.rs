@@ -0,0 +1,344 @@
+//! One-call registration of this crate's functions.
+//!
+//! ```ignore
+//! let ctx = SessionContext::new();
+//! register_all(&ctx)?;
+//! // or only the regex functions, under one engine's names:
+//! register_regex_functions(&ctx, Dialect::Trino)?;
+//! ```
+//!
+//! Functions can be installed into a [`SessionContext`], a [`SessionState`],
+//! any other [`FunctionRegistry`], or a [`SessionStateBuilder`] before the
+//! state is built; see [`FunctionTarget`]. Registering a name that is already
+//! taken, such as DataFusion's own `regexp_like`, replaces the function.
+//!
+//! Along with the functions, the `regexp.*` config namespace
+//! ([`RegexpConfig`]) and the [`RegexpConfigRewrite`] that applies it are
+//! installed. A bare [`FunctionRegistry`] only gets the rewrite, as it has no
+//! config to add the namespace to.
+//!
+//! [`register_all`] also installs the `REGEX` and `ACCESS_LOG` file formats
+//! ([`RegexFormatFactory`], [`AccessLogFormatFactory`]) for
+//! `CREATE EXTERNAL TABLE ... STORED AS`, keeping any format already
+//! registered under those names.
+//!
+//! The `RLIKE` and `REGEXP` operators are only planned by [`crate::sql`]:
+//! DataFusion 49's SQL planner rejects them before any planner extension
+//! could be consulted. `LIKE` and `ILIKE` keep DataFusion's planning.
+
+use std::collections::HashMap;
+use std::sync::Arc;
+
+use datafusion::datasource::file_format::FileFormatFactory;
+use datafusion::datasource::provider::DefaultTableFactory;
+use datafusion::execution::SessionStateBuilder;
+use datafusion::execution::context::{SessionConfig, SessionContext, SessionState};
+use datafusion::optimizer::Analyzer;
+use datafusion_common::Result;
+use datafusion_common::config::ConfigOptions;
+use datafusion_expr::expr_rewriter::FunctionRewrite;
+use datafusion_expr::registry::FunctionRegistry;
+use datafusion_expr::{ScalarUDF, ScalarUDFImpl};
+
+use crate::access_log::AccessLogFormatFactory;
+use crate::config::{RegexpConfig, RegexpConfigRewrite, is_configurable};
+use crate::grok::Grok;
+use crate::like::{Like, LikeMulti};
+use crate::parse_url::ParseUrl;
+use crate::redact::Redact;
+use crate::regex_format::RegexFormatFactory;
+use crate::regexp_classify::RegexpClassify;
+use crate::regexp_count::RegexpCount;
+use crate::regexp_explain::RegexpExplain;
+use crate::regexp_extract::RegexpExtract;
+use crate::regexp_extract_all::RegexpExtractAll;
+use crate::regexp_extract_bigquery::BigQueryRegexpExtract;
+use crate::regexp_extract_trino::TrinoRegexpExtract;
+use crate::regexp_extract_with_offsets::RegexpExtractWithOffsets;
+use crate::regexp_instr::RegexpInstr;
+use crate::regexp_replace::RegexpReplace;
+use crate::regexp_substr::RegexpSubstr;
+use crate::regexp_substr_snowflake::SnowflakeRegexpSubstr;
+use crate::rlike::RLike;
+use crate::split::Split;
+use crate::str_to_map::StrToMap;
+
+/// The engine whose function names and semantics
+/// [`register_regex_functions`] installs.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub enum Dialect {
+    /// Spark's `regexp_*` family, `rlike`, `split` and `str_to_map`.
+    #[default]
+    Spark,
+    /// `trino_regexp_extract` as `regexp_extract`, and `regexp_like`.
+    Trino,
+    /// `bigquery_regexp_extract` as `regexp_extract` and `regexp_substr`.
+    BigQuery,
+    /// `snowflake_regexp_substr` as `regexp_substr`.
+    Snowflake,
+}
+
+/// Somewhere functions, and the file formats of [`register_all`], can be
+/// registered.
+pub trait FunctionTarget {
+    fn register_udf(&mut self, udf: Arc<ScalarUDF>) -> Result<()>;
+
+    /// Adds `rewrite` unless one with the same name is already installed.
+    fn register_function_rewrite(
+        &mut self,
+        rewrite: Arc<dyn FunctionRewrite + Send + Sync>,
+    ) -> Result<()>;
+
+    /// Adds the `regexp.*` namespace unless it is already there, so values
+    /// set earlier are kept.
+    fn register_config(&mut self) {}
+
+    /// Adds `factory` unless a format with the same name is already there,
+    /// along with the table factory `STORED AS` looks that name up in.
+    fn register_file_format(&mut self, _factory: Arc<dyn FileFormatFactory>) -> Result<()> {
+        Ok(())
+    }
+}
+
+fn has_rewrite(analyzer: &Analyzer, name: &str) -> bool {
+    analyzer
+        .function_rewrites
+        .iter()
+        .any(|rewrite| rewrite.name() == name)
+}
+
+fn add_config(options: &mut ConfigOptions) {
+    if options.extensions.get::<RegexpConfig>().is_none() {
+        options.extensions.insert(RegexpConfig::default());
+    }
+}
+
+impl FunctionTarget for &SessionContext {
+    fn register_udf(&mut self, udf: Arc<ScalarUDF>) -> Result<()> {
+        SessionContext::register_udf(self, udf.as_ref().clone());
+        Ok(())
+    }
+
+    fn register_function_rewrite(
+        &mut self,
+        rewrite: Arc<dyn FunctionRewrite + Send + Sync>,
+    ) -> Result<()> {
+        let state = self.state_ref();
+        let mut state = state.write();
+        <&mut SessionState as FunctionTarget>::register_function_rewrite(&mut &mut *state, rewrite)
+    }
+
+    fn register_config(&mut self) {
+        let state = self.state_ref();
+        let mut state = state.write();
+        <&mut SessionState as FunctionTarget>::register_config(&mut &mut *state);
+    }
+
+    fn register_file_format(&mut self, factory: Arc<dyn FileFormatFactory>) -> Result<()> {
+        let state = self.state_ref();
+        let mut state = state.write();
+        <&mut SessionState as FunctionTarget>::register_file_format(&mut &mut *state, factory)
+    }
+}
+
+impl FunctionTarget for &mut SessionState {
+    fn register_udf(&mut self, udf: Arc<ScalarUDF>) -> Result<()> {
+        FunctionRegistry::register_udf(*self, udf).map(drop)
+    }
+
+    fn register_function_rewrite(
+        &mut self,
+        rewrite: Arc<dyn FunctionRewrite + Send + Sync>,
+    ) -> Result<()> {
+        if has_rewrite(self.analyzer(), rewrite.name()) {
+            return Ok(());
+        }
+        FunctionRegistry::register_function_rewrite(*self, rewrite)
+    }
+
+    fn register_config(&mut self) {
+        add_config(self.config_mut().options_mut());
+    }
+
+    fn register_file_format(&mut self, factory: Arc<dyn FileFormatFactory>) -> Result<()> {
+        self.table_factories_mut()
+            .entry(factory.get_ext().to_uppercase())
+            .or_insert_with(|| Arc::new(DefaultTableFactory::new()));
+        if self.get_file_format_factory(&factory.get_ext()).is_some() {
+            return Ok(());
+        }
+        SessionState::register_file_format(self, factory, false)
+    }
+}
+
+impl FunctionTarget for &mut dyn FunctionRegistry {
+    fn register_udf(&mut self, udf: Arc<ScalarUDF>) -> Result<()> {
+        FunctionRegistry::register_udf(*self, udf).map(drop)
+    }
+
+    /// A registry does not list its rewrites, so the rewrite is taken to be
+    /// installed when the registry already holds a function it configures,
+    /// which [`register_all`] only registers after installing the rewrite.
+    fn register_function_rewrite(
+        &mut self,
+        rewrite: Arc<dyn FunctionRewrite + Send + Sync>,
+    ) -> Result<()> {
+        let installed = self
+            .udfs()
+            .iter()
+            .any(|name| self.udf(name).is_ok_and(|udf| is_configurable(&udf)));
+        if installed {
+            return Ok(());
+        }
+        FunctionRegistry::register_function_rewrite(*self, rewrite)
+    }
+}
+
+/// Functions are appended to the builder's list and the namespace to its
+/// config, so call this after `with_default_features` and `with_config`,
+/// which would otherwise replace them.
+impl FunctionTarget for &mut SessionStateBuilder {
+    fn register_udf(&mut self, udf: Arc<ScalarUDF>) -> Result<()> {
+        self.scalar_functions()
+            .get_or_insert_with(Vec::new)
+            .push(udf);
+        Ok(())
+    }
+
+    fn register_function_rewrite(
+        &mut self,
+        rewrite: Arc<dyn FunctionRewrite + Send + Sync>,
+    ) -> Result<()> {
+        let analyzer = self.analyzer().get_or_insert_with(Analyzer::new);
+        if !has_rewrite(analyzer, rewrite.name()) {
+            analyzer.add_function_rewrite(rewrite);
+        }
+        Ok(())
+    }
+
+    fn register_config(&mut self) {
+        add_config(
+            self.config()
+                .get_or_insert_with(SessionConfig::new)
+                .options_mut(),
+        );
+    }
+
+    fn register_file_format(&mut self, factory: Arc<dyn FileFormatFactory>) -> Result<()> {
+        self.table_factories()
+            .get_or_insert_with(HashMap::new)
+            .entry(factory.get_ext().to_uppercase())
+            .or_insert_with(|| Arc::new(DefaultTableFactory::new()));
+        let formats = self.file_formats().get_or_insert_with(Vec::new);
+        let ext = factory.get_ext().to_lowercase();
+        if !formats
+            .iter()
+            .any(|format| format.get_ext().to_lowercase() == ext)
+        {
+            formats.push(factory);
+        }
+        Ok(())
+    }
+}
+
+fn install(
+    mut target: impl FunctionTarget,
+    functions: Vec<Arc<ScalarUDF>>,
+    file_formats: Vec<Arc<dyn FileFormatFactory>>,
+) -> Result<()> {
+    // Before the functions, which is how a bare registry tells whether an
+    // earlier call installed it.
+    target.register_function_rewrite(Arc::new(RegexpConfigRewrite))?;
+    for udf in functions {
+        target.register_udf(udf)?;
+    }
+    target.register_config();
+    for factory in file_formats {
+        target.register_file_format(factory)?;
+    }
+    Ok(())
+}
+
+fn udf(function: impl ScalarUDFImpl + 'static) -> Arc<ScalarUDF> {
+    Arc::new(ScalarUDF::new_from_impl(function))
+}
+
+/// The regex functions of `dialect`, under that engine's names.
+pub fn regex_functions(dialect: Dialect) -> Vec<Arc<ScalarUDF>> {
+    match dialect {
+        Dialect::Spark => vec![
+            udf(RegexpExtract::new()),
+            udf(RegexpExtractAll::new()),
+            udf(RegexpExtractWithOffsets::new()),
+            udf(RegexpInstr::new()),
+            udf(RegexpCount::new()),
+            udf(RegexpReplace::new()),
+            udf(RegexpSubstr::new()),
+            udf(RLike::new()),
+            udf(Split::new()),
+            udf(StrToMap::new()),
+        ],
+        Dialect::Trino => vec![
+            Arc::new(
+                ScalarUDF::new_from_impl(TrinoRegexpExtract::new())
+                    .with_aliases(["regexp_extract"]),
+            ),
+            udf(RLike::new()),
+        ],
+        Dialect::BigQuery => vec![Arc::new(
+            ScalarUDF::new_from_impl(BigQueryRegexpExtract::new())
+                .with_aliases(["regexp_extract", "regexp_substr"]),
+        )],
+        Dialect::Snowflake => vec![Arc::new(
+            ScalarUDF::new_from_impl(SnowflakeRegexpSubstr::new()).with_aliases(["regexp_substr"]),
+        )],
+    }
+}
+
+/// Every function in this crate: Spark names for the regex functions, and
+/// the other engines' variants under their prefixed names only.
+pub fn all_functions() -> Vec<Arc<ScalarUDF>> {
+    let mut functions = regex_functions(Dialect::Spark);
+    functions.extend([
+        udf(Like::new()),
+        udf(Like::ilike()),
+        udf(RegexpClassify::new()),
+        udf(RegexpClassify::all()),
+        udf(RegexpExplain::new()),
+        udf(Redact::new()),
+        udf(Grok::new()),
+        udf(ParseUrl::new()),
+        udf(ParseUrl::try_parse_url()),
+        udf(TrinoRegexpExtract::new()),
+        udf(BigQueryRegexpExtract::new()),
+        udf(SnowflakeRegexpSubstr::new()),
+    ]);
+    functions.extend(LikeMulti::all_variants().into_iter().map(udf));
+    functions
+}
+
+/// The file formats in this crate, for `CREATE EXTERNAL TABLE ... STORED AS`.
+pub fn all_file_formats() -> Vec<Arc<dyn FileFormatFactory>> {
+    vec![
+        Arc::new(RegexFormatFactory::new()),
+        Arc::new(AccessLogFormatFactory::new()),
+    ]
+}
+
+/// Registers the regex functions of `dialect`.
+pub fn register_regex_functions(target: impl FunctionTarget, dialect: Dialect) -> Result<()> {
+    install(target, regex_functions(dialect), Vec::new())
+}
+
+/// Registers every function and file format in this crate; see
+/// [`all_functions`] and [`all_file_formats`]. Calling it again on the same
+/// target changes nothing.
+///
+/// **This replaces DataFusion's built-in `regexp_replace`, `regexp_like`,
+/// `regexp_count` and `regexp_instr`** with Spark's versions, which take Java
+/// patterns and Spark's arguments, so existing queries using those names can
+/// return different results or fail. A `split` registered earlier is
+/// replaced the same way.
+pub fn register_all(target: impl FunctionTarget) -> Result<()> {
+    install(target, all_functions(), all_file_formats())
+}
@@ -0,0 +1,215 @@
+use std::any::Any;
+use std::sync::Arc;
+
+use arrow_array::builder::BooleanBuilder;
+use arrow_array::{Array, ArrayRef, StringArray};
+use datafusion_common::Result;
+use datafusion_common::arrow::datatypes::DataType;
+use datafusion_expr::{
+    ColumnarValue, ScalarFunctionArgs, ScalarUDFImpl, Signature, TypeSignature, Volatility,
+};
+use regex::Regex;
+
+use crate::args::{as_string_array, extract_input_and_pattern};
+use crate::collation::Collation;
+use crate::config::{Configurable, RegexpConfig};
+use crate::metrics::{BatchMetrics, RegexpMetrics};
+use crate::pattern::{BatchPatterns, PatternCache, PatternOptions};
+
+const NAME: &str = "rlike";
+
+/// Spark's `rlike(str, regexp)`, also available as `regexp` and
+/// `regexp_like`: true when `regexp` matches anywhere in `str`.
+///
+/// **The `x RLIKE p` and `x REGEXP p` operators only work through
+/// [`crate::sql::sql`].** DataFusion's own `SessionContext::sql` rejects
+/// them before consulting any planner extension, so there only the function
+/// call forms `rlike(x, p)`, `regexp(x, p)` and `regexp_like(x, p)` work.
+///
+/// Patterns use Java syntax, so `'^\d+$'` is ASCII-only as in Spark. A NULL
+/// `str` or `regexp` yields NULL.
+#[derive(Debug, Clone)]
+pub struct RLike {
+    signature: Signature,
+    aliases: Vec<String>,
+    options: PatternOptions,
+    cache: Arc<PatternCache>,
+    metrics: Arc<RegexpMetrics>,
+}
+
+impl Default for RLike {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl RLike {
+    pub fn new() -> Self {
+        Self {
+            signature: Signature::one_of(
+                vec![TypeSignature::Exact(vec![DataType::Utf8, DataType::Utf8])],
+                Volatility::Immutable,
+            ),
+            aliases: vec!["regexp".to_string(), "regexp_like".to_string()],
+            // Only whether the pattern matches is read, never where.
+            options: PatternOptions {
+                match_only: true,
+                ..Default::default()
+            },
+            cache: Arc::new(PatternCache::default()),
+            metrics: Arc::new(RegexpMetrics::new()),
+        }
+    }
+
+    /// The collation patterns are matched under.
+    pub fn with_collation(mut self, collation: Collation) -> Self {
+        self.options.collation = collation;
+        self
+    }
+
+    /// Execution metrics, shared by every clone of this instance.
+    pub fn metrics(&self) -> &RegexpMetrics {
+        &self.metrics
+    }
+}
+
+impl Configurable for RLike {
+    fn with_config(&self, config: &RegexpConfig) -> Option<Self> {
+        let options = self.options.with_config(config);
+        (options != self.options).then(|| Self {
+            options,
+            ..self.clone()
+        })
+    }
+}
+
+impl ScalarUDFImpl for RLike {
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn name(&self) -> &str {
+        NAME
+    }
+
+    fn aliases(&self) -> &[String] {
+        &self.aliases
+    }
+
+    fn signature(&self) -> &Signature {
+        &self.signature
+    }
+
+    fn return_type(&self, _arg_types: &[DataType]) -> Result<DataType> {
+        Ok(DataType::Boolean)
+    }
+
+    fn invoke_with_args(&self, args: ScalarFunctionArgs) -> Result<ColumnarValue> {
+        let num_rows = args.number_rows;
+        let (input_array_ref, pattern_array_ref): (ArrayRef, ArrayRef) =
+            extract_input_and_pattern(&args.args[0], &args.args[1], num_rows)?;
+        let input_array: &StringArray = as_string_array(NAME, "str", &input_array_ref)?;
+        let pattern_array: &StringArray = as_string_array(NAME, "regexp", &pattern_array_ref)?;
+
+        let mut builder = BooleanBuilder::with_capacity(num_rows);
+        let mut batch_metrics = BatchMetrics::start(NAME, num_rows, &self.metrics);
+        let mut patterns = BatchPatterns::new(NAME, &self.cache, self.options);
+
+        for i in 0..num_rows {
+            if input_array.is_null(i) || pattern_array.is_null(i) {
+                builder.append_null();
+                continue;
+            }
+
+            let compiled_regex: Arc<Regex> =
+                patterns.get(pattern_array.value(i), i, &mut batch_metrics)?;
+            batch_metrics.rows_processed += 1;
+
+            // Like Java's Matcher.find, the pattern may match anywhere.
+            let text = self.options.collation.prepare_text(input_array.value(i));
+            let matched = compiled_regex.is_match(text.as_str());
+            if matched {
+                batch_metrics.rows_matched += 1;
+            }
+            builder.append_value(matched);
+        }
+
+        Ok(ColumnarValue::Array(Arc::new(builder.finish())))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::error::RegexpError;
+    use arrow_array::BooleanArray;
+    use datafusion_common::ScalarValue;
+    use datafusion_common::arrow::datatypes::Field;
+
+    fn run_test(
+        input: ColumnarValue,
+        pattern: ColumnarValue,
+        num_rows: usize,
+    ) -> Result<Vec<Option<bool>>> {
+        let args = ScalarFunctionArgs {
+            args: vec![input, pattern],
+            number_rows: num_rows,
+            arg_fields: vec![],
+            return_field: Arc::new(Field::new("result", DataType::Boolean, true)),
+        };
+        let result = RLike::new().invoke_with_args(args)?;
+        let array = result.to_array(num_rows)?;
+        let booleans = array.as_any().downcast_ref::<BooleanArray>().unwrap();
+        Ok(booleans.iter().collect())
+    }
+
+    #[test]
+    fn test_rlike_matches_anywhere() {
+        let result = run_test(
+            ColumnarValue::Array(Arc::new(StringArray::from(vec!["abc123", "abc", "123"]))),
+            ColumnarValue::Scalar(ScalarValue::from(r"\d+")),
+            3,
+        )
+        .unwrap();
+        assert_eq!(result, vec![Some(true), Some(false), Some(true)]);
+    }
+
+    #[test]
+    fn test_rlike_null_propagation() {
+        let result = run_test(
+            ColumnarValue::Array(Arc::new(StringArray::from(vec![None, Some("abc")]))),
+            ColumnarValue::Array(Arc::new(StringArray::from(vec![Some("a"), None]))),
+            2,
+        )
+        .unwrap();
+        assert_eq!(result, vec![None, None]);
+    }
+
+    #[test]
+    fn test_rlike_uses_java_syntax() {
+        let result = run_test(
+            ColumnarValue::Array(Arc::new(StringArray::from(vec!["٣", "3", "a.b", "a+b"]))),
+            ColumnarValue::Array(Arc::new(StringArray::from(vec![
+                r"^\d$", r"^\d$", r"\Qa.b\E", r"\Qa.b\E",
+            ]))),
+            4,
+        )
+        .unwrap();
+        assert_eq!(
+            result,
+            vec![Some(false), Some(true), Some(true), Some(false)]
+        );
+    }
+
+    #[test]
+    fn test_rlike_unsupported_construct_is_invalid_pattern() {
+        let err = run_test(
+            ColumnarValue::Array(Arc::new(StringArray::from(vec!["abab"]))),
+            ColumnarValue::Scalar(ScalarValue::from(r"(ab)\1")),
+            1,
+        )
+        .unwrap_err();
+        let err = RegexpError::find(&err).unwrap();
+        assert_eq!(err.error_class(), "INVALID_PARAMETER_VALUE.PATTERN");
+    }
+}
@@ -0,0 +1,289 @@
+use std::any::Any;
+use std::sync::Arc;
+
+use arrow_array::builder::{ListBuilder, StringBuilder};
+use arrow_array::{Array, ArrayRef, StringArray};
+use datafusion_common::Result;
+use datafusion_common::arrow::datatypes::{DataType, Field};
+use datafusion_expr::{
+    ColumnarValue, ScalarFunctionArgs, ScalarUDFImpl, Signature, TypeSignature, Volatility,
+};
+use regex::Regex;
+
+use crate::args::{as_string_array, extract_input_and_pattern, scalar_int64};
+use crate::collation::{CollatedText, Collation};
+use crate::config::{Configurable, RegexpConfig};
+use crate::metrics::{BatchMetrics, RegexpMetrics};
+use crate::pattern::{BatchPatterns, PatternCache, PatternOptions, java_find_iter};
+
+const NAME: &str = "split";
+
+/// Spark's `split(str, regex[, limit])`: `str` split around matches of
+/// `regex`, as a `List<Utf8>`.
+///
+/// A positive `limit` caps the number of pieces, the last one holding the
+/// rest of the input. Zero or a negative `limit` (the default is -1) means no
+/// cap, and unlike Java's `String.split` trailing empty strings are kept. As
+/// in Java, a zero-width match at the start never produces a leading empty
+/// string, and an empty `regex` splits a non-empty `str` into characters.
+#[derive(Debug, Clone)]
+pub struct Split {
+    signature: Signature,
+    options: PatternOptions,
+    cache: Arc<PatternCache>,
+    metrics: Arc<RegexpMetrics>,
+}
+
+impl Default for Split {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Split {
+    pub fn new() -> Self {
+        Self {
+            signature: Signature::one_of(
+                vec![
+                    TypeSignature::Exact(vec![DataType::Utf8, DataType::Utf8]),
+                    TypeSignature::Exact(vec![DataType::Utf8, DataType::Utf8, DataType::Int64]),
+                ],
+                Volatility::Immutable,
+            ),
+            options: PatternOptions::default(),
+            cache: Arc::new(PatternCache::default()),
+            metrics: Arc::new(RegexpMetrics::new()),
+        }
+    }
+
+    /// The collation patterns are matched under.
+    pub fn with_collation(mut self, collation: Collation) -> Self {
+        self.options.collation = collation;
+        self
+    }
+
+    /// Execution metrics, shared by every clone of this instance.
+    pub fn metrics(&self) -> &RegexpMetrics {
+        &self.metrics
+    }
+}
+
+impl Configurable for Split {
+    fn with_config(&self, config: &RegexpConfig) -> Option<Self> {
+        let options = self.options.with_config(config);
+        (options != self.options).then(|| Self {
+            options,
+            ..self.clone()
+        })
+    }
+}
+
+impl ScalarUDFImpl for Split {
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn name(&self) -> &str {
+        NAME
+    }
+
+    fn signature(&self) -> &Signature {
+        &self.signature
+    }
+
+    fn return_type(&self, _arg_types: &[DataType]) -> Result<DataType> {
+        Ok(DataType::List(Arc::new(Field::new_list_field(
+            DataType::Utf8,
+            true,
+        ))))
+    }
+
+    fn invoke_with_args(&self, args: ScalarFunctionArgs) -> Result<ColumnarValue> {
+        let num_rows = args.number_rows;
+        let limit: Option<i64> = scalar_int64(NAME, "limit", args.args.get(2), -1)?;
+
+        let (input_array_ref, pattern_array_ref): (ArrayRef, ArrayRef) =
+            extract_input_and_pattern(&args.args[0], &args.args[1], num_rows)?;
+        let input_array: &StringArray = as_string_array(NAME, "str", &input_array_ref)?;
+        let pattern_array: &StringArray = as_string_array(NAME, "regex", &pattern_array_ref)?;
+
+        let mut list_builder = ListBuilder::new(StringBuilder::new());
+        let mut batch_metrics = BatchMetrics::start(NAME, num_rows, &self.metrics);
+        let mut patterns = BatchPatterns::new(NAME, &self.cache, self.options);
+
+        for i in 0..num_rows {
+            let Some(limit) = limit else {
+                list_builder.append_null();
+                continue;
+            };
+            if input_array.is_null(i) || pattern_array.is_null(i) {
+                list_builder.append_null();
+                continue;
+            }
+
+            let input_val: &str = input_array.value(i);
+            let pattern: &str = pattern_array.value(i);
+            // Java's own split treats 0 as "drop trailing empty strings",
+            // which Spark avoids by splitting with -1 instead.
+            let limit = usize::try_from(limit).ok().filter(|&limit| limit > 0);
+
+            let pieces = if pattern.is_empty() && !input_val.is_empty() {
+                batch_metrics.rows_processed += 1;
+                split_chars(input_val, limit)
+            } else {
+                let compiled_regex: Arc<Regex> = patterns.get(pattern, i, &mut batch_metrics)?;
+                batch_metrics.rows_processed += 1;
+                let text = self.options.collation.prepare_text(input_val);
+                java_split(&compiled_regex, &text, limit)
+            };
+            if pieces.len() > 1 {
+                batch_metrics.rows_matched += 1;
+            }
+            for piece in pieces {
+                list_builder.values().append_value(piece);
+            }
+            list_builder.append(true);
+        }
+
+        Ok(ColumnarValue::Array(Arc::new(list_builder.finish())))
+    }
+}
+
+/// Spark's special case for an empty pattern: one piece per character, the
+/// last piece taking whatever is left once `limit` is reached.
+pub fn split_chars(text: &str, limit: Option<usize>) -> Vec<&str> {
+    let num_chars = text.chars().count();
+    let pieces = limit
+        .filter(|&limit| limit <= num_chars)
+        .unwrap_or(num_chars);
+    let mut result: Vec<&str> = text
+        .char_indices()
+        .take(pieces - 1)
+        .map(|(offset, c)| &text[offset..offset + c.len_utf8()])
+        .collect();
+    let rest = result.iter().map(|piece| piece.len()).sum::<usize>();
+    result.push(&text[rest..]);
+    result
+}
+
+/// `java.util.regex.Pattern.split(input, limit)` for a positive `limit` or
+/// none, slicing the pieces from the original text.
+pub fn java_split<'a>(
+    regex: &Regex,
+    text: &CollatedText<'a>,
+    limit: Option<usize>,
+) -> Vec<&'a str> {
+    let haystack = text.as_str();
+    let mut result = Vec::new();
+    let mut index = 0;
+    for m in java_find_iter(regex, haystack) {
+        if limit.is_some_and(|limit| result.len() >= limit - 1) {
+            break;
+        }
+        // A zero-width match at the very start never yields a leading "".
+        if index == 0 && m.start() == 0 && m.is_empty() {
+            continue;
+        }
+        result.push(text.original(index..m.start()));
+        index = m.end();
+    }
+    if index == 0 {
+        return vec![text.original(0..haystack.len())];
+    }
+    result.push(text.original(index..haystack.len()));
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use arrow_array::ListArray;
+    use datafusion_common::ScalarValue;
+
+    fn run_test(input: Option<&str>, pattern: &str, limit: Option<i64>) -> Option<Vec<String>> {
+        let mut args = vec![
+            ColumnarValue::Array(Arc::new(StringArray::from(vec![input]))),
+            ColumnarValue::Scalar(ScalarValue::from(pattern)),
+        ];
+        if let Some(limit) = limit {
+            args.push(ColumnarValue::Scalar(ScalarValue::Int64(Some(limit))));
+        }
+        let args = ScalarFunctionArgs {
+            args,
+            number_rows: 1,
+            arg_fields: vec![],
+            return_field: Arc::new(Field::new(
+                "result",
+                Split::new().return_type(&[]).unwrap(),
+                true,
+            )),
+        };
+        let result = Split::new().invoke_with_args(args).unwrap();
+        let array = result.to_array(1).unwrap();
+        let list_array = array.as_any().downcast_ref::<ListArray>().unwrap();
+        if list_array.is_null(0) {
+            return None;
+        }
+        let values = list_array.value(0);
+        let strings = values.as_any().downcast_ref::<StringArray>().unwrap();
+        Some(strings.iter().map(|v| v.unwrap().to_string()).collect())
+    }
+
+    fn strings(values: &[&str]) -> Option<Vec<String>> {
+        Some(values.iter().map(|v| v.to_string()).collect())
+    }
+
+    #[test]
+    fn test_split_keeps_trailing_empty_strings() {
+        assert_eq!(
+            run_test(Some("a,b,,"), ",", None),
+            strings(&["a", "b", "", ""])
+        );
+        assert_eq!(
+            run_test(Some("a,b,,"), ",", Some(0)),
+            strings(&["a", "b", "", ""])
+        );
+        assert_eq!(run_test(Some(",a"), ",", Some(-5)), strings(&["", "a"]));
+    }
+
+    #[test]
+    fn test_split_positive_limit_caps_pieces() {
+        assert_eq!(
+            run_test(Some("oneAtwoBthreeC"), "[ABC]", Some(2)),
+            strings(&["one", "twoBthreeC"])
+        );
+        assert_eq!(run_test(Some("a,b,c"), ",", Some(1)), strings(&["a,b,c"]));
+        assert_eq!(run_test(Some("a,b"), ",", Some(10)), strings(&["a", "b"]));
+    }
+
+    #[test]
+    fn test_split_without_match_and_empty_input() {
+        assert_eq!(run_test(Some("abc"), ",", None), strings(&["abc"]));
+        assert_eq!(run_test(Some(""), ",", None), strings(&[""]));
+        assert_eq!(run_test(Some(""), "", None), strings(&[""]));
+        assert_eq!(run_test(None, ",", None), None);
+    }
+
+    #[test]
+    fn test_split_empty_pattern_splits_characters() {
+        assert_eq!(
+            run_test(Some("你好a"), "", None),
+            strings(&["你", "好", "a"])
+        );
+        assert_eq!(run_test(Some("abcd"), "", Some(2)), strings(&["a", "bcd"]));
+        assert_eq!(run_test(Some("ab"), "", Some(5)), strings(&["a", "b"]));
+    }
+
+    #[test]
+    fn test_split_zero_width_match_has_no_leading_empty_string() {
+        // Java's "abc".split("x*", -1) is [a, b, c, ""].
+        assert_eq!(
+            run_test(Some("abc"), "x*", None),
+            strings(&["a", "b", "c", ""])
+        );
+        assert_eq!(
+            run_test(Some("a1b"), r"\d*", None),
+            strings(&["a", "", "b", ""])
+        );
+    }
+}
@@ -0,0 +1,95 @@
+//! SQL syntax that DataFusion parses but does not plan.
+//!
+//! sqlparser understands Spark's `x [NOT] RLIKE p` and `x [NOT] REGEXP p`,
+//! but DataFusion 49's SQL planner rejects them as an unsupported AST node
+//! before it consults any `ExprPlanner`, and it has no hook for the parsed
+//! statement, so nothing [`crate::registry::register_all`] installs can make
+//! `SessionContext::sql` accept them. [`sql`] parses a query with the
+//! session's dialect, rewrites those operators into calls to `rlike` and
+//! plans the result, so the function has to be registered on the context
+//! first. Everything else, `LIKE` and `ILIKE` included, is planned exactly as
+//! `SessionContext::sql` plans it; Spark's LIKE semantics are available as
+//! the [`crate::like`] functions.
+
+use std::ops::ControlFlow;
+
+use datafusion::dataframe::DataFrame;
+use datafusion::execution::context::SessionContext;
+use datafusion::sql::parser::{CopyToSource, Statement};
+use datafusion::sql::sqlparser::ast::{
+    Expr, Function, FunctionArg, FunctionArgExpr, FunctionArgumentList, FunctionArguments, Ident,
+    ObjectName, ObjectNamePart, UnaryOperator, visit_expressions_mut,
+};
+use datafusion_common::Result;
+
+/// Runs `query` like `SessionContext::sql`, with `RLIKE` and `REGEXP`
+/// rewritten into `rlike` calls.
+pub async fn sql(ctx: &SessionContext, query: &str) -> Result<DataFrame> {
+    let state = ctx.state();
+    let dialect = state.config().options().sql_parser.dialect.clone();
+    let mut statement = state.sql_to_statement(query, &dialect)?;
+    rewrite_statement(&mut statement);
+    let plan = state.statement_to_plan(statement).await?;
+    ctx.execute_logical_plan(plan).await
+}
+
+/// Rewrites every `RLIKE` and `REGEXP` operator in `statement`, in place.
+pub fn rewrite_statement(statement: &mut Statement) {
+    match statement {
+        Statement::Statement(statement) => {
+            let _ = visit_expressions_mut(statement.as_mut(), rewrite_expr);
+        }
+        Statement::Explain(explain) => rewrite_statement(&mut explain.statement),
+        Statement::CopyTo(copy) => {
+            if let CopyToSource::Query(query) = &mut copy.source {
+                let _ = visit_expressions_mut(query.as_mut(), rewrite_expr);
+            }
+        }
+        Statement::CreateExternalTable(_) => {}
+    }
+}
+
+fn rewrite_expr(expr: &mut Expr) -> ControlFlow<()> {
+    if let Expr::RLike {
+        negated,
+        expr: input,
+        pattern,
+        ..
+    } = expr
+    {
+        let call = function_call("rlike", vec![*input.clone(), *pattern.clone()]);
+        *expr = negate_if(*negated, call);
+    }
+    ControlFlow::Continue(())
+}
+
+fn function_call(name: &str, args: Vec<Expr>) -> Expr {
+    Expr::Function(Function {
+        name: ObjectName(vec![ObjectNamePart::Identifier(Ident::new(name))]),
+        uses_odbc_syntax: false,
+        parameters: FunctionArguments::None,
+        args: FunctionArguments::List(FunctionArgumentList {
+            duplicate_treatment: None,
+            args: args
+                .into_iter()
+                .map(|arg| FunctionArg::Unnamed(FunctionArgExpr::Expr(arg)))
+                .collect(),
+            clauses: vec![],
+        }),
+        filter: None,
+        null_treatment: None,
+        over: None,
+        within_group: vec![],
+    })
+}
+
+fn negate_if(negated: bool, expr: Expr) -> Expr {
+    if negated {
+        Expr::UnaryOp {
+            op: UnaryOperator::Not,
+            expr: Box::new(expr),
+        }
+    } else {
+        expr
+    }
+}
@@ -0,0 +1,341 @@
+use std::any::Any;
+use std::collections::HashMap;
+use std::fmt;
+use std::str::FromStr;
+use std::sync::Arc;
+
+use arrow_array::builder::{MapBuilder, MapFieldNames, StringBuilder};
+use arrow_array::{Array, ArrayRef, StringArray};
+use datafusion_common::arrow::datatypes::{DataType, Field, Fields};
+use datafusion_common::{Result, ScalarValue};
+use datafusion_expr::{
+    ColumnarValue, ScalarFunctionArgs, ScalarUDFImpl, Signature, TypeSignature, Volatility,
+};
+use regex::Regex;
+
+use crate::args::{as_string_array, to_array};
+use crate::collation::Collation;
+use crate::config::{Configurable, RegexpConfig};
+use crate::error::RegexpError;
+use crate::metrics::{BatchMetrics, RegexpMetrics};
+use crate::pattern::{BatchPatterns, PatternCache, PatternOptions};
+use crate::split::{java_split, split_chars};
+
+const NAME: &str = "str_to_map";
+
+/// What to do when a key appears more than once in the same input, Spark's
+/// `spark.sql.mapKeyDedupPolicy`.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub enum MapKeyDedupPolicy {
+    /// Fail with `DUPLICATED_MAP_KEY`, Spark's default.
+    #[default]
+    Exception,
+    /// Keep the key where it first appeared, with the last value seen.
+    LastWin,
+}
+
+impl MapKeyDedupPolicy {
+    pub fn name(&self) -> &'static str {
+        match self {
+            MapKeyDedupPolicy::Exception => "EXCEPTION",
+            MapKeyDedupPolicy::LastWin => "LAST_WIN",
+        }
+    }
+}
+
+impl fmt::Display for MapKeyDedupPolicy {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+impl FromStr for MapKeyDedupPolicy {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_ascii_uppercase().as_str() {
+            "EXCEPTION" => Ok(MapKeyDedupPolicy::Exception),
+            "LAST_WIN" => Ok(MapKeyDedupPolicy::LastWin),
+            _ => Err(s.to_string()),
+        }
+    }
+}
+
+/// Spark's `str_to_map(text[, pairDelim[, keyValueDelim]])`: splits `text`
+/// into pairs on the `pairDelim` regex (default `,`) and each pair into a key
+/// and a value on the first match of the `keyValueDelim` regex (default
+/// `:`), returning a `Map<Utf8, Utf8>`.
+///
+/// A pair without `keyValueDelim` maps its key to NULL. Splitting follows
+/// [`Split`](crate::split::Split), so trailing empty pairs are kept. A NULL
+/// argument yields NULL.
+#[derive(Debug, Clone)]
+pub struct StrToMap {
+    signature: Signature,
+    dedup_policy: MapKeyDedupPolicy,
+    options: PatternOptions,
+    cache: Arc<PatternCache>,
+    metrics: Arc<RegexpMetrics>,
+}
+
+impl Default for StrToMap {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl StrToMap {
+    pub fn new() -> Self {
+        Self {
+            signature: Signature::one_of(
+                vec![
+                    TypeSignature::Exact(vec![DataType::Utf8]),
+                    TypeSignature::Exact(vec![DataType::Utf8, DataType::Utf8]),
+                    TypeSignature::Exact(vec![DataType::Utf8, DataType::Utf8, DataType::Utf8]),
+                ],
+                Volatility::Immutable,
+            ),
+            dedup_policy: MapKeyDedupPolicy::default(),
+            options: PatternOptions::default(),
+            cache: Arc::new(PatternCache::default()),
+            metrics: Arc::new(RegexpMetrics::new()),
+        }
+    }
+
+    /// How duplicate keys within one input are handled.
+    pub fn with_dedup_policy(mut self, dedup_policy: MapKeyDedupPolicy) -> Self {
+        self.dedup_policy = dedup_policy;
+        self
+    }
+
+    /// The collation delimiters are matched under.
+    pub fn with_collation(mut self, collation: Collation) -> Self {
+        self.options.collation = collation;
+        self
+    }
+
+    /// Execution metrics, shared by every clone of this instance.
+    pub fn metrics(&self) -> &RegexpMetrics {
+        &self.metrics
+    }
+
+    fn field_names() -> MapFieldNames {
+        MapFieldNames {
+            entry: "entries".to_string(),
+            key: "key".to_string(),
+            value: "value".to_string(),
+        }
+    }
+}
+
+impl Configurable for StrToMap {
+    fn with_config(&self, config: &RegexpConfig) -> Option<Self> {
+        let options = self.options.with_config(config);
+        (options != self.options).then(|| Self {
+            options,
+            ..self.clone()
+        })
+    }
+}
+
+impl ScalarUDFImpl for StrToMap {
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn name(&self) -> &str {
+        NAME
+    }
+
+    fn signature(&self) -> &Signature {
+        &self.signature
+    }
+
+    fn return_type(&self, _arg_types: &[DataType]) -> Result<DataType> {
+        let names = Self::field_names();
+        let entries = Fields::from(vec![
+            Field::new(names.key, DataType::Utf8, false),
+            Field::new(names.value, DataType::Utf8, true),
+        ]);
+        Ok(DataType::Map(
+            Arc::new(Field::new(names.entry, DataType::Struct(entries), false)),
+            false,
+        ))
+    }
+
+    fn invoke_with_args(&self, args: ScalarFunctionArgs) -> Result<ColumnarValue> {
+        let num_rows = args.number_rows;
+        let default_delim = |delim: &str| ColumnarValue::Scalar(ScalarValue::from(delim));
+        let text_array_ref: ArrayRef = to_array(&args.args[0], num_rows)?;
+        let pair_array_ref: ArrayRef =
+            to_array(args.args.get(1).unwrap_or(&default_delim(",")), num_rows)?;
+        let kv_array_ref: ArrayRef =
+            to_array(args.args.get(2).unwrap_or(&default_delim(":")), num_rows)?;
+        let text_array: &StringArray = as_string_array(NAME, "text", &text_array_ref)?;
+        let pair_array: &StringArray = as_string_array(NAME, "pairDelim", &pair_array_ref)?;
+        let kv_array: &StringArray = as_string_array(NAME, "keyValueDelim", &kv_array_ref)?;
+
+        let mut map_builder = MapBuilder::with_capacity(
+            Some(Self::field_names()),
+            StringBuilder::new(),
+            StringBuilder::new(),
+            num_rows,
+        );
+        let mut batch_metrics = BatchMetrics::start(NAME, num_rows, &self.metrics);
+        let collation = self.options.collation;
+        let mut pair_patterns = BatchPatterns::new(NAME, &self.cache, self.options);
+        let mut kv_patterns = BatchPatterns::new(NAME, &self.cache, self.options);
+
+        for i in 0..num_rows {
+            if text_array.is_null(i) || pair_array.is_null(i) || kv_array.is_null(i) {
+                map_builder.append(false)?;
+                continue;
+            }
+
+            let text_val: &str = text_array.value(i);
+            let pair_delim: &str = pair_array.value(i);
+            let kv_delim: &str = kv_array.value(i);
+
+            let pair_regex: Arc<Regex> = pair_patterns.get(pair_delim, i, &mut batch_metrics)?;
+            let kv_regex: Arc<Regex> = kv_patterns.get(kv_delim, i, &mut batch_metrics)?;
+            batch_metrics.rows_processed += 1;
+
+            let mut entries: Vec<(&str, Option<&str>)> = Vec::new();
+            let mut positions: HashMap<&str, usize> = HashMap::new();
+            for pair in spark_split(&pair_regex, pair_delim, text_val, None, collation) {
+                let mut kv = spark_split(&kv_regex, kv_delim, pair, Some(2), collation);
+                let value = (kv.len() == 2).then(|| kv.pop().unwrap());
+                let key = kv[0];
+                match positions.get(key) {
+                    None => {
+                        positions.insert(key, entries.len());
+                        entries.push((key, value));
+                    }
+                    Some(&position) => match self.dedup_policy {
+                        MapKeyDedupPolicy::LastWin => entries[position].1 = value,
+                        MapKeyDedupPolicy::Exception => {
+                            return Err(RegexpError::DuplicatedMapKey {
+                                function: NAME,
+                                key: key.to_string(),
+                                row: i,
+                            }
+                            .into());
+                        }
+                    },
+                }
+            }
+
+            if entries.iter().any(|(_, value)| value.is_some()) {
+                batch_metrics.rows_matched += 1;
+            }
+            for (key, value) in entries {
+                map_builder.keys().append_value(key);
+                map_builder.values().append_option(value);
+            }
+            map_builder.append(true)?;
+        }
+
+        Ok(ColumnarValue::Array(Arc::new(map_builder.finish())))
+    }
+}
+
+/// Spark's string split, including its empty-delimiter special case.
+fn spark_split<'a>(
+    regex: &Regex,
+    delim: &str,
+    text: &'a str,
+    limit: Option<usize>,
+    collation: Collation,
+) -> Vec<&'a str> {
+    if delim.is_empty() && !text.is_empty() {
+        split_chars(text, limit)
+    } else {
+        java_split(regex, &collation.prepare_text(text), limit)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use arrow_array::MapArray;
+    use arrow_array::cast::AsArray;
+
+    type Entries = Option<Vec<(String, Option<String>)>>;
+
+    fn run_test(udf: StrToMap, text: Option<&str>, delims: &[&str]) -> Result<Entries> {
+        let mut args = vec![ColumnarValue::Array(Arc::new(StringArray::from(vec![
+            text,
+        ])))];
+        args.extend(
+            delims
+                .iter()
+                .map(|delim| ColumnarValue::Scalar(ScalarValue::from(*delim))),
+        );
+        let args = ScalarFunctionArgs {
+            args,
+            number_rows: 1,
+            arg_fields: vec![],
+            return_field: Arc::new(Field::new("result", udf.return_type(&[])?, true)),
+        };
+        let array = udf.invoke_with_args(args)?.to_array(1)?;
+        let map = array.as_any().downcast_ref::<MapArray>().unwrap();
+        if map.is_null(0) {
+            return Ok(None);
+        }
+        let entries = map.value(0);
+        let keys = entries.column(0).as_string::<i32>();
+        let values = entries.column(1).as_string::<i32>();
+        Ok(Some(
+            keys.iter()
+                .zip(values.iter())
+                .map(|(k, v)| (k.unwrap().to_string(), v.map(str::to_string)))
+                .collect(),
+        ))
+    }
+
+    fn entries(pairs: &[(&str, Option<&str>)]) -> Entries {
+        Some(
+            pairs
+                .iter()
+                .map(|(k, v)| (k.to_string(), v.map(str::to_string)))
+                .collect(),
+        )
+    }
+
+    #[test]
+    fn test_default_delimiters() {
+        let result = run_test(StrToMap::new(), Some("a:1,b:2,c"), &[]).unwrap();
+        assert_eq!(
+            result,
+            entries(&[("a", Some("1")), ("b", Some("2")), ("c", None)])
+        );
+    }
+
+    #[test]
+    fn test_regex_delimiters() {
+        let result = run_test(
+            StrToMap::new(),
+            Some("k1 = v1 ;k2=v=2"),
+            &[r"\s*;\s*", r"\s*=\s*"],
+        )
+        .unwrap();
+        assert_eq!(result, entries(&[("k1", Some("v1")), ("k2", Some("v=2"))]));
+    }
+
+    #[test]
+    fn test_null_argument_yields_null() {
+        assert_eq!(run_test(StrToMap::new(), None, &[]).unwrap(), None);
+    }
+
+    #[test]
+    fn test_duplicate_key_policies() {
+        let err = run_test(StrToMap::new(), Some("a:1,a:2"), &[]).unwrap_err();
+        let err = RegexpError::find(&err).unwrap();
+        assert_eq!(err.error_class(), "DUPLICATED_MAP_KEY");
+        assert_eq!(err.sql_state(), "23505");
+
+        let last_win = StrToMap::new().with_dedup_policy(MapKeyDedupPolicy::LastWin);
+        let result = run_test(last_win, Some("a:1,b:2,a:3"), &[]).unwrap();
+        assert_eq!(result, entries(&[("a", Some("3")), ("b", Some("2"))]));
+    }
+}
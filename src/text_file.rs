@@ -0,0 +1,839 @@
+//! Line-oriented text files as a DataFusion file source.
+//!
+//! [`TextSource`] reads plain or compressed text files a line at a time and
+//! hands batches of lines to a [`LineFormat`], which turns them into columns.
+//! Files are decompressed and split into lines as they arrive from the
+//! object store, so only the lines of the batch being built are held.
+//! Uncompressed files are split on line boundaries so that one large file is
+//! read in parallel. The file formats in this crate, such as
+//! [`RegexFormat`](crate::regex_format::RegexFormat), are built on it and
+//! share the [`TextOptions`] for malformed lines, compression and file
+//! extensions.
+//!
+//! A record is a line unless [`TextOptions::record_start`] is set, in which
+//! case the lines up to the next record start are joined with `\n` into one
+//! record, so that a stack trace stays with the log line that reported it.
+//! Such a record belongs to the split its first line is in and is read past
+//! the end of that split, fetched in growing chunks until the next record
+//! start, while the next split skips the lines it begins with that do not
+//! start a record. A record keeps at most
+//! [`TextOptions::max_record_size`] bytes of its file; the lines past that
+//! are dropped.
+
+use std::any::Any;
+use std::fmt;
+use std::ops::Range;
+use std::str::FromStr;
+use std::sync::Arc;
+
+use bytes::Bytes;
+use datafusion::arrow::datatypes::{DataType, Field, Schema, SchemaRef};
+use datafusion::arrow::record_batch::RecordBatch;
+use datafusion::datasource::listing::PartitionedFile;
+use datafusion::datasource::physical_plan::{
+    FileMeta, FileOpenFuture, FileOpener, FileScanConfig, FileScanConfigBuilder, FileSource,
+};
+use datafusion::datasource::schema_adapter::SchemaAdapterFactory;
+use datafusion::datasource::source::DataSourceExec;
+use datafusion::physical_plan::ExecutionPlan;
+use datafusion::physical_plan::metrics::ExecutionPlanMetricsSet;
+use datafusion_common::{GetExt, Result, Statistics, config_datafusion_err, config_err};
+use datafusion_datasource::file_compression_type::FileCompressionType;
+use datafusion_datasource::{RangeCalculation, calculate_range};
+use futures::stream::BoxStream;
+use futures::{StreamExt, TryStreamExt};
+use object_store::path::Path;
+use object_store::{GetOptions, ObjectStore};
+use regex::Regex;
+
+use crate::error::RegexpError;
+use crate::pattern::PatternOptions;
+
+const DEFAULT_FILE_EXTENSION: &str = "log";
+const DEFAULT_MAX_RECORD_SIZE: usize = 10 * 1024 * 1024;
+const PAST_END_CHUNK_SIZE: u64 = 64 * 1024;
+
+/// The column that holds the lines that could not be parsed, under
+/// [`ParseMode::CorruptRecord`].
+pub const CORRUPT_RECORD_COLUMN: &str = "_corrupt_record";
+
+/// What a text file format does with a line that does not fit it.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub enum ParseMode {
+    /// Every column of the row is NULL, as in Hive.
+    #[default]
+    Permissive,
+    /// As `Permissive`, but the line is kept in a
+    /// [`CORRUPT_RECORD_COLUMN`], which is added to the inferred schema.
+    /// The column is NULL for lines that parse.
+    CorruptRecord,
+    /// The line is skipped.
+    DropMalformed,
+    /// The query fails with [`RegexpError::MalformedRecord`].
+    FailFast,
+}
+
+impl ParseMode {
+    pub fn name(&self) -> &'static str {
+        match self {
+            ParseMode::Permissive => "PERMISSIVE",
+            ParseMode::CorruptRecord => "CORRUPT_RECORD",
+            ParseMode::DropMalformed => "DROPMALFORMED",
+            ParseMode::FailFast => "FAILFAST",
+        }
+    }
+
+    /// Whether the malformed `line` still gives a row, failing under
+    /// [`ParseMode::FailFast`].
+    pub fn keep_malformed(&self, format: &'static str, line: &str) -> Result<bool> {
+        match self {
+            ParseMode::Permissive | ParseMode::CorruptRecord => Ok(true),
+            ParseMode::DropMalformed => Ok(false),
+            ParseMode::FailFast => Err(RegexpError::MalformedRecord {
+                format,
+                record: line.to_string(),
+            }
+            .into()),
+        }
+    }
+}
+
+impl fmt::Display for ParseMode {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+impl FromStr for ParseMode {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_ascii_uppercase().as_str() {
+            "PERMISSIVE" => Ok(ParseMode::Permissive),
+            "CORRUPT_RECORD" => Ok(ParseMode::CorruptRecord),
+            "DROPMALFORMED" => Ok(ParseMode::DropMalformed),
+            "FAILFAST" => Ok(ParseMode::FailFast),
+            _ => Err(s.to_string()),
+        }
+    }
+}
+
+/// Which lines begin a record that can span several lines. The pattern is
+/// in Java syntax and found anywhere in the line, so it is usually anchored
+/// with `^`.
+#[derive(Debug, Clone)]
+pub enum RecordStart {
+    /// A record begins at every line the pattern matches, set with the
+    /// `start_pattern` table option.
+    Pattern(Regex),
+    /// A record begins at every line the pattern does not match, set with
+    /// the `continuation_pattern` table option.
+    ContinuationPattern(Regex),
+}
+
+impl RecordStart {
+    pub fn pattern(pattern: &str) -> Result<Self> {
+        Ok(RecordStart::Pattern(compile("start_pattern", pattern)?))
+    }
+
+    pub fn continuation_pattern(pattern: &str) -> Result<Self> {
+        Ok(RecordStart::ContinuationPattern(compile(
+            "continuation_pattern",
+            pattern,
+        )?))
+    }
+
+    fn starts_record(&self, line: &str) -> bool {
+        match self {
+            RecordStart::Pattern(regex) => regex.is_match(line),
+            RecordStart::ContinuationPattern(regex) => !regex.is_match(line),
+        }
+    }
+}
+
+fn compile(function: &'static str, pattern: &str) -> Result<Regex> {
+    let invalid = |message: String| RegexpError::InvalidPattern {
+        function,
+        pattern: pattern.to_string(),
+        row: 0,
+        message,
+    };
+    // Record start patterns are only tested against each line.
+    let options = PatternOptions {
+        match_only: true,
+        ..Default::default()
+    };
+    let translated = options.translate(pattern).map_err(invalid)?;
+    Ok(Regex::new(&translated.pattern).map_err(|e| invalid(e.to_string()))?)
+}
+
+/// Settings every text file format has, set with the `mode`, `compression`,
+/// `file_extension`, `start_pattern`, `continuation_pattern` and
+/// `max_record_size` table options.
+#[derive(Debug, Clone)]
+pub struct TextOptions {
+    pub mode: ParseMode,
+    pub compression: FileCompressionType,
+    /// The extension of the files read from a directory when no columns are
+    /// declared, `log` by default.
+    pub file_extension: String,
+    /// Where records begin, when they can span lines. `None` makes every
+    /// line a record.
+    pub record_start: Option<RecordStart>,
+    /// The most bytes of the file, line terminators included, that a
+    /// multiline record keeps, 10 MiB by default.
+    pub max_record_size: usize,
+}
+
+impl Default for TextOptions {
+    fn default() -> Self {
+        Self {
+            mode: ParseMode::default(),
+            compression: FileCompressionType::UNCOMPRESSED,
+            file_extension: DEFAULT_FILE_EXTENSION.to_string(),
+            record_start: None,
+            max_record_size: DEFAULT_MAX_RECORD_SIZE,
+        }
+    }
+}
+
+impl TextOptions {
+    /// Applies the `CREATE EXTERNAL TABLE` option `key`, returning `false`
+    /// when it is not one of these.
+    pub fn set(&mut self, key: &str, value: &str) -> Result<bool> {
+        match key {
+            "format.mode" => {
+                self.mode = value.parse().map_err(|value| {
+                    config_datafusion_err!(
+                        "Unknown mode '{value}', expected PERMISSIVE, CORRUPT_RECORD, \
+                         DROPMALFORMED or FAILFAST"
+                    )
+                })?;
+            }
+            "format.compression" => self.compression = value.parse()?,
+            "format.file_extension" => self.file_extension = value.to_string(),
+            "format.start_pattern" | "format.continuation_pattern" => {
+                if self.record_start.is_some() {
+                    return config_err!(
+                        "Only one of 'start_pattern' and 'continuation_pattern' can be set"
+                    );
+                }
+                self.record_start = Some(if key == "format.start_pattern" {
+                    RecordStart::pattern(value)?
+                } else {
+                    RecordStart::continuation_pattern(value)?
+                });
+            }
+            "format.max_record_size" => {
+                self.max_record_size =
+                    value.parse().ok().filter(|&size| size > 0).ok_or_else(|| {
+                        config_datafusion_err!(
+                            "Invalid 'max_record_size' value '{value}', expected a positive \
+                             number of bytes"
+                        )
+                    })?;
+            }
+            _ => return Ok(false),
+        }
+        Ok(true)
+    }
+
+    /// The file extension for [`FileFormat::get_ext_with_compression`].
+    ///
+    /// [`FileFormat::get_ext_with_compression`]: datafusion::datasource::file_format::FileFormat::get_ext_with_compression
+    pub fn ext_with_compression(&self, compression: &FileCompressionType) -> String {
+        format!("{}{}", self.file_extension, compression.get_ext())
+    }
+
+    /// A schema of `fields`, followed by [`CORRUPT_RECORD_COLUMN`] under
+    /// [`ParseMode::CorruptRecord`].
+    pub fn schema(&self, mut fields: Vec<Field>) -> SchemaRef {
+        if self.mode == ParseMode::CorruptRecord {
+            fields.push(Field::new(CORRUPT_RECORD_COLUMN, DataType::Utf8, true));
+        }
+        Arc::new(Schema::new(fields))
+    }
+
+    /// Whether `field` is the column for lines that could not be parsed.
+    pub fn is_corrupt_record(&self, field: &Field) -> bool {
+        self.mode == ParseMode::CorruptRecord && field.name() == CORRUPT_RECORD_COLUMN
+    }
+}
+
+/// How a text file format turns lines into columns.
+pub trait LineFormat: fmt::Debug + Send + Sync {
+    /// The name shown in plans, such as `regex`.
+    fn file_type(&self) -> &str;
+
+    /// How the files are read.
+    fn options(&self) -> &TextOptions;
+
+    /// Prepares to decode the columns of `projection`, which are a subset of
+    /// those of `file_schema`.
+    fn decoder(
+        &self,
+        file_schema: &SchemaRef,
+        projection: SchemaRef,
+    ) -> Result<Box<dyn LineDecoder>>;
+}
+
+/// Decodes the records of one file, a batch at a time.
+pub trait LineDecoder: Send {
+    /// Builds a batch from `lines`, the records of the file with their line
+    /// terminators removed. A multiline record has its lines joined with
+    /// `\n`. Records may be left out of the batch, but never added.
+    fn decode(&mut self, lines: &[String]) -> Result<RecordBatch>;
+}
+
+/// The scan of `conf` with `format`, for [`FileFormat::create_physical_plan`].
+///
+/// [`FileFormat::create_physical_plan`]: datafusion::datasource::file_format::FileFormat::create_physical_plan
+pub fn scan_plan(conf: FileScanConfig, format: Arc<dyn LineFormat>) -> Arc<dyn ExecutionPlan> {
+    let conf = FileScanConfigBuilder::from(conf)
+        .with_file_compression_type(format.options().compression)
+        .with_source(Arc::new(TextSource::new(format)))
+        .build();
+    DataSourceExec::from_data_source(conf)
+}
+
+/// A [`FileSource`] for the files of a [`LineFormat`].
+#[derive(Clone)]
+pub struct TextSource {
+    format: Arc<dyn LineFormat>,
+    batch_size: Option<usize>,
+    metrics: ExecutionPlanMetricsSet,
+    projected_statistics: Option<Statistics>,
+    schema_adapter_factory: Option<Arc<dyn SchemaAdapterFactory>>,
+}
+
+impl TextSource {
+    pub fn new(format: Arc<dyn LineFormat>) -> Self {
+        Self {
+            format,
+            batch_size: None,
+            metrics: ExecutionPlanMetricsSet::new(),
+            projected_statistics: None,
+            schema_adapter_factory: None,
+        }
+    }
+}
+
+impl FileSource for TextSource {
+    fn create_file_opener(
+        &self,
+        object_store: Arc<dyn ObjectStore>,
+        base_config: &FileScanConfig,
+        _partition: usize,
+    ) -> Arc<dyn FileOpener> {
+        Arc::new(TextOpener {
+            format: Arc::clone(&self.format),
+            batch_size: self
+                .batch_size
+                .expect("Batch size must be set before creating an opener"),
+            file_schema: Arc::clone(&base_config.file_schema),
+            projected_schema: base_config.projected_file_schema(),
+            file_compression_type: base_config.file_compression_type,
+            object_store,
+        })
+    }
+
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn with_batch_size(&self, batch_size: usize) -> Arc<dyn FileSource> {
+        let mut source = self.clone();
+        source.batch_size = Some(batch_size);
+        Arc::new(source)
+    }
+
+    fn with_schema(&self, _schema: SchemaRef) -> Arc<dyn FileSource> {
+        Arc::new(self.clone())
+    }
+
+    fn with_projection(&self, _config: &FileScanConfig) -> Arc<dyn FileSource> {
+        Arc::new(self.clone())
+    }
+
+    fn with_statistics(&self, statistics: Statistics) -> Arc<dyn FileSource> {
+        let mut source = self.clone();
+        source.projected_statistics = Some(statistics);
+        Arc::new(source)
+    }
+
+    fn metrics(&self) -> &ExecutionPlanMetricsSet {
+        &self.metrics
+    }
+
+    fn statistics(&self) -> Result<Statistics> {
+        Ok(self
+            .projected_statistics
+            .clone()
+            .expect("projected_statistics must be set before calling statistics"))
+    }
+
+    fn file_type(&self) -> &str {
+        self.format.file_type()
+    }
+
+    fn with_schema_adapter_factory(
+        &self,
+        schema_adapter_factory: Arc<dyn SchemaAdapterFactory>,
+    ) -> Result<Arc<dyn FileSource>> {
+        Ok(Arc::new(Self {
+            schema_adapter_factory: Some(schema_adapter_factory),
+            ..self.clone()
+        }))
+    }
+
+    fn schema_adapter_factory(&self) -> Option<Arc<dyn SchemaAdapterFactory>> {
+        self.schema_adapter_factory.clone()
+    }
+}
+
+struct TextOpener {
+    format: Arc<dyn LineFormat>,
+    batch_size: usize,
+    file_schema: SchemaRef,
+    projected_schema: SchemaRef,
+    file_compression_type: FileCompressionType,
+    object_store: Arc<dyn ObjectStore>,
+}
+
+impl FileOpener for TextOpener {
+    fn open(&self, file_meta: FileMeta, _file: PartitionedFile) -> Result<FileOpenFuture> {
+        let store = Arc::clone(&self.object_store);
+        let batch_size = self.batch_size;
+        let compression = self.file_compression_type;
+        let options = self.format.options();
+        let record_start = options.record_start.clone();
+        let max_record_size = options.max_record_size as u64;
+        let decoder = self
+            .format
+            .decoder(&self.file_schema, Arc::clone(&self.projected_schema))?;
+
+        Ok(Box::pin(async move {
+            // The range is moved to line boundaries, so that every line is
+            // read by exactly one partition.
+            let range = match calculate_range(&file_meta, &store, None).await? {
+                RangeCalculation::Range(range) => range,
+                RangeCalculation::TerminateEarly => return Ok(futures::stream::empty().boxed()),
+            };
+            // A multiline record that begins in the range is read to its
+            // end, which is at most `max_record_size` bytes past the range.
+            let past_end = match (&range, &record_start) {
+                (Some(range), Some(_)) => Some(PastEnd {
+                    store: Arc::clone(&store),
+                    location: file_meta.location().clone(),
+                    next: range.end,
+                    end: range
+                        .end
+                        .saturating_add(max_record_size)
+                        .min(file_meta.object_meta.size),
+                    chunk_size: PAST_END_CHUNK_SIZE,
+                }),
+                _ => None,
+            };
+            let options = GetOptions {
+                range: range.clone().map(Into::into),
+                ..Default::default()
+            };
+            let result = store.get_opts(file_meta.location(), options).await?;
+            // The file is decoded as it arrives, a chunk at a time.
+            let chunks =
+                compression.convert_stream(result.into_stream().map_err(Into::into).boxed())?;
+
+            let scan = Scan {
+                input: Input { chunks, past_end },
+                records: Records {
+                    lines: Lines::default(),
+                    end: range
+                        .as_ref()
+                        .map_or(u64::MAX, |range| range.end - range.start),
+                    skip_continuations: range.is_some_and(|range| range.start > 0),
+                    record_start,
+                    max_record_size,
+                    record: None,
+                    done: false,
+                },
+                decoder,
+                batch_size,
+            };
+            Ok(futures::stream::try_unfold(scan, Scan::next_batch)
+                .map(|batch| batch.map_err(Into::into))
+                .boxed())
+        }))
+    }
+}
+
+/// Splits bytes, as they are read, into lines without their `\n` or `\r\n`
+/// terminators. Bytes that are not UTF-8 are replaced with U+FFFD.
+#[derive(Default)]
+struct Lines {
+    buffer: Vec<u8>,
+    /// Where the unread lines start in `buffer`.
+    start: usize,
+    /// How much of `buffer` after `start` is known to hold no `\n`.
+    searched: usize,
+    /// The bytes of the lines read so far.
+    position: u64,
+    finished: bool,
+}
+
+impl Lines {
+    fn push(&mut self, bytes: &[u8]) {
+        if self.start > 0 && self.start >= self.buffer.len() / 2 {
+            self.buffer.drain(..self.start);
+            self.start = 0;
+        }
+        self.buffer.extend_from_slice(bytes);
+    }
+
+    /// Marks the end of the input, so that a last line without a terminator
+    /// is read.
+    fn finish(&mut self) {
+        self.finished = true;
+    }
+
+    /// The next line with the bytes of the input it was read from, or `None`
+    /// when it is not all there yet or the input has ended.
+    fn next_line(&mut self) -> Option<(Range<u64>, String)> {
+        let unread = &self.buffer[self.start..];
+        let len = match unread[self.searched..].iter().position(|&b| b == b'\n') {
+            Some(newline) => self.searched + newline + 1,
+            None if self.finished && !unread.is_empty() => unread.len(),
+            None => {
+                self.searched = unread.len();
+                return None;
+            }
+        };
+        let mut line = &unread[..len];
+        if let Some(rest) = line.strip_suffix(b"\n") {
+            line = rest.strip_suffix(b"\r").unwrap_or(rest);
+        }
+        let line = String::from_utf8_lossy(line).into_owned();
+        let range = self.position..self.position + len as u64;
+        self.start += len;
+        self.searched = 0;
+        self.position = range.end;
+        Some((range, line))
+    }
+}
+
+/// What [`Records::next_record`] found.
+enum Next {
+    Record(String),
+    NeedInput,
+    Done,
+}
+
+/// The records of a split: its lines, or with a [`RecordStart`] the lines
+/// joined into multiline records.
+struct Records {
+    lines: Lines,
+    /// Where the split ends, in bytes from where reading started. Only
+    /// records that begin before it are read.
+    end: u64,
+    /// Whether the lines before the first record start are left to the
+    /// split before, which reads them past its end.
+    skip_continuations: bool,
+    record_start: Option<RecordStart>,
+    max_record_size: u64,
+    /// The multiline record being assembled, with its size in the file.
+    record: Option<(String, u64)>,
+    done: bool,
+}
+
+impl Records {
+    fn next_record(&mut self) -> Next {
+        while !self.done {
+            let Some((range, line)) = self.lines.next_line() else {
+                if !self.lines.finished {
+                    return Next::NeedInput;
+                }
+                self.done = true;
+                break;
+            };
+            let Some(record_start) = &self.record_start else {
+                if range.start >= self.end {
+                    self.done = true;
+                    break;
+                }
+                return Next::Record(line);
+            };
+            let starts_record = record_start.starts_record(&line);
+            match &mut self.record {
+                Some((record, size)) if !starts_record => {
+                    *size += range.end - range.start;
+                    if *size <= self.max_record_size {
+                        record.push('\n');
+                        record.push_str(&line);
+                    }
+                }
+                // The previous split reads the continuation lines at the
+                // start of this one.
+                None if self.skip_continuations && !starts_record => {}
+                _ => {
+                    self.skip_continuations = false;
+                    let next = (range.start < self.end).then(|| (line, range.end - range.start));
+                    self.done = next.is_none();
+                    if let Some((record, _)) = std::mem::replace(&mut self.record, next) {
+                        return Next::Record(record);
+                    }
+                }
+            }
+        }
+        match self.record.take() {
+            Some((record, _)) => Next::Record(record),
+            None => Next::Done,
+        }
+    }
+}
+
+/// The bytes of a split, followed for a multiline split by those past its
+/// end, which are only fetched while its last record is still being read.
+struct Input {
+    chunks: BoxStream<'static, Result<Bytes>>,
+    past_end: Option<PastEnd>,
+}
+
+struct PastEnd {
+    store: Arc<dyn ObjectStore>,
+    location: Path,
+    next: u64,
+    end: u64,
+    /// The size of the next fetch, doubled after each one.
+    chunk_size: u64,
+}
+
+impl Input {
+    async fn next(&mut self) -> Option<Result<Bytes>> {
+        if let Some(bytes) = self.chunks.next().await {
+            return Some(bytes);
+        }
+        let past_end = self.past_end.as_mut()?;
+        if past_end.next >= past_end.end {
+            return None;
+        }
+        let range = past_end.next..past_end.end.min(past_end.next + past_end.chunk_size);
+        past_end.next = range.end;
+        past_end.chunk_size = past_end.chunk_size.saturating_mul(2);
+        let bytes = past_end.store.get_range(&past_end.location, range).await;
+        Some(bytes.map_err(Into::into))
+    }
+}
+
+/// The state of the scan of one file.
+struct Scan {
+    input: Input,
+    records: Records,
+    decoder: Box<dyn LineDecoder>,
+    batch_size: usize,
+}
+
+impl Scan {
+    async fn next_batch(mut self) -> Result<Option<(RecordBatch, Self)>> {
+        let mut lines = Vec::with_capacity(self.batch_size);
+        while lines.len() < self.batch_size {
+            match self.records.next_record() {
+                Next::Record(record) => lines.push(record),
+                Next::NeedInput => match self.input.next().await {
+                    Some(bytes) => self.records.lines.push(&bytes?),
+                    None => self.records.lines.finish(),
+                },
+                Next::Done => break,
+            }
+        }
+        if lines.is_empty() {
+            return Ok(None);
+        }
+        let batch = self.decoder.decode(&lines)?;
+        Ok(Some((batch, self)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const LOG: &str = "2024-01-01 ERROR boom\n\
+                       java.lang.IllegalStateException: boom\n\
+                       \tat a.B.c(B.java:10)\r\n\
+                       2024-01-01 INFO ok\n\
+                       2024-01-02 ERROR again\n\
+                       \tat a.B.d(B.java:20)\n";
+
+    /// The records of `data[range]`, read the way a split is: up to
+    /// `max_record_size` bytes past its end, a few bytes at a time.
+    fn records(
+        data: &str,
+        range: Option<Range<u64>>,
+        record_start: Option<RecordStart>,
+        max_record_size: u64,
+    ) -> Vec<String> {
+        let start = range.as_ref().map_or(0, |range| range.start as usize);
+        let end = range
+            .as_ref()
+            .map_or(data.len(), |range| {
+                range.end as usize + max_record_size as usize
+            })
+            .min(data.len());
+        let mut chunks = data.as_bytes()[start..end].chunks(3);
+        let mut records = Records {
+            lines: Lines::default(),
+            end: range
+                .as_ref()
+                .map_or(u64::MAX, |range| range.end - range.start),
+            skip_continuations: range.is_some_and(|range| range.start > 0),
+            record_start,
+            max_record_size,
+            record: None,
+            done: false,
+        };
+        let mut read = Vec::new();
+        loop {
+            match records.next_record() {
+                Next::Record(record) => read.push(record),
+                Next::NeedInput => match chunks.next() {
+                    Some(chunk) => records.lines.push(chunk),
+                    None => records.lines.finish(),
+                },
+                Next::Done => return read,
+            }
+        }
+    }
+
+    fn start() -> Option<RecordStart> {
+        Some(RecordStart::pattern(r"^\d{4}-\d{2}-\d{2} ").unwrap())
+    }
+
+    #[test]
+    fn test_lines_without_record_start() {
+        let lines = records("a\nb\r\n\nc", None, None, 0);
+        assert_eq!(lines, ["a", "b", "", "c"]);
+        // Characters split between reads are put back together.
+        let lines = records("你好\n世界\n", None, None, 0);
+        assert_eq!(lines, ["你好", "世界"]);
+    }
+
+    #[test]
+    fn test_multiline_records() {
+        let expected = [
+            "2024-01-01 ERROR boom\n\
+             java.lang.IllegalStateException: boom\n\
+             \tat a.B.c(B.java:10)",
+            "2024-01-01 INFO ok",
+            "2024-01-02 ERROR again\n\tat a.B.d(B.java:20)",
+        ];
+        assert_eq!(records(LOG, None, start(), 1024), expected);
+
+        let continuation = RecordStart::continuation_pattern(r"^(\s|java\.)").unwrap();
+        assert_eq!(records(LOG, None, Some(continuation), 1024), expected);
+
+        // Lines before the first record start are a record of their own.
+        let lines = records("\tat x\n\tat y\n2024-01-01 z\n", None, start(), 1024);
+        assert_eq!(lines, ["\tat x\n\tat y", "2024-01-01 z"]);
+    }
+
+    #[test]
+    fn test_splits_read_every_record_once() {
+        let whole = records(LOG, None, start(), 1024);
+        // Splits on line boundaries, as `calculate_range` leaves them.
+        let boundaries: Vec<u64> = std::iter::once(0)
+            .chain(LOG.match_indices('\n').map(|(i, _)| i as u64 + 1))
+            .collect();
+        for &middle in &boundaries {
+            let mut split = records(LOG, Some(0..middle), start(), 1024);
+            split.extend(records(LOG, Some(middle..LOG.len() as u64), start(), 1024));
+            assert_eq!(split, whole, "split at {middle}");
+        }
+    }
+
+    #[test]
+    fn test_max_record_size() {
+        let data = "2024-01-01 a\nbb\ncc\n2024-01-02 d\n";
+        // The first line is always kept; "bb\n" still fits, "cc\n" does not.
+        let lines = records(data, None, start(), 16);
+        assert_eq!(lines, ["2024-01-01 a\nbb", "2024-01-02 d"]);
+        // A split never reads more than the limit past its end.
+        let lines = records(data, Some(0..13), start(), 16);
+        assert_eq!(lines, ["2024-01-01 a\nbb"]);
+        let lines = records(data, Some(13..data.len() as u64), start(), 16);
+        assert_eq!(lines, ["2024-01-02 d"]);
+    }
+
+    #[tokio::test]
+    async fn test_reads_past_the_end_only_to_the_next_record() {
+        let data = "2024-01-01 a\n\tat b\n".repeat(10_000);
+        let store = Arc::new(object_store::memory::InMemory::new());
+        let location = Path::from("app.log");
+        store.put(&location, data.clone().into()).await.unwrap();
+
+        // The split holds only the first line.
+        let split_end = 13;
+        let mut input = Input {
+            chunks: futures::stream::iter([Ok(Bytes::copy_from_slice(&data.as_bytes()[..13]))])
+                .boxed(),
+            past_end: Some(PastEnd {
+                store,
+                location,
+                next: split_end,
+                end: split_end + DEFAULT_MAX_RECORD_SIZE as u64,
+                chunk_size: PAST_END_CHUNK_SIZE,
+            }),
+        };
+        let mut records = Records {
+            lines: Lines::default(),
+            end: split_end,
+            skip_continuations: false,
+            record_start: start(),
+            max_record_size: DEFAULT_MAX_RECORD_SIZE as u64,
+            record: None,
+            done: false,
+        };
+        let mut read = Vec::new();
+        loop {
+            match records.next_record() {
+                Next::Record(record) => read.push(record),
+                Next::NeedInput => match input.next().await {
+                    Some(bytes) => records.lines.push(&bytes.unwrap()),
+                    None => records.lines.finish(),
+                },
+                Next::Done => break,
+            }
+        }
+        assert_eq!(read, ["2024-01-01 a\n\tat b"]);
+        assert_eq!(
+            input.past_end.unwrap().next,
+            split_end + PAST_END_CHUNK_SIZE
+        );
+    }
+
+    #[test]
+    fn test_record_start_options() {
+        let mut options = TextOptions::default();
+        assert!(options.set("format.start_pattern", r"^\S").unwrap());
+        let err = options
+            .set("format.continuation_pattern", r"^\s")
+            .unwrap_err();
+        assert!(err.to_string().contains("Only one of"), "{err}");
+
+        assert!(options.set("format.max_record_size", "4096").unwrap());
+        assert_eq!(options.max_record_size, 4096);
+        for value in ["0", "-1", "10MB"] {
+            assert!(options.set("format.max_record_size", value).is_err());
+        }
+
+        let err = TextOptions::default()
+            .set("format.start_pattern", "(")
+            .unwrap_err();
+        let err = RegexpError::find(&err).unwrap();
+        assert!(matches!(
+            err,
+            RegexpError::InvalidPattern {
+                function: "start_pattern",
+                ..
+            }
+        ));
+    }
+}
@@ -1,8 +1,14 @@
-use datafusion::arrow::array::StringArray;
+use datafusion::arrow::array::{BooleanArray, Int64Array, ListArray, StringArray};
 use datafusion::arrow::record_batch::RecordBatch;
 use datafusion::prelude::*;
 use datafusion_expr::ScalarUDF;
+use regexp_extract_datafusion::error::RegexpError;
+use regexp_extract_datafusion::like::{Like, LikeMulti};
+use regexp_extract_datafusion::parse_url::ParseUrl;
 use regexp_extract_datafusion::regexp_extract::RegexpExtract;
+use regexp_extract_datafusion::regexp_replace::RegexpReplace;
+use regexp_extract_datafusion::regexp_substr::RegexpSubstr;
+use regexp_extract_datafusion::split::Split;
 use std::sync::Arc;
 
 async fn run_compatibility_test(
@@ -68,3 +74,286 @@ async fn spark_compat_no_capture_group_returns_empty_string() {
     let expected = StringArray::from(vec![Some("")]);
     assert_eq!(result, expected);
 }
+
+#[tokio::test]
+async fn spark_compat_negative_index_raises_regex_group_index_error() {
+    let ctx = SessionContext::new();
+    let udf = ScalarUDF::new_from_impl(RegexpExtract::new());
+    ctx.register_udf(udf.clone());
+
+    let err = ctx
+        .sql("SELECT regexp_extract('a-b', '(a)-(b)', -1)")
+        .await
+        .unwrap()
+        .collect()
+        .await
+        .unwrap_err();
+
+    let regexp_error = RegexpError::find(&err).expect("Expected a RegexpError");
+    assert_eq!(
+        regexp_error.error_class(),
+        "INVALID_PARAMETER_VALUE.REGEX_GROUP_INDEX"
+    );
+    assert_eq!(regexp_error.sql_state(), "22023");
+    assert_eq!(regexp_error.pattern(), Some("(a)-(b)"));
+}
+
+#[tokio::test]
+async fn spark_compat_regexp_replace_uses_java_replacement_syntax() {
+    let ctx = SessionContext::new();
+    ctx.register_udf(ScalarUDF::new_from_impl(RegexpReplace::new()));
+
+    let results = ctx
+        .sql(
+            r"SELECT regexp_replace('2023-12-25', '(\d+)-(\d+)-(\d+)', '$3/$2/$1'),
+                     regexp_replace('a.b.c', '\.', '\$', 4)",
+        )
+        .await
+        .unwrap()
+        .collect()
+        .await
+        .unwrap();
+
+    let batch = &results[0];
+    let column = |i: usize| {
+        batch
+            .column(i)
+            .as_any()
+            .downcast_ref::<StringArray>()
+            .unwrap()
+            .value(0)
+            .to_string()
+    };
+    assert_eq!(column(0), "25/12/2023");
+    assert_eq!(column(1), "a.b$c");
+}
+
+#[tokio::test]
+async fn spark_compat_regexp_substr_is_null_where_regexp_extract_is_empty() {
+    let ctx = SessionContext::new();
+    ctx.register_udf(ScalarUDF::new_from_impl(RegexpExtract::new()));
+    ctx.register_udf(ScalarUDF::new_from_impl(RegexpSubstr::new()));
+
+    let results = ctx
+        .sql(
+            r"SELECT regexp_extract(v, '\d+', 0), regexp_substr(v, '\d+'),
+                     regexp_extract(v, '\d*', 0), regexp_substr(v, '\d*')
+              FROM (VALUES ('id 42'), ('no digits'), (NULL)) AS t(v)",
+        )
+        .await
+        .unwrap()
+        .collect()
+        .await
+        .unwrap();
+
+    let batch = &results[0];
+    let column = |i: usize| {
+        batch
+            .column(i)
+            .as_any()
+            .downcast_ref::<StringArray>()
+            .unwrap()
+            .clone()
+    };
+    assert_eq!(
+        column(0),
+        StringArray::from(vec![Some("42"), Some(""), None])
+    );
+    assert_eq!(column(1), StringArray::from(vec![Some("42"), None, None]));
+    // `\d*` matches the empty string at position 0 of both rows.
+    assert_eq!(column(2), StringArray::from(vec![Some(""), Some(""), None]));
+    assert_eq!(
+        column(3),
+        StringArray::from(vec![None as Option<&str>, None, None])
+    );
+}
+
+#[tokio::test]
+async fn spark_compat_count_differs_between_regexp_extract_and_regexp_substr() {
+    let ctx = SessionContext::new();
+    ctx.register_udf(ScalarUDF::new_from_impl(RegexpExtract::new()));
+    ctx.register_udf(ScalarUDF::new_from_impl(RegexpSubstr::new()));
+
+    let results = ctx
+        .sql(
+            r"SELECT COUNT(regexp_extract(v, 'ERR\d+', 0)), COUNT(regexp_substr(v, 'ERR\d+'))
+              FROM (VALUES ('ERR1 disk'), ('ok'), ('ERR2 net')) AS t(v)",
+        )
+        .await
+        .unwrap()
+        .collect()
+        .await
+        .unwrap();
+
+    let batch = &results[0];
+    let counts = |i: usize| {
+        batch
+            .column(i)
+            .as_any()
+            .downcast_ref::<Int64Array>()
+            .unwrap()
+            .value(0)
+    };
+    assert_eq!(counts(0), 3);
+    assert_eq!(counts(1), 2);
+}
+
+#[tokio::test]
+async fn spark_compat_split_limit_semantics() {
+    let ctx = SessionContext::new();
+    ctx.register_udf(ScalarUDF::new_from_impl(Split::new()));
+
+    let results = ctx
+        .sql(
+            r"SELECT split('oneAtwoBthreeC', '[ABC]'),
+                     split('oneAtwoBthreeC', '[ABC]', 2),
+                     split('oneAtwoBthreeC', '[ABC]', 0),
+                     split('abc', '')",
+        )
+        .await
+        .unwrap()
+        .collect()
+        .await
+        .unwrap();
+
+    let batch = &results[0];
+    let column = |i: usize| -> Vec<String> {
+        let list = batch
+            .column(i)
+            .as_any()
+            .downcast_ref::<ListArray>()
+            .unwrap();
+        let values = list.value(0);
+        values
+            .as_any()
+            .downcast_ref::<StringArray>()
+            .unwrap()
+            .iter()
+            .map(|v| v.unwrap().to_string())
+            .collect()
+    };
+    assert_eq!(column(0), vec!["one", "two", "three", ""]);
+    assert_eq!(column(1), vec!["one", "twoBthreeC"]);
+    assert_eq!(column(2), vec!["one", "two", "three", ""]);
+    assert_eq!(column(3), vec!["a", "b", "c"]);
+}
+
+#[tokio::test]
+async fn spark_compat_like_escape_and_multi_pattern_functions() {
+    let ctx = SessionContext::new();
+    ctx.register_udf(ScalarUDF::new_from_impl(Like::new()));
+    ctx.register_udf(ScalarUDF::new_from_impl(Like::ilike()));
+    for variant in LikeMulti::all_variants() {
+        ctx.register_udf(ScalarUDF::new_from_impl(variant));
+    }
+
+    let results = ctx
+        .sql(
+            r"SELECT like(v, '50/%', '/'),
+                     ilike(v, 'A%'),
+                     NOT like(v, 'a%'),
+                     like_any(v, '%!', '50%'),
+                     not_like_any(v, '%!', '50%'),
+                     like_all(v, '5%', '%%'),
+                     like_any(v, 'zzz', NULL)
+              FROM (VALUES ('50%'), ('abc!'), (NULL)) AS t(v)",
+        )
+        .await
+        .unwrap()
+        .collect()
+        .await
+        .unwrap();
+
+    let batch = &results[0];
+    let column = |i: usize| -> Vec<Option<bool>> {
+        batch
+            .column(i)
+            .as_any()
+            .downcast_ref::<BooleanArray>()
+            .unwrap()
+            .iter()
+            .collect()
+    };
+    assert_eq!(column(0), vec![Some(true), Some(false), None]);
+    assert_eq!(column(1), vec![Some(false), Some(true), None]);
+    assert_eq!(column(2), vec![Some(true), Some(false), None]);
+    assert_eq!(column(3), vec![Some(true), Some(true), None]);
+    assert_eq!(column(4), vec![Some(true), Some(true), None]);
+    assert_eq!(column(5), vec![Some(true), Some(false), None]);
+    assert_eq!(column(6), vec![None, None, None]);
+}
+
+#[tokio::test]
+async fn spark_compat_like_escape_must_precede_wildcard() {
+    let ctx = SessionContext::new();
+    ctx.register_udf(ScalarUDF::new_from_impl(Like::new()));
+
+    let err = ctx
+        .sql(r"SELECT like('abc', '/abc', '/')")
+        .await
+        .unwrap()
+        .collect()
+        .await
+        .unwrap_err();
+
+    let err = RegexpError::find(&err).expect("expected a RegexpError");
+    assert_eq!(err.error_class(), "INVALID_FORMAT.ESC_IN_THE_MIDDLE");
+    assert_eq!(err.sql_state(), "42601");
+}
+
+#[tokio::test]
+async fn spark_compat_parse_url_examples() {
+    let ctx = SessionContext::new();
+    ctx.register_udf(ScalarUDF::new_from_impl(ParseUrl::new()));
+
+    // The examples from Spark's documentation, plus an invalid URL.
+    let results = ctx
+        .sql(
+            r"SELECT parse_url('http://spark.apache.org/path?query=1', 'HOST'),
+                     parse_url('http://spark.apache.org/path?query=1', 'QUERY'),
+                     parse_url('http://spark.apache.org/path?query=1', 'QUERY', 'query'),
+                     parse_url('inva lid://spark.apache.org/path?query=1', 'QUERY')",
+        )
+        .await
+        .unwrap()
+        .collect()
+        .await
+        .unwrap();
+
+    let batch = &results[0];
+    let column = |i: usize| -> Option<String> {
+        batch
+            .column(i)
+            .as_any()
+            .downcast_ref::<StringArray>()
+            .unwrap()
+            .iter()
+            .next()
+            .unwrap()
+            .map(str::to_string)
+    };
+    assert_eq!(column(0).as_deref(), Some("spark.apache.org"));
+    assert_eq!(column(1).as_deref(), Some("query=1"));
+    assert_eq!(column(2).as_deref(), Some("1"));
+    assert_eq!(column(3), None);
+}
+
+#[tokio::test]
+async fn spark_compat_parse_url_ansi_mode_raises_invalid_url() {
+    let ctx = SessionContext::new();
+    ctx.register_udf(ScalarUDF::new_from_impl(
+        ParseUrl::new().with_ansi_mode(true),
+    ));
+
+    let err = ctx
+        .sql(r"SELECT parse_url('inva lid://spark.apache.org/path?query=1', 'QUERY')")
+        .await
+        .unwrap()
+        .collect()
+        .await
+        .unwrap_err();
+
+    let err = RegexpError::find(&err).expect("expected a RegexpError");
+    assert_eq!(err.error_class(), "INVALID_URL");
+    assert_eq!(err.sql_state(), "22P02");
+}
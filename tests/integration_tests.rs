@@ -1,10 +1,28 @@
-use datafusion::arrow::array::StringArray;
+use datafusion::arrow::array::{BooleanArray, Int32Array, StringArray};
+use datafusion::arrow::compute::cast;
 use datafusion::arrow::datatypes::{DataType, Field, Schema};
 use datafusion::arrow::record_batch::RecordBatch;
 use datafusion::datasource::MemTable;
+use datafusion::execution::SessionStateBuilder;
 use datafusion::prelude::*;
 use datafusion_expr::ScalarUDF;
+use datafusion_expr::registry::FunctionRegistry;
+use regexp_extract_datafusion::grok::Grok;
+use regexp_extract_datafusion::redact::Redact;
+use regexp_extract_datafusion::regexp_classify::RegexpClassify;
+use regexp_extract_datafusion::regexp_count::RegexpCount;
+use regexp_extract_datafusion::regexp_explain::RegexpExplain;
 use regexp_extract_datafusion::regexp_extract::RegexpExtract;
+use regexp_extract_datafusion::regexp_extract_all::RegexpExtractAll;
+use regexp_extract_datafusion::regexp_extract_bigquery::BigQueryRegexpExtract;
+use regexp_extract_datafusion::regexp_extract_with_offsets::RegexpExtractWithOffsets;
+use regexp_extract_datafusion::regexp_instr::RegexpInstr;
+use regexp_extract_datafusion::regexp_substr_snowflake::SnowflakeRegexpSubstr;
+use regexp_extract_datafusion::registry::{Dialect, register_all, register_regex_functions};
+use regexp_extract_datafusion::rlike::RLike;
+use regexp_extract_datafusion::sql::sql;
+use regexp_extract_datafusion::str_to_map::StrToMap;
+use std::io::Write;
 use std::sync::Arc;
 
 #[tokio::test]
@@ -108,3 +126,981 @@ async fn test_regexp_extract_dataframe_api() {
     let expected = StringArray::from(vec![Some("10"), Some("20"), Some("30")]);
     assert_eq!(string_array, &expected);
 }
+
+#[tokio::test]
+async fn test_regexp_extract_metrics() {
+    let ctx = SessionContext::new();
+    let udf = ScalarUDF::new_from_impl(RegexpExtract::new());
+    ctx.register_udf(udf.clone());
+
+    let schema = Arc::new(Schema::new(vec![Field::new("text", DataType::Utf8, true)]));
+    let batch = RecordBatch::try_new(
+        schema.clone(),
+        vec![Arc::new(StringArray::from(vec![
+            Some("alpha-10"),
+            Some("no digits"),
+            None,
+        ]))],
+    )
+    .unwrap();
+    let provider = MemTable::try_new(schema, vec![vec![batch.clone()], vec![batch]]).unwrap();
+    ctx.register_table("metrics_table", Arc::new(provider))
+        .unwrap();
+
+    ctx.sql(r"SELECT regexp_extract(text, '(\d+)', 1) FROM metrics_table")
+        .await
+        .unwrap()
+        .collect()
+        .await
+        .unwrap();
+
+    let metrics = udf
+        .inner()
+        .as_any()
+        .downcast_ref::<RegexpExtract>()
+        .expect("Expected a RegexpExtract")
+        .metrics();
+    let snapshot = metrics.snapshot();
+    assert_eq!(snapshot.rows_processed, 4);
+    assert_eq!(snapshot.rows_matched, 2);
+    assert_eq!(snapshot.compilations, 1);
+    assert_eq!(snapshot.cache_misses + snapshot.cache_hits, 2);
+
+    metrics.reset();
+    assert_eq!(metrics.snapshot().rows_processed, 0);
+}
+
+#[tokio::test]
+async fn test_regexp_extract_with_collation() {
+    let ctx = SessionContext::new();
+    ctx.register_udf(ScalarUDF::new_from_impl(RegexpExtract::new()));
+
+    let df = ctx
+        .sql(r"SELECT regexp_extract('Level=WARN', 'level=(\w+)', 1, 'UTF8_LCASE')")
+        .await
+        .unwrap();
+
+    let results = df.collect().await.unwrap();
+    let string_array = results[0]
+        .column(0)
+        .as_any()
+        .downcast_ref::<StringArray>()
+        .expect("Expected a StringArray");
+    assert_eq!(string_array, &StringArray::from(vec!["WARN"]));
+}
+
+#[tokio::test]
+async fn test_regexp_extract_all_with_unnest() {
+    let ctx = SessionContext::new();
+    ctx.register_udf(ScalarUDF::new_from_impl(RegexpExtractAll::new()));
+
+    let schema = Arc::new(Schema::new(vec![Field::new(
+        "message",
+        DataType::Utf8,
+        true,
+    )]));
+    let batch = RecordBatch::try_new(
+        schema.clone(),
+        vec![Arc::new(StringArray::from(vec![
+            Some("see https://a.example/x and http://b.example/y"),
+            Some("no links here"),
+            None,
+            Some("one more: https://c.example/"),
+        ]))],
+    )
+    .unwrap();
+    let provider = MemTable::try_new(schema, vec![vec![batch]]).unwrap();
+    ctx.register_table("messages", Arc::new(provider)).unwrap();
+
+    let df = ctx
+        .sql(
+            r"SELECT unnest(regexp_extract_all(message, '(https?://[^ ]+)', 1)) AS url
+              FROM messages",
+        )
+        .await
+        .unwrap();
+
+    let results = df.collect().await.unwrap();
+    let urls: Vec<String> = results
+        .iter()
+        .flat_map(|batch| {
+            let column = batch
+                .column(0)
+                .as_any()
+                .downcast_ref::<StringArray>()
+                .expect("Expected a StringArray");
+            column
+                .iter()
+                .map(|v| v.unwrap().to_string())
+                .collect::<Vec<_>>()
+        })
+        .collect();
+
+    assert_eq!(
+        urls,
+        vec![
+            "https://a.example/x",
+            "http://b.example/y",
+            "https://c.example/"
+        ]
+    );
+}
+
+#[tokio::test]
+async fn test_regexp_extract_all_key_value_pairs() {
+    let ctx = SessionContext::new();
+    ctx.register_udf(ScalarUDF::new_from_impl(RegexpExtractAll::new()));
+
+    let df = ctx
+        .sql(
+            r"SELECT unnest(regexp_extract_all('k1=v1 k2=v2', '(\w+)=(\w+)', 1)) AS k,
+                     unnest(regexp_extract_all('k1=v1 k2=v2', '(\w+)=(\w+)', 2)) AS v",
+        )
+        .await
+        .unwrap();
+
+    let results = df.collect().await.unwrap();
+    let batch = &results[0];
+    let keys = batch
+        .column(0)
+        .as_any()
+        .downcast_ref::<StringArray>()
+        .unwrap();
+    let values = batch
+        .column(1)
+        .as_any()
+        .downcast_ref::<StringArray>()
+        .unwrap();
+    assert_eq!(keys, &StringArray::from(vec!["k1", "k2"]));
+    assert_eq!(values, &StringArray::from(vec!["v1", "v2"]));
+}
+
+#[tokio::test]
+async fn test_rlike_and_regexp_operators() {
+    let ctx = SessionContext::new();
+    ctx.register_udf(ScalarUDF::new_from_impl(RLike::new()));
+
+    let schema = Arc::new(Schema::new(vec![Field::new("line", DataType::Utf8, true)]));
+    let batch = RecordBatch::try_new(
+        schema.clone(),
+        vec![Arc::new(StringArray::from(vec![
+            Some("ERROR disk full"),
+            Some("INFO started"),
+            None,
+            Some("error: lower case"),
+        ]))],
+    )
+    .unwrap();
+    let provider = MemTable::try_new(schema, vec![vec![batch]]).unwrap();
+    ctx.register_table("logs", Arc::new(provider)).unwrap();
+
+    let df = sql(
+        &ctx,
+        r"SELECT line RLIKE '^ERROR\s',
+                 line NOT RLIKE 'ERROR',
+                 line REGEXP '(?i)^error',
+                 regexp_like(line, 'started$')
+          FROM logs",
+    )
+    .await
+    .unwrap();
+
+    let results = df.collect().await.unwrap();
+    let column = |i: usize| -> Vec<Option<bool>> {
+        results[0]
+            .column(i)
+            .as_any()
+            .downcast_ref::<BooleanArray>()
+            .expect("Expected a BooleanArray")
+            .iter()
+            .collect()
+    };
+    assert_eq!(column(0), vec![Some(true), Some(false), None, Some(false)]);
+    assert_eq!(column(1), vec![Some(false), Some(true), None, Some(true)]);
+    assert_eq!(column(2), vec![Some(true), Some(false), None, Some(true)]);
+    assert_eq!(column(3), vec![Some(false), Some(true), None, Some(false)]);
+}
+
+#[tokio::test]
+async fn test_rlike_in_where_clause() {
+    let ctx = SessionContext::new();
+    ctx.register_udf(ScalarUDF::new_from_impl(RLike::new()));
+
+    let df = sql(
+        &ctx,
+        r"SELECT v FROM (VALUES ('a1'), ('b2'), ('c')) AS t(v) WHERE v RLIKE '\d' ORDER BY v",
+    )
+    .await
+    .unwrap();
+
+    let results = df.collect().await.unwrap();
+    let values = results[0]
+        .column(0)
+        .as_any()
+        .downcast_ref::<StringArray>()
+        .unwrap();
+    assert_eq!(values, &StringArray::from(vec!["a1", "b2"]));
+}
+
+#[tokio::test]
+async fn test_sql_leaves_like_to_datafusion() {
+    // No `like` function is registered, so LIKE must not be rewritten.
+    let ctx = SessionContext::new();
+    ctx.register_udf(ScalarUDF::new_from_impl(RLike::new()));
+
+    let df = sql(&ctx, r"SELECT 'abc' LIKE 'a%', 'abc' RLIKE 'b'")
+        .await
+        .unwrap();
+
+    let results = df.collect().await.unwrap();
+    for i in 0..2 {
+        let value = results[0]
+            .column(i)
+            .as_any()
+            .downcast_ref::<BooleanArray>()
+            .expect("Expected a BooleanArray");
+        assert_eq!(value, &BooleanArray::from(vec![true]));
+    }
+}
+
+#[tokio::test]
+async fn test_regexp_count_per_line() {
+    let ctx = SessionContext::new();
+    ctx.register_udf(ScalarUDF::new_from_impl(RegexpCount::new()));
+
+    let df = ctx
+        .sql(
+            r"SELECT regexp_count(line, 'E\d{3}') AS errors
+              FROM (VALUES ('E001 then E002'), ('clean'), (NULL)) AS t(line)",
+        )
+        .await
+        .unwrap();
+
+    let results = df.collect().await.unwrap();
+    let counts = results[0]
+        .column(0)
+        .as_any()
+        .downcast_ref::<Int32Array>()
+        .expect("Expected an Int32Array");
+    assert_eq!(counts, &Int32Array::from(vec![Some(2), Some(0), None]));
+}
+
+#[tokio::test]
+async fn test_regexp_instr_slices_rest_of_line() {
+    let ctx = SessionContext::new();
+    ctx.register_udf(ScalarUDF::new_from_impl(RegexpInstr::new()));
+    ctx.register_udf(ScalarUDF::new_from_impl(RegexpExtractWithOffsets::new()));
+
+    let df = ctx
+        .sql(
+            r"SELECT substr(line, regexp_instr(line, '-')) AS rest,
+                     regexp_extract_with_offsets(line, '世界')['end'] AS end_pos
+              FROM (VALUES ('你好-世界')) AS t(line)",
+        )
+        .await
+        .unwrap();
+
+    let results = df.collect().await.unwrap();
+    // substr may hand back a view array; compare as plain Utf8.
+    let rest = cast(results[0].column(0), &DataType::Utf8).unwrap();
+    let rest = rest
+        .as_any()
+        .downcast_ref::<StringArray>()
+        .expect("Expected a StringArray");
+    let end_pos = results[0]
+        .column(1)
+        .as_any()
+        .downcast_ref::<Int32Array>()
+        .expect("Expected an Int32Array");
+    assert_eq!(rest, &StringArray::from(vec!["-世界"]));
+    assert_eq!(end_pos, &Int32Array::from(vec![6]));
+}
+
+#[tokio::test]
+async fn test_str_to_map_query_string() {
+    let ctx = SessionContext::new();
+    ctx.register_udf(ScalarUDF::new_from_impl(StrToMap::new()));
+
+    let df = ctx
+        .sql(r"SELECT str_to_map('user=alice&lang=en', '&', '=')['lang'] AS lang")
+        .await
+        .unwrap();
+
+    let results = df.collect().await.unwrap();
+    let lang = results[0]
+        .column(0)
+        .as_any()
+        .downcast_ref::<StringArray>()
+        .expect("Expected a StringArray");
+    assert_eq!(lang, &StringArray::from(vec!["en"]));
+}
+
+#[tokio::test]
+async fn test_regexp_classify_with_literal_and_lookup_rules() {
+    let ctx = SessionContext::new();
+    ctx.register_udf(ScalarUDF::new_from_impl(RegexpClassify::new()));
+    ctx.register_udf(ScalarUDF::new_from_impl(RegexpClassify::all()));
+    ctx.sql(
+        "CREATE TABLE logs AS VALUES \
+         (1, 'java.lang.OutOfMemoryError'), (2, 'read timed out'), (3, 'ok')",
+    )
+    .await
+    .unwrap();
+    ctx.sql(
+        r"CREATE TABLE rules (label VARCHAR, pattern VARCHAR) AS VALUES
+         ('TIMEOUT', 'timed? ?out'), ('OOM', 'OutOfMemory'), ('JAVA', 'java\.\w+')",
+    )
+    .await
+    .unwrap();
+
+    let literal = ctx
+        .sql(
+            "SELECT regexp_classify(column2, map(['TIMEOUT', 'OOM'], ['timed? ?out', 'OutOfMemory'])) \
+             FROM logs ORDER BY column1",
+        )
+        .await
+        .unwrap()
+        .collect()
+        .await
+        .unwrap();
+    let labels = literal[0]
+        .column(0)
+        .as_any()
+        .downcast_ref::<StringArray>()
+        .expect("Expected a StringArray");
+    assert_eq!(
+        labels,
+        &StringArray::from(vec![Some("OOM"), Some("TIMEOUT"), None])
+    );
+
+    let lookup = ctx
+        .sql(
+            "SELECT array_to_string(regexp_classify_all(column2, \
+               (SELECT array_agg(named_struct('label', label, 'pattern', pattern)) FROM rules)), '|') \
+             FROM logs ORDER BY column1",
+        )
+        .await
+        .unwrap()
+        .collect()
+        .await
+        .unwrap();
+    let labels = cast(lookup[0].column(0), &DataType::Utf8).unwrap();
+    let labels = labels
+        .as_any()
+        .downcast_ref::<StringArray>()
+        .expect("Expected a StringArray");
+    assert_eq!(labels, &StringArray::from(vec!["OOM|JAVA", "TIMEOUT", ""]));
+}
+
+#[tokio::test]
+async fn test_redact_log_line() {
+    let ctx = SessionContext::new();
+    ctx.register_udf(ScalarUDF::new_from_impl(Redact::new()));
+
+    let df = ctx
+        .sql(
+            "SELECT redact('10.0.0.7 alice@example.com paid 4111-1111-1111-1111', \
+             'ipv4, email, credit_card') AS line",
+        )
+        .await
+        .unwrap();
+
+    let results = df.collect().await.unwrap();
+    let line = results[0]
+        .column(0)
+        .as_any()
+        .downcast_ref::<StringArray>()
+        .expect("Expected a StringArray");
+    assert_eq!(
+        line,
+        &StringArray::from(vec![
+            "[REDACTED:ipv4] [REDACTED:email] paid ****-****-****-1111"
+        ])
+    );
+}
+
+#[tokio::test]
+async fn test_grok_http_log() {
+    let ctx = SessionContext::new();
+    ctx.register_udf(ScalarUDF::new_from_impl(Grok::new()));
+
+    let schema = Arc::new(Schema::new(vec![Field::new(
+        "http_log",
+        DataType::Utf8,
+        false,
+    )]));
+    let data = RecordBatch::try_new(
+        schema.clone(),
+        vec![Arc::new(StringArray::from(vec![
+            "1.2.3.4 - GET /index.html 200",
+            "5.6.7.8 - POST /api/data 404",
+            "No match here",
+        ]))],
+    )
+    .unwrap();
+    let provider = MemTable::try_new(schema, vec![vec![data]]).unwrap();
+    ctx.register_table("logs", Arc::new(provider)).unwrap();
+
+    let df = ctx
+        .sql(
+            "SELECT g['path'] AS path, g['status'] AS status FROM (\
+               SELECT grok(http_log, '%{IPORHOST:client} - %{WORD:method} \
+               %{URIPATHPARAM:path} %{NUMBER:status:int}') AS g FROM logs) \
+             WHERE g['status'] >= 400",
+        )
+        .await
+        .unwrap();
+
+    let results = df.collect().await.unwrap();
+    let path = results[0]
+        .column(0)
+        .as_any()
+        .downcast_ref::<StringArray>()
+        .expect("Expected a StringArray");
+    assert_eq!(path, &StringArray::from(vec!["/api/data"]));
+    assert_eq!(results[0].column(1).data_type(), &DataType::Int64);
+}
+
+#[tokio::test]
+async fn test_regexp_explain_group_count() {
+    let ctx = SessionContext::new();
+    ctx.register_udf(ScalarUDF::new_from_impl(RegexpExplain::new()));
+
+    let df = ctx
+        .sql(
+            r"SELECT e['group_count'] AS groups, e['literal_prefix'] AS prefix
+              FROM (SELECT regexp_explain(' - ([A-Z]+) ') AS e)",
+        )
+        .await
+        .unwrap();
+
+    let results = df.collect().await.unwrap();
+    let groups = results[0]
+        .column(0)
+        .as_any()
+        .downcast_ref::<Int32Array>()
+        .expect("Expected an Int32Array");
+    let prefix = results[0]
+        .column(1)
+        .as_any()
+        .downcast_ref::<StringArray>()
+        .expect("Expected a StringArray");
+    assert_eq!(groups, &Int32Array::from(vec![1]));
+    assert_eq!(prefix, &StringArray::from(vec![" - "]));
+}
+
+#[tokio::test]
+async fn test_regexp_explain_reports_the_session_dialect() {
+    let ctx = SessionContext::new();
+    register_all(&ctx).unwrap();
+    ctx.sql("SET regexp.dialect = 'rust'")
+        .await
+        .unwrap()
+        .collect()
+        .await
+        .unwrap();
+
+    let df = ctx
+        .sql(
+            r"SELECT regexp_extract('aé1', '\w+', 0) AS extracted,
+                     regexp_explain('\w+')['dialect'] AS dialect",
+        )
+        .await
+        .unwrap();
+
+    let results = df.collect().await.unwrap();
+    let extracted = results[0]
+        .column(0)
+        .as_any()
+        .downcast_ref::<StringArray>()
+        .expect("Expected a StringArray");
+    let dialect = results[0]
+        .column(1)
+        .as_any()
+        .downcast_ref::<StringArray>()
+        .expect("Expected a StringArray");
+    assert_eq!(extracted, &StringArray::from(vec!["aé1"]));
+    assert_eq!(dialect, &StringArray::from(vec!["rust"]));
+}
+
+#[tokio::test]
+async fn test_bigquery_regexp_extract_occurrence() {
+    let ctx = SessionContext::new();
+    ctx.register_udf(ScalarUDF::new_from_impl(BigQueryRegexpExtract::new()));
+
+    let df = ctx
+        .sql(
+            r"SELECT bigquery_regexp_extract(path, '/([a-z]+)', 1, 2) AS second
+              FROM (VALUES ('/api/users/42'), ('/health')) AS t(path)",
+        )
+        .await
+        .unwrap();
+
+    let results = df.collect().await.unwrap();
+    let second = results[0]
+        .column(0)
+        .as_any()
+        .downcast_ref::<StringArray>()
+        .expect("Expected a StringArray");
+    assert_eq!(second, &StringArray::from(vec![Some("users"), None]));
+}
+
+#[tokio::test]
+async fn test_snowflake_regexp_substr_parameters() {
+    let ctx = SessionContext::new();
+    ctx.register_udf(ScalarUDF::new_from_impl(SnowflakeRegexpSubstr::new()));
+
+    let df = ctx
+        .sql(
+            r"SELECT snowflake_regexp_substr(line, 'amount=(\d+)', 1, 1, 'ie') AS amount
+              FROM (VALUES ('ID 7 AMOUNT=120'), ('id 8 amount=95'), ('id 9')) AS t(line)",
+        )
+        .await
+        .unwrap();
+
+    let results = df.collect().await.unwrap();
+    let amount = results[0]
+        .column(0)
+        .as_any()
+        .downcast_ref::<StringArray>()
+        .expect("Expected a StringArray");
+    assert_eq!(
+        amount,
+        &StringArray::from(vec![Some("120"), Some("95"), None])
+    );
+}
+
+#[tokio::test]
+async fn test_register_all_installs_every_function() {
+    let ctx = SessionContext::new();
+    register_all(&ctx).unwrap();
+
+    let df = sql(
+        &ctx,
+        r"SELECT regexp_extract('a-1', '([a-z])-(\d)', 2) AS digit,
+                 'Abc' RLIKE '^A' AS matched,
+                 trino_regexp_extract('none', '(\d)', 1) AS missing",
+    )
+    .await
+    .unwrap();
+
+    let results = df.collect().await.unwrap();
+    let digit = results[0]
+        .column(0)
+        .as_any()
+        .downcast_ref::<StringArray>()
+        .expect("Expected a StringArray");
+    let matched = results[0]
+        .column(1)
+        .as_any()
+        .downcast_ref::<BooleanArray>()
+        .expect("Expected a BooleanArray");
+    assert_eq!(digit, &StringArray::from(vec!["1"]));
+    assert_eq!(matched, &BooleanArray::from(vec![true]));
+    assert!(results[0].column(2).is_null(0));
+}
+
+#[tokio::test]
+async fn test_register_regex_functions_with_dialect_on_state_builder() {
+    let mut builder = SessionStateBuilder::new().with_default_features();
+    register_regex_functions(&mut builder, Dialect::Trino).unwrap();
+    let ctx = SessionContext::new_with_state(builder.build());
+
+    // Under the Trino dialect `regexp_extract` returns NULL, not "", without a match.
+    let df = ctx
+        .sql(r"SELECT regexp_extract('none', '(\d)', 1) AS missing")
+        .await
+        .unwrap();
+
+    let results = df.collect().await.unwrap();
+    assert!(results[0].column(0).is_null(0));
+}
+
+#[test]
+fn test_register_all_twice_on_a_registry_adds_one_rewrite() {
+    let mut state = SessionStateBuilder::new().with_default_features().build();
+    for _ in 0..2 {
+        let registry: &mut dyn FunctionRegistry = &mut state;
+        register_all(registry).unwrap();
+    }
+
+    let rewrites = state
+        .analyzer()
+        .function_rewrites
+        .iter()
+        .filter(|rewrite| rewrite.name() == "regexp_config")
+        .count();
+    assert_eq!(rewrites, 1);
+}
+
+#[tokio::test]
+async fn test_set_regexp_config_applies_to_following_queries() {
+    let ctx = SessionContext::new();
+    register_all(&ctx).unwrap();
+    let query = r"SELECT regexp_extract('a-b', '(a)-(b)', -1) AS value";
+
+    let err = ctx.sql(query).await.unwrap().collect().await.unwrap_err();
+    assert!(err.to_string().contains("REGEX_GROUP_INDEX"));
+
+    ctx.sql("SET regexp.legacy_group_index = true")
+        .await
+        .unwrap()
+        .collect()
+        .await
+        .unwrap();
+
+    let results = ctx.sql(query).await.unwrap().collect().await.unwrap();
+    let value = results[0]
+        .column(0)
+        .as_any()
+        .downcast_ref::<StringArray>()
+        .expect("Expected a StringArray");
+    assert_eq!(value, &StringArray::from(vec![""]));
+}
+
+#[tokio::test]
+async fn test_set_regexp_dialect_applies_to_every_function() {
+    let ctx = SessionContext::new();
+    register_all(&ctx).unwrap();
+    let query = r"SELECT regexp_replace('aé1', '\w+', 'X') AS replaced,
+                         regexp_count('aé1', '\w+') AS count";
+
+    // Java's \w is ASCII-only, so `é` splits the input into two words.
+    let results = ctx.sql(query).await.unwrap().collect().await.unwrap();
+    let replaced = results[0]
+        .column(0)
+        .as_any()
+        .downcast_ref::<StringArray>()
+        .expect("Expected a StringArray");
+    let count = results[0]
+        .column(1)
+        .as_any()
+        .downcast_ref::<Int32Array>()
+        .expect("Expected an Int32Array");
+    assert_eq!(replaced.value(0), "XéX");
+    assert_eq!(count.value(0), 2);
+
+    ctx.sql("SET regexp.dialect = 'rust'")
+        .await
+        .unwrap()
+        .collect()
+        .await
+        .unwrap();
+
+    let results = ctx.sql(query).await.unwrap().collect().await.unwrap();
+    let replaced = results[0]
+        .column(0)
+        .as_any()
+        .downcast_ref::<StringArray>()
+        .expect("Expected a StringArray");
+    let count = results[0]
+        .column(1)
+        .as_any()
+        .downcast_ref::<Int32Array>()
+        .expect("Expected an Int32Array");
+    assert_eq!(replaced.value(0), "X");
+    assert_eq!(count.value(0), 1);
+}
+
+#[tokio::test]
+async fn test_set_regexp_collation_is_the_default_collation() {
+    let ctx = SessionContext::new();
+    register_all(&ctx).unwrap();
+
+    let err = ctx.sql("SET regexp.collation = 'NOPE'").await.unwrap_err();
+    assert!(err.to_string().contains("Unknown regexp.collation 'NOPE'"));
+
+    ctx.sql("SET regexp.collation = 'UTF8_LCASE'")
+        .await
+        .unwrap()
+        .collect()
+        .await
+        .unwrap();
+
+    // An explicit collation argument still wins over the session's.
+    let df = ctx
+        .sql(
+            r"SELECT regexp_extract('ABC', 'b', 0) AS extracted,
+                     regexp_extract('ABC', 'b', 0, 'UTF8_BINARY') AS binary,
+                     rlike('ABC', 'b') AS matched",
+        )
+        .await
+        .unwrap();
+
+    let results = df.collect().await.unwrap();
+    let extracted = results[0]
+        .column(0)
+        .as_any()
+        .downcast_ref::<StringArray>()
+        .expect("Expected a StringArray");
+    let binary = results[0]
+        .column(1)
+        .as_any()
+        .downcast_ref::<StringArray>()
+        .expect("Expected a StringArray");
+    let matched = results[0]
+        .column(2)
+        .as_any()
+        .downcast_ref::<BooleanArray>()
+        .expect("Expected a BooleanArray");
+    assert_eq!(extracted, &StringArray::from(vec!["B"]));
+    assert_eq!(binary, &StringArray::from(vec![""]));
+    assert_eq!(matched, &BooleanArray::from(vec![true]));
+}
+
+#[tokio::test]
+async fn test_create_external_table_stored_as_regex() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("access.log");
+    std::fs::write(
+        &path,
+        "10.0.0.1 GET /index.html 200\n\
+         not a log line\n\
+         10.0.0.2 POST /login 302\n",
+    )
+    .unwrap();
+
+    let ctx = SessionContext::new();
+    register_all(&ctx).unwrap();
+    ctx.sql(&format!(
+        r"CREATE EXTERNAL TABLE access STORED AS REGEX LOCATION '{}'
+          OPTIONS ('input.regex' '(?<host>\S+) (?<method>[A-Z]+) (\S+) (?<status>\d+)',
+                   'mode' 'corrupt_record')",
+        path.display()
+    ))
+    .await
+    .unwrap();
+
+    let results = ctx
+        .sql("SELECT host, _c2, status, _corrupt_record FROM access")
+        .await
+        .unwrap()
+        .collect()
+        .await
+        .unwrap();
+    let expected = [
+        "+----------+-------------+--------+-----------------+",
+        "| host     | _c2         | status | _corrupt_record |",
+        "+----------+-------------+--------+-----------------+",
+        "| 10.0.0.1 | /index.html | 200    |                 |",
+        "|          |             |        | not a log line  |",
+        "| 10.0.0.2 | /login      | 302    |                 |",
+        "+----------+-------------+--------+-----------------+",
+    ];
+    datafusion::assert_batches_eq!(expected, &results);
+}
+
+#[tokio::test]
+async fn test_regex_table_gzip_with_declared_columns() {
+    let dir = tempfile::tempdir().unwrap();
+    let file = std::fs::File::create(dir.path().join("part-0.log.gz")).unwrap();
+    let mut encoder = flate2::write::GzEncoder::new(file, flate2::Compression::default());
+    encoder
+        .write_all(b"level=INFO took=12\nlevel=warn took=250\nlevel=debug\n")
+        .unwrap();
+    encoder.finish().unwrap();
+
+    let ctx = SessionContext::new();
+    register_all(&ctx).unwrap();
+    ctx.sql(&format!(
+        r"CREATE EXTERNAL TABLE events (level VARCHAR, took INT) STORED AS REGEX
+          LOCATION '{}/'
+          OPTIONS ('input.regex' 'level=(info|warn) took=(\d+)',
+                   'input.regex.case.insensitive' 'true',
+                   'mode' 'dropmalformed',
+                   'compression' 'gzip')",
+        dir.path().display()
+    ))
+    .await
+    .unwrap();
+
+    let results = ctx
+        .sql("SELECT level, took FROM events WHERE took > 100")
+        .await
+        .unwrap()
+        .collect()
+        .await
+        .unwrap();
+    let expected = [
+        "+-------+------+",
+        "| level | took |",
+        "+-------+------+",
+        "| warn  | 250  |",
+        "+-------+------+",
+    ];
+    datafusion::assert_batches_eq!(expected, &results);
+
+    let results = ctx
+        .sql("SELECT count(*) AS n FROM events")
+        .await
+        .unwrap()
+        .collect()
+        .await
+        .unwrap();
+    let expected = ["+---+", "| n |", "+---+", "| 2 |", "+---+"];
+    datafusion::assert_batches_eq!(expected, &results);
+}
+
+#[tokio::test]
+async fn test_create_external_table_stored_as_access_log() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("access.log");
+    std::fs::write(
+        &path,
+        r#"127.0.0.1 - frank [10/Oct/2000:13:55:36 -0700] "GET /apache_pb.gif HTTP/1.0" 200 2326 "http://example.com/" "Mozilla/4.08 \"en\""
+10.0.0.9 - - [10/Oct/2000:14:01:02 -0700] "POST /login HTTP/1.1" 503 - "-" "curl/8.0"
+"#,
+    )
+    .unwrap();
+
+    let ctx = SessionContext::new();
+    register_all(&ctx).unwrap();
+    ctx.sql(&format!(
+        "CREATE EXTERNAL TABLE access STORED AS ACCESS_LOG LOCATION '{}'
+         OPTIONS ('log_format' 'combined')",
+        path.display()
+    ))
+    .await
+    .unwrap();
+
+    let results = ctx
+        .sql(
+            "SELECT remote_user, time_local, status, body_bytes_sent, http_user_agent
+             FROM access WHERE status >= 200 ORDER BY time_local",
+        )
+        .await
+        .unwrap()
+        .collect()
+        .await
+        .unwrap();
+    let expected = [
+        "+-------------+----------------------+--------+-----------------+-------------------+",
+        "| remote_user | time_local           | status | body_bytes_sent | http_user_agent   |",
+        "+-------------+----------------------+--------+-----------------+-------------------+",
+        "| frank       | 2000-10-10T20:55:36Z | 200    | 2326            | Mozilla/4.08 \"en\" |",
+        "|             | 2000-10-10T21:01:02Z | 503    |                 | curl/8.0          |",
+        "+-------------+----------------------+--------+-----------------+-------------------+",
+    ];
+    datafusion::assert_batches_eq!(expected, &results);
+}
+
+#[tokio::test]
+async fn test_access_log_custom_nginx_format() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("nginx.log");
+    std::fs::write(
+        &path,
+        "10.1.1.1 GET / 0.012\n\
+         10.1.1.2 GET /slow 1.5\n\
+         10.1.1.3 GET /bad fast\n",
+    )
+    .unwrap();
+
+    let ctx = SessionContext::new();
+    register_all(&ctx).unwrap();
+    ctx.sql(&format!(
+        "CREATE EXTERNAL TABLE timings STORED AS ACCESS_LOG LOCATION '{}'
+         OPTIONS ('log_format' '$remote_addr $request_method $uri $request_time',
+                  'mode' 'dropmalformed')",
+        path.display()
+    ))
+    .await
+    .unwrap();
+
+    let results = ctx
+        .sql("SELECT uri, request_time FROM timings WHERE request_time > 1")
+        .await
+        .unwrap()
+        .collect()
+        .await
+        .unwrap();
+    let expected = [
+        "+-------+--------------+",
+        "| uri   | request_time |",
+        "+-------+--------------+",
+        "| /slow | 1.5          |",
+        "+-------+--------------+",
+    ];
+    datafusion::assert_batches_eq!(expected, &results);
+
+    // The line with `fast` is dropped even when `request_time` is not read.
+    let results = ctx
+        .sql("SELECT count(*) AS n FROM timings")
+        .await
+        .unwrap()
+        .collect()
+        .await
+        .unwrap();
+    let expected = ["+---+", "| n |", "+---+", "| 2 |", "+---+"];
+    datafusion::assert_batches_eq!(expected, &results);
+}
+
+#[tokio::test]
+async fn test_multiline_records_across_file_splits() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("app.log");
+    let mut log = String::new();
+    for i in 0..50 {
+        log.push_str(&format!(
+            "2024-05-01 12:00:{:02} ERROR request {i} failed\n",
+            i % 60
+        ));
+        log.push_str("java.lang.IllegalStateException: boom\n");
+        for frame in 0..i % 4 {
+            log.push_str(&format!(
+                "\tat com.example.Handler.step{frame}(Handler.java:{frame})\n"
+            ));
+        }
+    }
+    std::fs::write(&path, log).unwrap();
+
+    // Small files are split too, so that records cross split boundaries.
+    let config = SessionConfig::new()
+        .with_target_partitions(8)
+        .with_repartition_file_min_size(64);
+    let ctx = SessionContext::new_with_config(config);
+    register_all(&ctx).unwrap();
+    ctx.sql(&format!(
+        r"CREATE EXTERNAL TABLE errors STORED AS REGEX LOCATION '{}'
+          OPTIONS ('input.regex' '(?s)(?<time>\S+ \S+) (?<level>\w+) request (?<id>\d+) failed\n(?<trace>.*)',
+                   'start_pattern' '^\d{{4}}-\d{{2}}-\d{{2}} ',
+                   'mode' 'failfast')",
+        path.display()
+    ))
+    .await
+    .unwrap();
+
+    let plan = ctx
+        .sql("EXPLAIN SELECT * FROM errors")
+        .await
+        .unwrap()
+        .collect()
+        .await
+        .unwrap();
+    let plan = datafusion::arrow::util::pretty::pretty_format_batches(&plan)
+        .unwrap()
+        .to_string();
+    assert!(plan.contains("file_groups={8 groups"), "{plan}");
+
+    let results = ctx
+        .sql(
+            r"SELECT count(*) AS n, count(DISTINCT id) AS ids,
+                     sum(length(trace) - length(replace(trace, chr(10), ''))) AS newlines
+              FROM errors",
+        )
+        .await
+        .unwrap()
+        .collect()
+        .await
+        .unwrap();
+    // 50 records, each with its exception line and 0 to 3 frames, which
+    // add 73 lines to the traces.
+    let expected = [
+        "+----+-----+----------+",
+        "| n  | ids | newlines |",
+        "+----+-----+----------+",
+        "| 50 | 50  | 73       |",
+        "+----+-----+----------+",
+    ];
+    datafusion::assert_batches_eq!(expected, &results);
+}
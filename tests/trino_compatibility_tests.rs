@@ -0,0 +1,117 @@
+use datafusion::arrow::array::StringArray;
+use datafusion::arrow::record_batch::RecordBatch;
+use datafusion::prelude::*;
+use datafusion_expr::ScalarUDF;
+use regexp_extract_datafusion::error::RegexpError;
+use regexp_extract_datafusion::regexp_extract_trino::TrinoRegexpExtract;
+use std::sync::Arc;
+
+async fn run_compatibility_test(
+    input: Vec<Option<&str>>,
+    pattern: &str,
+    group: Option<i64>,
+) -> StringArray {
+    let ctx = SessionContext::new();
+    let udf = ScalarUDF::new_from_impl(TrinoRegexpExtract::new());
+    ctx.register_udf(udf.clone());
+
+    let schema =
+        datafusion::arrow::datatypes::Schema::new(vec![datafusion::arrow::datatypes::Field::new(
+            "text",
+            datafusion::arrow::datatypes::DataType::Utf8,
+            true,
+        )]);
+
+    let batch =
+        RecordBatch::try_new(Arc::new(schema), vec![Arc::new(StringArray::from(input))]).unwrap();
+
+    let df = ctx.read_batch(batch).unwrap();
+
+    let mut args = vec![col("text"), lit(pattern)];
+    args.extend(group.map(lit));
+    let df = df.select(vec![udf.call(args)]).unwrap();
+
+    let results = df.collect().await.unwrap();
+    let batch = &results[0];
+    let column = batch.column(0);
+
+    column
+        .as_any()
+        .downcast_ref::<StringArray>()
+        .unwrap()
+        .clone()
+}
+
+async fn run_error_test(query: &str) -> RegexpError {
+    let ctx = SessionContext::new();
+    ctx.register_udf(ScalarUDF::new_from_impl(TrinoRegexpExtract::new()));
+
+    let err = ctx.sql(query).await.unwrap().collect().await.unwrap_err();
+
+    RegexpError::find(&err)
+        .expect("Expected a RegexpError")
+        .clone()
+}
+
+#[tokio::test]
+async fn trino_compat_documentation_examples() {
+    let result = run_compatibility_test(vec![Some("1a 2b 14m")], r"\d+", None).await;
+    assert_eq!(result, StringArray::from(vec![Some("1")]));
+
+    let result = run_compatibility_test(vec![Some("1a 2b 14m")], r"(\d+)([a-z]+)", Some(2)).await;
+    assert_eq!(result, StringArray::from(vec![Some("a")]));
+}
+
+#[tokio::test]
+async fn trino_compat_no_match_returns_null() {
+    let result = run_compatibility_test(vec![Some("abc")], r"(\d+)", Some(1)).await;
+    let expected = StringArray::from(vec![None as Option<&str>]);
+    assert_eq!(result, expected);
+}
+
+#[tokio::test]
+async fn trino_compat_null_input_propagates_null() {
+    let result = run_compatibility_test(vec![None], r"(\w+)", Some(1)).await;
+    let expected = StringArray::from(vec![None as Option<&str>]);
+    assert_eq!(result, expected);
+}
+
+#[tokio::test]
+async fn trino_compat_group_defaults_to_whole_match() {
+    let result = run_compatibility_test(vec![Some("key=value")], r"(\w+)=(\w+)", None).await;
+    let expected = StringArray::from(vec![Some("key=value")]);
+    assert_eq!(result, expected);
+}
+
+#[tokio::test]
+async fn trino_compat_unmatched_optional_group_returns_null() {
+    let result = run_compatibility_test(vec![Some("b")], r"(a)|(b)", Some(1)).await;
+    let expected = StringArray::from(vec![None as Option<&str>]);
+    assert_eq!(result, expected);
+}
+
+#[tokio::test]
+async fn trino_compat_index_out_of_bounds_raises_error() {
+    let err = run_error_test("SELECT trino_regexp_extract('a-b', '(a)-(b)', 3)").await;
+    assert_eq!(
+        err.error_class(),
+        "INVALID_PARAMETER_VALUE.REGEX_GROUP_INDEX"
+    );
+    assert_eq!(err.pattern(), Some("(a)-(b)"));
+}
+
+#[tokio::test]
+async fn trino_compat_no_capture_group_raises_error() {
+    let err = run_error_test("SELECT trino_regexp_extract('abc', 'a.c', 1)").await;
+    assert!(err.to_string().contains("between 0 and 0, but got 1"));
+}
+
+#[tokio::test]
+async fn trino_compat_negative_index_raises_error() {
+    let err = run_error_test("SELECT trino_regexp_extract('a-b', '(a)-(b)', -1)").await;
+    assert_eq!(
+        err.error_class(),
+        "INVALID_PARAMETER_VALUE.REGEX_GROUP_INDEX"
+    );
+    assert_eq!(err.sql_state(), "22023");
+}